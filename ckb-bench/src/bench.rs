@@ -1,16 +1,17 @@
-use ckb_testkit::ckb_types::core::{EpochNumberWithFraction, TransactionBuilder, TransactionView};
+use ckb_testkit::ckb_types::core::{EpochNumberWithFraction, TransactionView};
 use ckb_testkit::ckb_types::packed::{CellDep, CellOutput};
 use ckb_testkit::ckb_types::{
     core::cell::CellMeta,
-    packed::{Byte32, CellInput, OutPoint},
+    packed::{Byte32, OutPoint},
     prelude::*,
 };
 use ckb_testkit::util::since_from_absolute_epoch_number_with_fraction;
-use ckb_testkit::{Node, User};
+use ckb_testkit::{Node, TxBuilder, User};
 use crossbeam_channel::{Receiver, Sender};
 use lru::LruCache;
 use std::collections::HashMap;
-use std::time::Instant;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 
 pub struct LiveCellProducer {
     users: Vec<User>,
@@ -37,6 +38,13 @@ impl LiveCellProducer {
                 .map(|node| node.get_tip_block_number())
                 .min()
                 .unwrap();
+            // Let the indexer worker catch up rather than syncing it block by block here
+            if let Some(metrics) = self.nodes[0].indexer_metrics() {
+                if metrics.indexed_number < min_tip_number {
+                    sleep(Duration::from_millis(10));
+                    continue;
+                }
+            }
             for user in self.users.iter() {
                 let live_cells = user
                     .get_spendable_single_secp256k1_cells(&self.nodes[0])
@@ -162,64 +170,54 @@ impl TransactionProducer {
                 } else {
                     0
                 };
-                let inputs = live_cells
-                    .values()
-                    .map(|cell| {
-                        CellInput::new_builder()
-                            .previous_output(cell.out_point.clone())
-                            .since(since.pack())
-                            .build()
-                    })
-                    .collect::<Vec<_>>();
-                let outputs = live_cells
-                    .values()
-                    .map(|cell| {
-                        // use tx_index as random number
-                        let tx_index = cell.transaction_info.as_ref().unwrap().index;
-                        let user = self.users.get(&lock_hash).expect("should be ok");
-                        match tx_index % 3 {
-                            0 => CellOutput::new_builder()
-                                .capacity((cell.capacity().as_u64() - 1000).pack())
-                                .lock(user.single_secp256k1_lock_script_via_data())
-                                .build(),
-                            1 => CellOutput::new_builder()
-                                .capacity((cell.capacity().as_u64() - 1000).pack())
-                                .lock(user.single_secp256k1_lock_script_via_type())
-                                .build(),
-                            2 => {
-                                if enabled_data1_script {
-                                    CellOutput::new_builder()
-                                        .capacity((cell.capacity().as_u64() - 1000).pack())
-                                        .lock(user.single_secp256k1_lock_script_via_data1())
-                                        .build()
-                                } else {
-                                    CellOutput::new_builder()
-                                        .capacity((cell.capacity().as_u64() - 1000).pack())
-                                        .lock(user.single_secp256k1_lock_script_via_data())
-                                        .build()
-                                }
+                let cells = live_cells.values().collect::<Vec<_>>();
+                let payer = self
+                    .users
+                    .get(&cells[0].cell_output.calc_lock_hash())
+                    .expect("should be ok");
+                let mut signers = vec![payer.lock_script()];
+                let builder = cells.iter().fold(TxBuilder::new(payer), |builder, cell| {
+                    // use tx_index as random number
+                    let tx_index = cell.transaction_info.as_ref().unwrap().index;
+                    let user = self
+                        .users
+                        .get(&cell.cell_output.calc_lock_hash())
+                        .expect("should be ok");
+                    let lock = match tx_index % 3 {
+                        0 => user.single_secp256k1_lock_script_via_data(),
+                        1 => user.single_secp256k1_lock_script_via_type(),
+                        2 => {
+                            if enabled_data1_script {
+                                user.single_secp256k1_lock_script_via_data1()
+                            } else {
+                                user.single_secp256k1_lock_script_via_data()
                             }
-                            _ => unreachable!(),
                         }
-                    })
-                    .collect::<Vec<_>>();
-                let outputs_data = live_cells.values().map(|_| Default::default());
-                let raw_tx = TransactionBuilder::default()
-                    .inputs(inputs)
-                    .outputs(outputs)
-                    .outputs_data(outputs_data)
-                    .cell_deps(self.cell_deps.clone())
-                    .build();
-                // NOTE: We know the transaction's inputs and outputs are paired by index, so this
-                // signed way is okay.
-                let witnesses = live_cells.values().map(|cell| {
-                    let lock_hash = cell.cell_output.calc_lock_hash();
-                    let user = self.users.get(&lock_hash).expect("should be ok");
-                    user.single_secp256k1_signed_witness(&raw_tx)
-                        .as_bytes()
-                        .pack()
+                        _ => unreachable!(),
+                    };
+                    let output = CellOutput::new_builder()
+                        .capacity((cell.capacity().as_u64() - 1000).pack())
+                        .lock(lock)
+                        .build();
+                    // A user signs all the inputs locked by it at once
+                    let builder = if signers.contains(&user.lock_script()) {
+                        builder
+                    } else {
+                        signers.push(user.lock_script());
+                        builder.signer(user)
+                    };
+                    builder
+                        .input((*cell).clone(), since)
+                        .output(output, Default::default())
                 });
-                let signed_tx = raw_tx.as_advanced_builder().witnesses(witnesses).build();
+                let signed_tx = self
+                    .cell_deps
+                    .iter()
+                    .fold(builder, |builder, cell_dep| {
+                        builder.cell_dep(cell_dep.clone())
+                    })
+                    .build()
+                    .expect("build transaction");
 
                 if transaction_sender.send(signed_tx).is_err() {
                     // SendError occurs, the corresponding transaction receiver is dead
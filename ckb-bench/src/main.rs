@@ -13,7 +13,7 @@ use crate::utils::maybe_retry_send_transaction;
 use crate::watcher::Watcher;
 use ckb_testkit::ckb_crypto::secp::Privkey;
 use ckb_testkit::ckb_types::{core::BlockNumber, packed::Byte32, prelude::*, H256};
-use ckb_testkit::{Node, Nodes, User};
+use ckb_testkit::{IndexerRpc, Node, Nodes, User, DEFAULT_INDEXER_POLL_INTERVAL};
 use clap::{value_t_or_exit, values_t_or_exit, App, Arg, ArgMatches, SubCommand};
 use crossbeam_channel::bounded;
 use std::env;
@@ -21,7 +21,8 @@ use std::ops::Div;
 use std::path::PathBuf;
 use std::process::exit;
 use std::str::FromStr;
-use std::thread::{sleep, spawn};
+use std::sync::Arc;
+use std::thread::sleep;
 use std::time::{Duration, Instant};
 use url::Url;
 
@@ -229,6 +230,8 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
         ("bench", Some(arguments)) => {
             let rpc_urls = values_t_or_exit!(arguments, "rpc-urls", Url);
             let data_dir = value_t_or_exit!(arguments, "data-dir", PathBuf);
+            let use_indexer_rpc =
+                value_t_or_exit!(arguments, "cell-source", String) == "indexer-rpc";
             let nodes = rpc_urls
                 .iter()
                 .map(|url| {
@@ -242,7 +245,13 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
                             err
                         )
                     });
-                    Node::init_from_url(url.as_str(), node_data_dir)
+                    let mut node = Node::init_from_url(url.as_str(), node_data_dir);
+                    if use_indexer_rpc {
+                        node.set_cell_source(Arc::new(IndexerRpc::new()));
+                    } else {
+                        node.start_indexer_worker(DEFAULT_INDEXER_POLL_INTERVAL);
+                    }
+                    node
                 })
                 .collect::<Vec<_>>();
             let n_users = value_t_or_exit!(arguments, "n-users", usize);
@@ -282,14 +291,16 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
             let (transaction_sender, transaction_receiver) = bounded(1000000);
 
             wait_for_nodes_sync(&nodes);
-            wait_for_indexer_synced(&nodes);
+            if !use_indexer_rpc {
+                wait_for_indexer_synced(&nodes);
+            }
             ckb_testkit::info!(
                 "bench with params --n-users {} --n-inout {} --tx-interval-ms {} --bench-time-ms {}",
                 users.len(), n_inout, t_tx_interval.as_millis(), t_bench.as_millis(),
             );
 
             let live_cell_producer = LiveCellProducer::new(users.clone(), nodes.clone());
-            spawn(move || {
+            ckb_testkit::logger::spawn(move || {
                 live_cell_producer.run(live_cell_sender);
             });
 
@@ -298,7 +309,7 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
                 vec![users[0].single_secp256k1_cell_dep()],
                 n_inout,
             );
-            spawn(move || {
+            ckb_testkit::logger::spawn(move || {
                 transaction_producer.run(live_cell_receiver, transaction_sender);
             });
 
@@ -351,9 +362,10 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
                 if last_log_time.elapsed() > Duration::from_secs(30) {
                     last_log_time = Instant::now();
                     ckb_testkit::info!(
-                        "benched {} transactions, {} duplicated",
+                        "benched {} transactions, {} duplicated, indexer lag {:?}",
                         benched_transactions,
-                        duplicated_transactions
+                        duplicated_transactions,
+                        nodes[0].indexer_metrics().map(|metrics| metrics.lag()),
                     );
                 }
                 if start_time.elapsed() > t_bench {
@@ -517,6 +529,15 @@ fn clap_app() -> App<'static, 'static> {
                     Arg::with_name("is-smoking-test")
                         .long("is-smoking-test")
                         .help("Whether the target network is production network, like mainnet, testnet, devnet"),
+                )
+                .arg(
+                    Arg::with_name("cell-source")
+                        .long("cell-source")
+                        .value_name("SOURCE")
+                        .takes_value(true)
+                        .possible_values(&["embedded-indexer", "indexer-rpc"])
+                        .default_value("embedded-indexer")
+                        .help("Where to look up live cells, \"indexer-rpc\" requires the nodes to enable the built-in indexer"),
                 ),
         )
         .subcommand(
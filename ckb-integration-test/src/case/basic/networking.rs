@@ -32,7 +32,7 @@ impl Case for BasicNetworking {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2019 = nodes.get_node("node2019");
         let node2021 = nodes.get_node("node2021");
         node2019.mine(10);
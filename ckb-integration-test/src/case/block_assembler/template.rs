@@ -0,0 +1,145 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_types::{
+    core::{BlockView, HeaderView},
+    packed::{self, CellbaseWitness},
+    prelude::*,
+};
+use ckb_testkit::BlockAssembler;
+use std::collections::HashSet;
+
+const N_BLOCKS: usize = 30;
+
+/// Assemble blocks via `BlockAssembler` upon the tip of a live node, and compare them with the
+/// node's block templates and `calculate_dao_field`. The assembled blocks are submitted to the
+/// node, which must accept them.
+///
+/// Every few blocks a transaction is sent, so that the templates also propose and commit
+/// transactions.
+pub struct BlockAssemblerTemplate;
+
+impl Case for BlockAssemblerTemplate {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.read().unwrap().clone(),
+                initial_database: "testdata/db/Height13TestData",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: &mut Nodes) {
+        let node = nodes.get_node("node2021");
+        let mut spent = HashSet::new();
+        let mut committed = 0;
+        for i in 0..N_BLOCKS {
+            if i % 3 == 0 {
+                let input = node
+                    .get_spendable_always_success_cells()
+                    .into_iter()
+                    .find(|cell| !spent.contains(&cell.out_point))
+                    .expect("spendable always-success cell");
+                spent.insert(input.out_point.clone());
+                node.submit_transaction(&node.always_success_transaction(&input));
+            }
+
+            let template = node.rpc_client().get_block_template(None, None, None);
+            let expected_dao = node
+                .rpc_client()
+                .calculate_dao_field(template.clone())
+                .expect("calculate_dao_field");
+            let expected: BlockView = packed::Block::from(template).into_view();
+            let parent = node.get_tip_block().header();
+            let assembled = assemble_like(node, &parent, &expected);
+
+            assert_eq!(
+                expected.dao(),
+                expected_dao,
+                "block {}, the template dao field should equal calculate_dao_field",
+                expected.number()
+            );
+            assert_eq!(
+                assembled.dao(),
+                expected_dao,
+                "block {}, the assembled dao field should equal calculate_dao_field",
+                expected.number()
+            );
+            assert_eq!(assembled.number(), expected.number());
+            assert_eq!(assembled.epoch(), expected.epoch());
+            assert_eq!(assembled.compact_target(), expected.compact_target());
+            assert_eq!(
+                assembled.transactions_root(),
+                expected.transactions_root(),
+                "block {}",
+                expected.number()
+            );
+            assert_eq!(assembled.proposals_hash(), expected.proposals_hash());
+
+            node.submit_block(&assembled);
+            assert_eq!(
+                node.get_tip_block().hash(),
+                assembled.hash(),
+                "the assembled block {} should be accepted, node.log: {}",
+                assembled.number(),
+                node.log_path().display()
+            );
+            committed += assembled.transactions().len() - 1;
+        }
+        assert!(
+            committed > 0,
+            "the assembled blocks should commit transactions"
+        );
+    }
+}
+
+// Assemble the block with the same contents as `expected`, including its cellbase
+fn assemble_like(node: &Node, parent: &HeaderView, expected: &BlockView) -> BlockView {
+    let cellbase = expected.transaction(0).expect("cellbase");
+    let witness = cellbase
+        .witnesses()
+        .get(0)
+        .map(|witness| CellbaseWitness::from_slice(&witness.raw_data()).expect("CellbaseWitness"))
+        .expect("cellbase witness");
+    let mut assembler = BlockAssembler::new(node.consensus(), parent)
+        .timestamp(expected.timestamp())
+        .cellbase_lock(witness.lock())
+        .cellbase_message(witness.message().raw_data())
+        .transactions(expected.transactions().into_iter().skip(1).collect());
+    if let Some(output) = cellbase.output(0) {
+        assembler = assembler.cellbase_reward(output.capacity().unpack(), output.lock());
+    }
+    for proposal in expected.data().proposals().into_iter() {
+        assembler = assembler.proposal(proposal);
+    }
+    for uncle in expected.uncles().into_iter() {
+        assembler = assembler.uncle(&uncle);
+    }
+    if let Some(extension) = expected.extension() {
+        assembler = assembler.extension(extension.raw_data());
+    }
+    let assembled = assembler
+        .assemble(|out_point| {
+            let cell = node
+                .rpc_client()
+                .get_live_cell(out_point.clone().into(), true)
+                .cell?;
+            Some((cell.output.into(), cell.data?.content.into_bytes()))
+        })
+        .unwrap_or_else(|err| panic!("failed to assemble block, error: {}", err));
+    assert_eq!(
+        assembled.transaction(0).expect("cellbase").hash(),
+        cellbase.hash(),
+        "block {}, the assembled cellbase should equal the template one",
+        expected.number()
+    );
+    assembled
+}
@@ -0,0 +1,174 @@
+use super::ERROR_IMMATURE;
+use crate::prelude::*;
+use ckb_testkit::ckb_types::{
+    core::{Capacity, EpochNumberWithFraction, FeeRate, TransactionView},
+    packed::OutPoint,
+    prelude::*,
+};
+use ckb_testkit::util::{
+    dao_withdraw_since, since_from_absolute_epoch_number_with_fraction, DAO_LOCK_PERIOD_EPOCHS,
+};
+use ckb_testkit::{assert_result_eq, User, DEFAULT_FEE_RATE};
+
+/// Exercise the Nervos DAO lifecycle across the 2021 hardfork boundary:
+///
+/// 1. Deposit before the hardfork;
+/// 2. Prepare (phase-1 withdraw) after the hardfork, the maximum withdraw capacity covers the
+///    deposited capacity plus the interest;
+/// 3. Withdraw (phase-2 withdraw) with the minimal `since`, which is rejected as immature
+///    until the lock period ends, then committed, paying the maximum withdraw capacity minus
+///    the fee.
+///
+/// ## Note
+///
+/// The deposit is locked for at least 180 epochs. The chain spec uses 10-block epochs, so that
+/// the lock period is short enough to mine.
+pub struct DAOLifecycle;
+
+impl Case for DAOLifecycle {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.read().unwrap().clone(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/short_epoch_2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: &mut Nodes) {
+        let node2021 = nodes.get_node("node2021");
+        let user = User::new_always_success(node2021.genesis_block().clone());
+        // The 2021 hardfork activates its RFCs together, take RFC0032 as the switch
+        let fork_switch_height = node2021
+            .rfc_activation_number("0032")
+            .unwrap_or_else(|err| panic!("{}", err));
+        node2021.mine_to(fork_switch_height - 10);
+
+        let deposit_capacity = Capacity::bytes(1000).expect("1000 CKB");
+        let deposit_tx = user
+            .dao_deposit_transaction(node2021, deposit_capacity)
+            .expect("build dao deposit transaction");
+        self.commit(node2021, &deposit_tx);
+        let deposit = node2021
+            .get_cell_meta(OutPoint::new(deposit_tx.hash(), 0))
+            .expect("deposit cell should be live");
+        let deposit_number = deposit.transaction_info.as_ref().unwrap().block_number;
+        assert!(
+            deposit_number < fork_switch_height,
+            "deposit at {} should be before the hardfork at {}",
+            deposit_number,
+            fork_switch_height
+        );
+
+        node2021.mine_to(fork_switch_height + 10);
+        let prepare_tx = user
+            .dao_prepare_transaction(node2021, &deposit)
+            .expect("build dao prepare transaction");
+        self.commit(node2021, &prepare_tx);
+        let withdrawing = node2021
+            .get_cell_meta(OutPoint::new(prepare_tx.hash(), 0))
+            .expect("withdrawing cell should be live");
+        let prepare_hash = withdrawing
+            .transaction_info
+            .as_ref()
+            .unwrap()
+            .block_hash
+            .clone();
+        let maximum_withdraw = node2021.rpc_client().calculate_dao_maximum_withdraw(
+            withdrawing.out_point.clone().into(),
+            prepare_hash.clone(),
+        );
+        assert!(
+            maximum_withdraw > deposit_capacity,
+            "maximum withdraw {} should cover deposit {} plus the interest",
+            maximum_withdraw,
+            deposit_capacity
+        );
+
+        let withdraw_tx = user
+            .dao_withdraw_transaction(node2021, &withdrawing)
+            .expect("build dao withdraw transaction");
+        let deposit_header = node2021.get_header_by_number(deposit_number);
+        let prepare_header = node2021.get_block(prepare_hash).header();
+        let deposit_epoch = deposit_header.epoch();
+        let withdraw_epoch = EpochNumberWithFraction::new(
+            deposit_epoch.number() + DAO_LOCK_PERIOD_EPOCHS,
+            deposit_epoch.index(),
+            deposit_epoch.length(),
+        );
+        let expected_since = since_from_absolute_epoch_number_with_fraction(withdraw_epoch);
+        assert_eq!(
+            expected_since,
+            dao_withdraw_since(&deposit_header, &prepare_header),
+            "deposited less than {} epochs, locked for {} epochs",
+            DAO_LOCK_PERIOD_EPOCHS,
+            DAO_LOCK_PERIOD_EPOCHS,
+        );
+        let withdraw_since: u64 = withdraw_tx.inputs().get(0).unwrap().since().unpack();
+        assert_eq!(expected_since, withdraw_since);
+        let actual_result = node2021
+            .rpc_client()
+            .send_transaction_result(withdraw_tx.data().into())
+            .map(|_| ());
+        let expected_result: Result<(), &str> = Err(ERROR_IMMATURE);
+        assert_result_eq!(
+            expected_result,
+            actual_result,
+            "node.log: {}",
+            node2021.log_path().to_string_lossy(),
+        );
+
+        // The withdraw capacity is the maximum withdraw capacity minus the fee
+        let withdraw_capacity = withdraw_tx
+            .outputs_capacity()
+            .expect("withdraw outputs capacity");
+        let withdraw_fee =
+            FeeRate::from_u64(DEFAULT_FEE_RATE).fee(withdraw_tx.data().serialized_size_in_block());
+        assert_eq!(
+            maximum_withdraw,
+            withdraw_capacity.safe_add(withdraw_fee).unwrap(),
+            "maximum withdraw {} should equal the withdraw capacity {} plus the fee {}",
+            maximum_withdraw,
+            withdraw_capacity,
+            withdraw_fee,
+        );
+
+        // The withdraw is mature once the chain reaches `withdraw_epoch`
+        let withdraw_number = node2021
+            .epoch_start_number(withdraw_epoch.number())
+            .unwrap_or_else(|err| panic!("{}", err))
+            + withdraw_epoch.index();
+        node2021.mine_to(withdraw_number);
+        self.commit(node2021, &withdraw_tx);
+        let withdrawn = node2021
+            .get_cell_meta(OutPoint::new(withdraw_tx.hash(), 0))
+            .expect("withdrawn cell should be live");
+        assert_eq!(withdrawn.capacity(), withdraw_capacity);
+        assert!(node2021
+            .get_cell_meta(withdrawing.out_point.clone())
+            .is_none());
+    }
+}
+
+impl DAOLifecycle {
+    fn commit(&self, node: &Node, tx: &TransactionView) {
+        node.submit_transaction(tx);
+        node.mine(3);
+        assert!(
+            node.is_transaction_committed(tx),
+            "transaction {:#x} should be committed, node.log: {}",
+            tx.hash(),
+            node.log_path().to_string_lossy(),
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub(super) mod lifecycle;
+
+const ERROR_IMMATURE: &str = "Immature";
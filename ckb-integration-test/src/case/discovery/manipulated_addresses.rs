@@ -14,6 +14,10 @@ use std::sync::{Arc, RwLock};
 pub struct ManipulatedAddresses;
 
 impl Case for ManipulatedAddresses {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -29,7 +33,7 @@ impl Case for ManipulatedAddresses {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let template_node = nodes.get_node("node2021");
         template_node.mine(1);
         for case in self.cases_params() {
@@ -18,6 +18,10 @@ use std::time::Duration;
 pub struct IdentifyConnection;
 
 impl Case for IdentifyConnection {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -33,7 +37,7 @@ impl Case for IdentifyConnection {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let template_node = nodes.get_node("node2021");
         template_node.mine(1);
         for case in self.cases_params() {
@@ -13,6 +13,10 @@ use std::sync::{Arc, RwLock};
 pub struct IdentifyIdenticalKeyPair;
 
 impl Case for IdentifyIdenticalKeyPair {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -28,7 +32,7 @@ impl Case for IdentifyIdenticalKeyPair {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let template_node = nodes.get_node("node2021");
         template_node.mine(1);
         for case in self.cases_params() {
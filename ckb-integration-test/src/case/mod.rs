@@ -1,5 +1,7 @@
 mod basic;
+mod block_assembler;
 mod case_options;
+mod dao;
 mod discovery;
 mod identify;
 mod rfc0028;
@@ -10,13 +12,23 @@ mod rfc0032;
 mod rfc0034;
 mod rfc0035;
 mod rfc0036;
+mod reorg;
+mod upgrade;
+mod vm;
 
+use crate::util::artifacts::collect_case_artifacts;
+use crate::util::binaries::{BinaryCombination, CkbEdition};
 pub use case_options::CaseOptions;
-use ckb_testkit::{Node, Nodes};
+use ckb_testkit::{logger, LogContext, LogFile, Node, Nodes};
+use std::env;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
 
 pub fn all_cases() -> Vec<Box<dyn Case>> {
     vec![
         Box::new(basic::networking::BasicNetworking),
+        Box::new(block_assembler::template::BlockAssemblerTemplate),
         Box::new(rfc0028::chained::RFC0028Chained),
         Box::new(rfc0028::rfc0028::RFC0028),
         Box::new(rfc0029::rfc0029::RFC0029),
@@ -25,25 +37,125 @@ pub fn all_cases() -> Vec<Box<dyn Case>> {
         Box::new(rfc0036::rfc0036::RFC0036),
         Box::new(rfc0032::rfc0032::RFC0032),
         Box::new(rfc0034::rfc0034::RFC0034),
+        Box::new(vm::test_scripts::VMTestScripts),
         Box::new(rfc0035::v2019::RFC0035V2019),
         Box::new(rfc0035::relay_transaction::RFC0035RelayTransaction),
         Box::new(rfc0035::connection::RFC0035V2021Connection),
         Box::new(rfc0035::discovery::RFC0035V2021Discovery),
+        Box::new(dao::lifecycle::DAOLifecycle),
         Box::new(identify::connection::IdentifyConnection),
         Box::new(identify::identical_key_pair::IdentifyIdenticalKeyPair),
         Box::new(discovery::flood_attack::DiscoveryFloodAttack),
         Box::new(discovery::manipulated_addresses::ManipulatedAddresses),
+        Box::new(reorg::partition::ReorgAcrossPartition),
+        Box::new(upgrade::before_hardfork::RollingUpgradeBeforeHardfork),
+        Box::new(upgrade::across_hardfork::RollingUpgradeAcrossHardfork),
     ]
 }
 
-pub fn run_case(case: Box<dyn Case>) {
-    ckb_testkit::LOG_TARGET.with(|c| {
-        *c.borrow_mut() = case.case_name().to_string();
+/// Run `case` against every combination of the registered binaries of the editions it needs.
+/// The runs of a multi-combination case are put under `<RUN_DIR>/<combination>/`. A failed
+/// combination does not stop the others; the failed ones are reported in a panic at the end.
+pub fn run_case_matrix(case: Box<dyn Case>) {
+    let combinations = crate::BINARY_REGISTRY
+        .read()
+        .unwrap()
+        .combinations(&case.ckb_editions());
+    if combinations.is_empty() {
+        panic!(
+            "no ckb binaries of the editions {:?} required by case {}",
+            case.ckb_editions(),
+            case.case_name()
+        );
+    }
+    let root_run_dir = crate::RUN_DIR.read().unwrap().clone();
+    let original_tmp = env::var_os("CKB_INTEGRATION_TEST_TMP");
+    let multiple = combinations.len() > 1;
+    let mut failed_combinations = Vec::new();
+    for combination in combinations.iter() {
+        let run_dir = if multiple {
+            root_run_dir.join(combination.to_string())
+        } else {
+            root_run_dir.clone()
+        };
+        bind_binaries(combination);
+        // `ckb_testkit::util::temp_path` creates the node working dirs under
+        // `$CKB_INTEGRATION_TEST_TMP/<case_name>/`
+        env::set_var("CKB_INTEGRATION_TEST_TMP", &run_dir);
+        println!("running case {} against {}", case.case_name(), combination);
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_case(case.as_ref(), &run_dir);
+        }));
+        if result.is_err() {
+            println!("case {} failed against {}", case.case_name(), combination);
+            failed_combinations.push(combination.to_string());
+        }
+    }
+    match original_tmp {
+        Some(original_tmp) => env::set_var("CKB_INTEGRATION_TEST_TMP", original_tmp),
+        None => env::remove_var("CKB_INTEGRATION_TEST_TMP"),
+    }
+    if !failed_combinations.is_empty() {
+        panic!(
+            "case {} failed against {} of {} combinations: {}",
+            case.case_name(),
+            failed_combinations.len(),
+            combinations.len(),
+            failed_combinations.join(", ")
+        );
+    }
+}
+
+// Bind `CKB2019` and `CKB2021` to the binaries of `combination`
+fn bind_binaries(combination: &BinaryCombination) {
+    if let Some(ckb2019) = combination.ckb2019.as_ref() {
+        *crate::CKB2019.write().unwrap() = ckb2019.path().to_path_buf();
+    }
+    if let Some(ckb2021) = combination.ckb2021.as_ref() {
+        *crate::CKB2021.write().unwrap() = ckb2021.path().to_path_buf();
+    }
+}
+
+pub fn run_case(case: &dyn Case, run_dir: &Path) {
+    let case_dir = run_dir.join(case.case_name());
+    fs::create_dir_all(&case_dir).unwrap_or_else(|err| {
+        panic!(
+            "failed to create case dir {}, error: {}",
+            case_dir.display(),
+            err
+        )
     });
+    let log_path = case_dir.join("case.log");
+    let log_file =
+        LogFile::open(&log_path, *crate::LOG_FORMAT.read().unwrap()).unwrap_or_else(|err| {
+            panic!(
+                "failed to open case log {}, error: {}",
+                log_path.display(),
+                err
+            )
+        });
+    logger::set_context(LogContext::new(case.case_name()).with_file(log_file));
 
     ckb_testkit::info!("********** START **********");
-    let nodes = case.before_run();
-    case.run(nodes);
+    let mut nodes = case.before_run();
+    link_node_logs(&case_dir, &nodes);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        case.run(&mut nodes);
+        if let Err(divergence) = nodes.check_invariants() {
+            panic!("chain-state invariant violated: {}", divergence);
+        }
+    }));
+    report_node_logs(&case_dir, &nodes);
+    // Stop the nodes before cleaning up their working dirs
+    drop(nodes);
+    collect_case_artifacts(
+        &case_dir,
+        result.is_ok(),
+        *crate::KEEP_ARTIFACTS.read().unwrap(),
+    );
+    if let Err(err) = result {
+        panic::resume_unwind(err);
+    }
     ckb_testkit::info!("********** END **********");
 }
 
@@ -52,6 +164,12 @@ pub trait Case: Send {
         case_name(self)
     }
 
+    /// The ckb editions the case needs. The case runs against every combination of the
+    /// registered binaries of these editions, bound to `CKB2019` and `CKB2021`.
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2019, CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions;
 
     fn before_run(&self) -> Nodes {
@@ -100,7 +218,52 @@ pub trait Case: Send {
         nodes
     }
 
-    fn run(&self, nodes: Nodes);
+    fn run(&self, nodes: &mut Nodes);
+}
+
+// Link the nodes' `run.log` into the case dir, as `<node_name>.run.log`
+fn link_node_logs(case_dir: &Path, nodes: &Nodes) {
+    for node in nodes.nodes() {
+        let link = case_dir.join(format!("{}.run.log", node.node_name()));
+        let _ = fs::remove_file(&link);
+        #[cfg(unix)]
+        let result = ::std::os::unix::fs::symlink(node.log_path(), &link);
+        #[cfg(not(unix))]
+        let result = fs::copy(node.log_path(), &link).map(|_| ());
+        if let Err(err) = result {
+            ckb_testkit::warn!(
+                "failed to link {} to {}, error: {}",
+                node.log_path().display(),
+                link.display(),
+                err
+            );
+        }
+    }
+}
+
+// Collect the ERROR and WARN lines of the nodes' `run.log` into `report.log` under the case dir
+fn report_node_logs(case_dir: &Path, nodes: &Nodes) {
+    let mut report = String::new();
+    for node in nodes.nodes() {
+        let problems = node.log_problems();
+        if problems.is_empty() {
+            continue;
+        }
+        ckb_testkit::warn!(
+            "[Node {}] logged {} ERROR/WARN lines, see {}",
+            node.node_name(),
+            problems.len(),
+            case_dir.join("report.log").display()
+        );
+        report.push_str(&format!("[Node {}]\n", node.node_name()));
+        for line in problems {
+            report.push_str(&line);
+            report.push('\n');
+        }
+    }
+    if !report.is_empty() {
+        let _ = fs::write(case_dir.join("report.log"), report);
+    }
 }
 
 fn case_name<T: ?Sized>(_: &T) -> &str {
@@ -0,0 +1,108 @@
+use crate::prelude::*;
+use ckb_testkit::util::Waiter;
+use ckb_testkit::ForkBranch;
+use std::time::Duration;
+
+const NODE_NAMES: [&str; 3] = ["node1", "node2", "node3"];
+
+/// Partition the network and reorg it after healing:
+///
+/// 1. Partition `node1` from `node2` and `node3`, the groups then grow separately and the
+///    heavier group chain wins after healing;
+/// 2. Build a fork with a shorter branch on `node1` and a longer branch on `node2`, all nodes
+///    switch to the longer one after resolving the fork.
+///
+/// The chain spec uses a permanent difficulty, the longer branch is the heavier one.
+pub struct ReorgAcrossPartition;
+
+impl Case for ReorgAcrossPartition {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: true,
+            node_options: NODE_NAMES
+                .iter()
+                .map(|node_name| NodeOptions {
+                    node_name: String::from(*node_name),
+                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    initial_database: "testdata/db/Height13TestData",
+                    chain_spec: "testdata/spec/ckb2021",
+                    app_config: "testdata/config/ckb2021",
+                })
+                .collect(),
+        }
+    }
+
+    fn run(&self, nodes: &mut Nodes) {
+        // 1. Partition and heal
+        let group_a = &NODE_NAMES[..1];
+        let group_b = &NODE_NAMES[1..];
+        nodes.partition(group_a, group_b);
+        assert!(nodes.is_partitioned());
+        nodes.assert_partitioned(group_a, group_b);
+
+        let node1 = nodes.get_node("node1");
+        let node2 = nodes.get_node("node2");
+        let node3 = nodes.get_node("node3");
+        node1.mine(3);
+        node2.mine(1);
+        // The blocks are synced inside the group, but not across groups
+        let group_b_tip = node2.get_tip_block();
+        Waiter::new(
+            "node3 syncing node2 within the group",
+            Duration::from_secs(60),
+        )
+        .wait_until(|| node3.get_tip_block() == group_b_tip)
+        .unwrap_or_else(|err| panic!("{}", err));
+        let group_a_tip = node1.get_tip_block();
+        assert_ne!(node2.get_tip_block(), group_a_tip);
+        nodes.assert_partitioned(group_a, group_b);
+
+        nodes.heal();
+        assert!(!nodes.is_partitioned());
+        nodes
+            .waiting_for_sync()
+            .expect("nodes should be synced after healing");
+        for node in nodes.nodes() {
+            assert_eq!(
+                node.get_tip_block(),
+                group_a_tip,
+                "node \"{}\" should switch to the heavier chain of node1",
+                node.node_name()
+            );
+        }
+
+        // 2. Fork and resolve
+        let ancestor = nodes.get_fixed_header();
+        let fork = nodes.build_fork(
+            &ancestor,
+            vec![ForkBranch::new("node1", 2), ForkBranch::new("node2", 4)],
+        );
+        assert!(nodes.is_partitioned());
+        assert_eq!(fork.ancestor(), &ancestor);
+        assert_eq!(fork.branch("node1").len(), 2);
+        assert_eq!(fork.branch("node2").len(), 4);
+        for node_name in ["node1", "node2"].iter() {
+            assert_eq!(
+                nodes.get_node(node_name).get_tip_block().header(),
+                fork.branch_tip(node_name),
+                "node \"{}\" should be on its own branch before resolving",
+                node_name
+            );
+        }
+        let expected_winner = fork
+            .expected_winner()
+            .expect("the longer branch is the heavier one")
+            .to_string();
+        assert_eq!(expected_winner, "node2");
+
+        let winner = nodes.resolve_fork(&fork);
+        assert_eq!(winner, expected_winner);
+        nodes.assert_fork_winner(&fork, &winner);
+    }
+}
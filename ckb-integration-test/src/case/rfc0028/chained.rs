@@ -1,5 +1,6 @@
 use super::RFC0028_EPOCH_NUMBER;
 use crate::case::{Case, CaseOptions};
+use crate::util::binaries::CkbEdition;
 use crate::util::estimate_start_number_of_epoch;
 use crate::CKB2021;
 use ckb_testkit::ckb_types::core::Capacity;
@@ -16,6 +17,10 @@ use ckb_testkit::{BuildInstruction, Nodes};
 pub struct RFC0028Chained;
 
 impl Case for RFC0028Chained {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -33,7 +38,7 @@ impl Case for RFC0028Chained {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
         let median_time_block_count = node2021.consensus().median_time_block_count.value();
 
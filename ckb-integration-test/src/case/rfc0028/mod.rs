@@ -1,5 +1,4 @@
 pub(super) mod chained;
 pub(super) mod rfc0028;
 
-const ERROR_IMMATURE: &str = "Immature";
 const RFC0028_EPOCH_NUMBER: u64 = 3;
@@ -1,11 +1,11 @@
-use super::{ERROR_IMMATURE, RFC0028_EPOCH_NUMBER};
+use super::RFC0028_EPOCH_NUMBER;
 use crate::prelude::*;
 use crate::util::estimate_start_number_of_epoch;
 use ckb_testkit::ckb_types::{
-    core::TransactionView,
+    core::{BlockNumber, EpochNumber, EpochNumberWithFraction, TransactionView},
     packed::{CellInput, OutPoint},
 };
-use ckb_testkit::util::since_from_relative_timestamp;
+use ckb_testkit::util::{Since, SinceBlock};
 use ckb_testkit::{assert_result_eq, BuildInstruction};
 
 /// ## Convention
@@ -59,6 +59,10 @@ use ckb_testkit::{assert_result_eq, BuildInstruction};
 pub struct RFC0028;
 
 impl Case for RFC0028 {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -74,15 +78,25 @@ impl Case for RFC0028 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
         let fork_switch_height = estimate_start_number_of_epoch(node2021, RFC0028_EPOCH_NUMBER);
 
         let relative_secs = 1;
-        let since = since_from_relative_timestamp(relative_secs);
+        let since = Since::relative_timestamp(relative_secs);
+
+        let t = node2021.get_tip_block().timestamp();
+        let median_time_block_count = node2021.consensus().median_time_block_count.value();
+        // The block `number` of the constructed chain, whose timestamps are `t + number`. Only
+        // the epoch of the committing block matters to the timestamp metric
+        let modeled_block = |number: BlockNumber, epoch_number: EpochNumber| SinceBlock {
+            number,
+            epoch: EpochNumberWithFraction::new(epoch_number, 0, 1),
+            timestamp: t + number,
+            parent_median_time: t + number - 1 - median_time_block_count / 2,
+        };
 
         let construct_chain_instructions = {
-            let t = node2021.get_tip_block().timestamp();
             let mut ins = Vec::new();
             for number in node2021.get_tip_block_number() + 1..fork_switch_height {
                 ins.push(BuildInstruction::HeaderTimestamp {
@@ -102,15 +116,14 @@ impl Case for RFC0028 {
         // The current tip number is 2999, that has not activate fork.
         // Please read struct documentation for detail.
         //
-        // [(case_id, input_tx_committed_number, tip_number)]
-        let cases_before_switch = vec![
-            (0, 1998, Ok(())),
-            (1, 1999, Ok(())),
-            (2, 2000, Err(ERROR_IMMATURE)),
-        ];
-        for (case_id, input_tx_committed_number, expected_result_before_switch) in
-            cases_before_switch
-        {
+        // [(case_id, input_tx_committed_number)]
+        let cases_before_switch = vec![(0, 1998), (1, 1999), (2, 2000)];
+        for (case_id, input_tx_committed_number) in cases_before_switch {
+            let expected_result_before_switch = since.verify(
+                node2021.consensus(),
+                &modeled_block(input_tx_committed_number, 0),
+                &modeled_block(fork_switch_height - 1, RFC0028_EPOCH_NUMBER - 1),
+            );
             let node = node2021.clone_node(&format!("case-{}-before-switch", case_id));
 
             let mut ins = construct_chain_instructions.clone();
@@ -125,7 +138,7 @@ impl Case for RFC0028 {
                 },
             ]);
 
-            let tx = build_transaction(&node, &input_tx, since);
+            let tx = build_transaction(&node, &input_tx, since.to_u64());
             ins.extend(vec![
                 BuildInstruction::Propose {
                     template_number: fork_switch_height - 1 - 2,
@@ -152,14 +165,14 @@ impl Case for RFC0028 {
         // The current tip number is 2999, that has not activate fork.
         // Please read struct documentation for detail.
         //
-        // [(case_id, input_tx_committed_number, tip_number)]
-        let cases_after_switch = vec![
-            (3, 1980, Ok(())),
-            (4, 1981, Ok(())),
-            (5, 1982, Err(ERROR_IMMATURE)),
-        ];
-        for (case_id, input_tx_committed_number, expected_result_after_switch) in cases_after_switch
-        {
+        // [(case_id, input_tx_committed_number)]
+        let cases_after_switch = vec![(3, 1980), (4, 1981), (5, 1982)];
+        for (case_id, input_tx_committed_number) in cases_after_switch {
+            let expected_result_after_switch = since.verify(
+                node2021.consensus(),
+                &modeled_block(input_tx_committed_number, 0),
+                &modeled_block(fork_switch_height, RFC0028_EPOCH_NUMBER),
+            );
             let node = node2021.clone_node(&format!("case-{}-after-switch", case_id));
 
             let mut ins = construct_chain_instructions.clone();
@@ -174,7 +187,7 @@ impl Case for RFC0028 {
                 },
             ]);
 
-            let tx = build_transaction(&node, &input_tx, since);
+            let tx = build_transaction(&node, &input_tx, since.to_u64());
             ins.extend(vec![
                 BuildInstruction::Propose {
                     template_number: fork_switch_height - 2,
@@ -1,14 +1,11 @@
-use super::{
-    ERROR_DUPLICATE_CELL_DEPS, ERROR_MULTIPLE_MATCHES, RFC0029_BLOCK_NUMBER, RFC0029_EPOCH_NUMBER,
-};
+use super::{ERROR_DUPLICATE_CELL_DEPS, ERROR_MULTIPLE_MATCHES};
 use crate::prelude::*;
 use crate::util::deployer::Deployer;
 use ckb_testkit::ckb_types::{
     core::{
-        cell::CellMeta, BlockNumber, Capacity, DepType, ScriptHashType, TransactionBuilder,
-        TransactionView,
+        cell::CellMeta, BlockNumber, Capacity, ScriptHashType, TransactionBuilder, TransactionView,
     },
-    packed::{Byte32, CellDep, CellInput, CellOutput, OutPointVec, Script},
+    packed::{Byte32, CellInput, CellOutput, Script},
     prelude::*,
 };
 use ckb_testkit::{assert_result_eq, BuildInstruction};
@@ -99,6 +96,10 @@ struct CaseParams {
 pub struct RFC0029;
 
 impl Case for RFC0029 {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -114,7 +115,7 @@ impl Case for RFC0029 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
 
         // We use this as type script of our deployed cells,
@@ -126,7 +127,7 @@ impl Case for RFC0029 {
             .build();
 
         // Deploy dependent cells
-        let mut deployer = Deployer::new();
+        let mut deployer = Deployer::open(node2021);
         // deploy "a1"
         {
             let output_data =
@@ -161,107 +162,25 @@ impl Case for RFC0029 {
                 .unwrap();
             deployer.deploy(node2021, "b1", output, output_data)
         }
-        // deploy Group("a1"), naming "group_a1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![deployer.get_out_point("a1")])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1", output, output_data)
-        }
-        // deploy Group("a2"), naming "group_a2"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![deployer.get_out_point("a2")])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a2", output, output_data)
-        }
-        // deploy Group("b1"), naming "group_b1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![deployer.get_out_point("b1")])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_b1", output, output_data)
-        }
-        // deploy Group("a1", "a1"), naming "group_a1_a1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![
-                    deployer.get_out_point("a1"),
-                    deployer.get_out_point("a1"),
-                ])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1_a1", output, output_data)
-        }
-        // deploy Group("a1", "a2"), naming "group_a1_a2"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![
-                    deployer.get_out_point("a1"),
-                    deployer.get_out_point("a2"),
-                ])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1_a2", output, output_data)
-        }
-        // deploy Group("a1", "b1"), naming "group_a1_b1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![
-                    deployer.get_out_point("a1"),
-                    deployer.get_out_point("b1"),
-                ])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1_b1", output, output_data)
-        }
+        // deploy dep groups, naming "group_<members>"
+        deployer.deploy_dep_group(node2021, "group_a1", &["a1"]);
+        deployer.deploy_dep_group(node2021, "group_a2", &["a2"]);
+        deployer.deploy_dep_group(node2021, "group_b1", &["b1"]);
+        deployer.deploy_dep_group(node2021, "group_a1_a1", &["a1", "a1"]);
+        deployer.deploy_dep_group(node2021, "group_a1_a2", &["a1", "a2"]);
+        deployer.deploy_dep_group(node2021, "group_a1_b1", &["a1", "b1"]);
 
-        let code_hash_via_data_hash = {
-            let out_point = deployer.get_out_point("a1");
-            let cell_with_status = node2021.rpc_client().get_live_cell(out_point.into(), true);
-            let raw_data = cell_with_status.cell.unwrap().data.unwrap().content;
-            CellOutput::calc_data_hash(raw_data.as_bytes())
-        };
+        let code_hash_via_data_hash = deployer.get_cell("a1").data_hash();
         let code_hash_via_type_hash = { type_script.calc_script_hash() };
 
         // Assert the current tip is lower than fork switch height
-        assert!(node2021.get_tip_block().epoch().number() < RFC0029_EPOCH_NUMBER);
+        assert!(!node2021.is_rfc_activated("0029"));
+        let rfc0029_activation_number = node2021
+            .rfc_activation_number("0029")
+            .unwrap_or_else(|err| panic!("{}", err));
 
         let input = node2021.get_spendable_always_success_cells()[0].to_owned();
-        for case in self.cases_params() {
+        for case in self.cases_params(rfc0029_activation_number) {
             let node = {
                 let node = node2021.clone_node(&format!("case-{}-node", case.id));
                 node.pull_node(node2021).unwrap();
@@ -301,368 +220,368 @@ impl Case for RFC0029 {
 }
 
 impl RFC0029 {
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn cases_params(&self, rfc0029_activation_number: BlockNumber) -> Vec<CaseParams> {
         vec![
             CaseParams {
                 id: 0,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 1,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1", "a1"],
                 expected_result: Err(ERROR_DUPLICATE_CELL_DEPS),
             },
             CaseParams {
                 id: 2,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1", "a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 3,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1", "b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 4,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 5,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1_a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 6,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1_a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 7,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1_b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 8,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 9,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 10,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 11,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "group_a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 12,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "group_b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 13,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 14,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1", "a1"],
                 expected_result: Err(ERROR_DUPLICATE_CELL_DEPS),
             },
             CaseParams {
                 id: 15,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1", "a2"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 16,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1", "b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 17,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 18,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1_a1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 19,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1_a2"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 20,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1_b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 21,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "a1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 22,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "a2"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 23,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 24,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "group_a2"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 25,
-                height: RFC0029_BLOCK_NUMBER - 1,
+                height: rfc0029_activation_number - 1,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "group_b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 26,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 27,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1", "a1"],
                 expected_result: Err(ERROR_DUPLICATE_CELL_DEPS),
             },
             CaseParams {
                 id: 28,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1", "a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 29,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["a1", "b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 30,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 31,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1_a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 32,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1_a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 33,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1_b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 34,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 35,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 36,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 37,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "group_a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 38,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Data,
                 cell_deps: vec!["group_a1", "group_b1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 39,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 40,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1", "a1"],
                 expected_result: Err(ERROR_DUPLICATE_CELL_DEPS),
             },
             CaseParams {
                 id: 41,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1", "a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 42,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["a1", "b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 43,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 44,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1_a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 45,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1_a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 46,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1_b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 47,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "a1"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 48,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 49,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
             },
             CaseParams {
                 id: 50,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "group_a2"],
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 51,
-                height: RFC0029_BLOCK_NUMBER,
+                height: rfc0029_activation_number,
                 script_hash_type: ScriptHashType::Type,
                 cell_deps: vec!["group_a1", "group_b1"],
                 expected_result: Err(ERROR_MULTIPLE_MATCHES),
@@ -702,17 +621,7 @@ impl RFC0029 {
             cell_deps.push(node.always_success_cell_dep());
             // cell-deps for output.type_
             for cell_name in str_cell_deps {
-                let cell_meta = deployer.get_cell(cell_name);
-                let dep_type = if cell_name.contains("group") {
-                    DepType::DepGroup
-                } else {
-                    DepType::Code
-                };
-                let cell_dep = CellDep::new_builder()
-                    .dep_type(dep_type.into())
-                    .out_point(cell_meta.out_point)
-                    .build();
-                cell_deps.push(cell_dep);
+                cell_deps.push(deployer.cell_dep(cell_name));
             }
             cell_deps
         };
@@ -115,6 +115,10 @@ struct CaseParams {
 pub struct RFC0030;
 
 impl Case for RFC0030 {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -132,7 +136,7 @@ impl Case for RFC0030 {
 
     // NOTE: This test make a strong assumption that
     // `input.tx_info.block.epoch == EpochNumberWithFraction(1, 0, 1000)`
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
 
         // Construct input out point which
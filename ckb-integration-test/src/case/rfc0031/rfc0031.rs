@@ -1,14 +1,10 @@
-use super::{ERROR_EMPTY_EXT, ERROR_MAX_LIMIT, ERROR_UNKNOWN_FIELDS, RFC0031_EPOCH_NUMBER};
+use super::{ERROR_EMPTY_EXT, ERROR_MAX_LIMIT, ERROR_UNKNOWN_FIELDS};
 use crate::prelude::*;
-use crate::util::estimate_start_number_of_epoch;
-use ckb_testkit::assert_result_eq;
 use ckb_testkit::ckb_types::{
+    bytes::Bytes,
     core::{BlockNumber, BlockView},
-    packed,
-    prelude::*,
 };
-
-const RFC0031_BLOCK_NUMBER: BlockNumber = 3000;
+use ckb_testkit::{assert_result_eq, BlockMutator};
 
 /// ```text
 /// ┌─────────────────────┬───────────────────────┬───────────────────────┐
@@ -35,6 +31,10 @@ const RFC0031_BLOCK_NUMBER: BlockNumber = 3000;
 pub struct RFC0031;
 
 impl Case for RFC0031 {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -50,13 +50,15 @@ impl Case for RFC0031 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
 
-        let fork_switch_height = estimate_start_number_of_epoch(node2021, RFC0031_EPOCH_NUMBER);
+        let fork_switch_height = node2021
+            .rfc_activation_number("0031")
+            .unwrap_or_else(|err| panic!("{}", err));
         node2021.mine_to(fork_switch_height - 6);
 
-        for case in self.cases_params() {
+        for case in self.cases_params(fork_switch_height) {
             let node = node2021.clone_node(&format!("case-{}-node", case.id));
             node.mine_to(case.height - 1);
             let block = self.build_block(&node, case.extension_size);
@@ -85,108 +87,107 @@ struct CaseParams {
 impl RFC0031 {
     fn build_block(&self, node: &Node, extension_size: Option<usize>) -> BlockView {
         let template = node.rpc_client().get_block_template(None, None, None);
-        packed::Block::from(template)
-            .as_advanced_builder()
-            .extension(extension_size.map(|s| vec![0u8; s].pack()))
+        BlockMutator::new(template)
+            .extension(extension_size.map(|s| Bytes::from(vec![0u8; s])))
             .build()
     }
 
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn cases_params(&self, fork_switch_height: BlockNumber) -> Vec<CaseParams> {
         vec![
             CaseParams {
                 id: 0,
                 extension_size: None,
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 1,
                 extension_size: Some(0),
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_UNKNOWN_FIELDS),
             },
             CaseParams {
                 id: 2,
                 extension_size: Some(1),
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_UNKNOWN_FIELDS),
             },
             CaseParams {
                 id: 3,
                 extension_size: Some(16),
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_UNKNOWN_FIELDS),
             },
             CaseParams {
                 id: 4,
                 extension_size: Some(32),
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_UNKNOWN_FIELDS),
             },
             CaseParams {
                 id: 5,
                 extension_size: Some(64),
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_UNKNOWN_FIELDS),
             },
             CaseParams {
                 id: 6,
                 extension_size: Some(96),
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_UNKNOWN_FIELDS),
             },
             CaseParams {
                 id: 7,
                 extension_size: Some(97),
-                height: RFC0031_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_UNKNOWN_FIELDS),
             },
             CaseParams {
                 id: 8,
                 extension_size: None,
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 9,
                 extension_size: Some(0),
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Err(ERROR_EMPTY_EXT),
             },
             CaseParams {
                 id: 10,
                 extension_size: Some(1),
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 11,
                 extension_size: Some(16),
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 12,
                 extension_size: Some(32),
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 13,
                 extension_size: Some(64),
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 14,
                 extension_size: Some(96),
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Ok(()),
             },
             CaseParams {
                 id: 15,
                 extension_size: Some(97),
-                height: RFC0031_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Err(ERROR_MAX_LIMIT),
             },
         ]
@@ -1,14 +1,16 @@
 use crate::prelude::*;
 use ckb_testkit::ckb_types::{
-    core::{BlockNumber, Capacity, Cycle, ScriptHashType, TransactionBuilder, TransactionView},
+    core::{Capacity, Cycle, ScriptHashType, TransactionBuilder, TransactionView},
     packed::{CellInput, CellOutput, OutPoint, Script},
     prelude::*,
 };
+use ckb_testkit::util::hardfork::HARDFORK_DELAY_WINDOW;
 use ckb_testkit::{assert_result_eq, Node, NodeOptions, Nodes, SYSTEM_CELL_ALWAYS_SUCCESS_INDEX};
 
 /// ## Convention
 ///
-/// 1. Fork2021 activates at height `3000`.
+/// 1. Fork2021 activates at the start of the epoch RFC0032 is scheduled, see
+///    `Node::rfc_activation_number`.
 ///
 /// ## Note
 ///
@@ -40,16 +42,21 @@ pub struct RFC0032;
 
 const VM0_CYCLES: Cycle = 537 + 537;
 const VM1_CYCLES: Cycle = 537 + 539;
-const RFC0032_BLOCK_NUMBER: BlockNumber = 3000;
-const HARDFORK_DELAY_WINDOW: u64 = 10;
+// The tip offsets from the RFC0032 activation, out of the hardfork delay window
+const NON_ACTIVATED_OFFSET: i64 = -(HARDFORK_DELAY_WINDOW as i64) - 1;
+const ACTIVATED_OFFSET: i64 = HARDFORK_DELAY_WINDOW as i64 + 1;
 const ERROR_INVALID_VM_VERSION: &str = "Invalid VM Version";
 
 impl Case for RFC0032 {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         Default::default()
     }
 
-    fn run(&self, _nodes: Nodes) {
+    fn run(&self, _nodes: &mut Nodes) {
         for case in self.cases_params() {
             let node = self.setup_node(&case);
             let tx = self.build_transaction(&node, &case);
@@ -70,7 +77,8 @@ impl Case for RFC0032 {
 struct CaseParams {
     id: usize,
     type_script_hash_type: ScriptHashType,
-    height: BlockNumber,
+    // The tip number relative to the RFC0032 activation
+    offset: i64,
     expected_result: Result<Cycle, String>,
 }
 
@@ -97,7 +105,7 @@ impl RFC0032 {
         let mut node = Node::init(self.case_name(), node_options, true);
         node.start();
 
-        node.mine_to(case.height);
+        node.mine_to_rfc_activation("0032", case.offset);
 
         node
     }
@@ -165,37 +173,37 @@ impl RFC0032 {
             CaseParams {
                 id: 0,
                 type_script_hash_type: ScriptHashType::Data,
-                height: RFC0032_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                offset: NON_ACTIVATED_OFFSET,
                 expected_result: Ok(VM0_CYCLES),
             },
             CaseParams {
                 id: 1,
                 type_script_hash_type: ScriptHashType::Type,
-                height: RFC0032_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                offset: NON_ACTIVATED_OFFSET,
                 expected_result: Ok(VM0_CYCLES),
             },
             CaseParams {
                 id: 2,
                 type_script_hash_type: ScriptHashType::Data1,
-                height: RFC0032_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                offset: NON_ACTIVATED_OFFSET,
                 expected_result: Err(ERROR_INVALID_VM_VERSION.to_string()),
             },
             CaseParams {
                 id: 3,
                 type_script_hash_type: ScriptHashType::Data,
-                height: RFC0032_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                offset: ACTIVATED_OFFSET,
                 expected_result: Ok(VM0_CYCLES),
             },
             CaseParams {
                 id: 4,
                 type_script_hash_type: ScriptHashType::Type,
-                height: RFC0032_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                offset: ACTIVATED_OFFSET,
                 expected_result: Ok(VM1_CYCLES),
             },
             CaseParams {
                 id: 5,
                 type_script_hash_type: ScriptHashType::Data1,
-                height: RFC0032_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                offset: ACTIVATED_OFFSET,
                 expected_result: Ok(VM1_CYCLES),
             },
         ]
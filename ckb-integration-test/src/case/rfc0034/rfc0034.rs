@@ -74,6 +74,10 @@ use ckb_testkit::ckb_types::{
 pub struct RFC0034;
 
 impl Case for RFC0034 {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -89,7 +93,7 @@ impl Case for RFC0034 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
 
         // Make sure the VM1 is activated
@@ -97,7 +101,7 @@ impl Case for RFC0034 {
         node2021.mine_to(fork_switch_height + 10);
 
         // Deploy contract cells
-        let mut deployer = Deployer::new();
+        let mut deployer = Deployer::open(node2021);
         {
             let scripts_data = vec![
                 (
@@ -181,16 +185,13 @@ impl RFC0034 {
 
         // Prepare common-used utils
         let exec_callee_data: Bytes = {
-            let exec_callee_cell = deployer.get_cell("exec_callee");
             let cell_with_status = node
                 .rpc_client()
-                .get_live_cell(exec_callee_cell.out_point.clone().into(), true);
+                .get_live_cell(deployer.get_out_point("exec_callee").into(), true);
             let raw_data = cell_with_status.cell.unwrap().data.unwrap().content;
             raw_data.into_bytes().pack()
         };
-        let exec_caller_cell_dep = CellDep::new_builder()
-            .out_point(deployer.get_cell("exec_caller").out_point.clone())
-            .build();
+        let exec_caller_cell_dep = deployer.cell_dep("exec_caller");
         let exec_caller_output = {
             let exec_params = ExecParams::new_builder()
                 .source(ckb_exec_params::ckb_types::prelude::Pack::pack(
@@ -202,14 +203,7 @@ impl RFC0034 {
                 .index(ckb_exec_params::ckb_types::prelude::Pack::pack(&0u32))
                 .bounds(ckb_exec_params::ckb_types::prelude::Pack::pack(&0u64))
                 .build();
-            let exec_caller_data_hash = {
-                let exec_caller_out_point = deployer.get_out_point("exec_caller");
-                let cell_with_status = node
-                    .rpc_client()
-                    .get_live_cell(exec_caller_out_point.into(), true);
-                let raw_data = cell_with_status.cell.unwrap().data.unwrap().content;
-                CellOutput::calc_data_hash(raw_data.as_bytes())
-            };
+            let exec_caller_data_hash = deployer.get_cell("exec_caller").data_hash();
             CellOutput::new_builder()
                 .lock(node.always_success_script())
                 .type_(
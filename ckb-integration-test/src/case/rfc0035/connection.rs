@@ -6,13 +6,10 @@
 
 use crate::case::{Case, CaseOptions};
 use crate::{CKB2019, CKB2021};
-use ckb_testkit::ckb_types::core::BlockNumber;
 use ckb_testkit::util::wait_until;
 use ckb_testkit::Nodes;
 use ckb_testkit::{Node, NodeOptions};
 
-const RFC0035_BLOCK_NUMBER: BlockNumber = 3000;
-
 pub struct RFC0035V2021Connection;
 
 impl Case for RFC0035V2021Connection {
@@ -56,11 +53,16 @@ impl Case for RFC0035V2021Connection {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
-        let rfc0035_activated_number = RFC0035_BLOCK_NUMBER - 1;
+    fn run(&self, nodes: &mut Nodes) {
+        let node2021 = nodes.get_node("node2021");
+        // RFC0035 is not a hardfork feature of `Consensus`, ckb switches the p2p protocols along
+        // with RFC0032
+        let rfc0035_activated_number = node2021
+            .rfc_activation_number("0032")
+            .unwrap_or_else(|err| panic!("{}", err))
+            - 1;
         let rfc0035_non_activated_number = rfc0035_activated_number - 1;
 
-        let node2021 = nodes.get_node("node2021");
         node2021.mine_to(rfc0035_non_activated_number);
         nodes
             .waiting_for_sync()
@@ -3,6 +3,7 @@
 // After fork2021, node2021s will still propagate info
 
 use crate::case::{Case, CaseOptions};
+use crate::util::binaries::CkbEdition;
 use crate::util::estimate_start_number_of_epoch;
 use crate::CKB2021;
 use ckb_testkit::ckb_types::core::EpochNumber;
@@ -14,6 +15,10 @@ const RFC0035_EPOCH_NUMBER: EpochNumber = 3;
 pub struct RFC0035V2021Discovery;
 
 impl Case for RFC0035V2021Discovery {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -47,7 +52,7 @@ impl Case for RFC0035V2021Discovery {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         // Move node2021s beyond fork2021
         for node in nodes.nodes() {
             node.mine_to(estimate_start_number_of_epoch(node, RFC0035_EPOCH_NUMBER));
@@ -1,4 +1,3 @@
-use super::{HARDFORK_DELAY_WINDOW, RFC0035_BLOCK_NUMBER};
 use crate::{
     prelude::*,
     util::{v0_100, v0_43},
@@ -13,6 +12,7 @@ use ckb_testkit::ckb_types::{
 use ckb_testkit::connector::{
     Connector, ConnectorBuilder, SharedState, SimpleProtocolHandler, SimpleServiceHandler,
 };
+use ckb_testkit::util::hardfork::HARDFORK_DELAY_WINDOW;
 use ckb_testkit::util::wait_until;
 use ckb_testkit::SupportProtocols;
 use ckb_testkit::SYSTEM_CELL_ALWAYS_SUCCESS_INDEX;
@@ -132,8 +132,13 @@ impl Case for RFC0035RelayTransaction {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
+        // RFC0035 is not a hardfork feature of `Consensus`, ckb switches the p2p protocols along
+        // with RFC0032
+        let rfc0035_activation_number = node2021
+            .rfc_activation_number("0032")
+            .unwrap_or_else(|err| panic!("{}", err));
         node2021.mine(13);
 
         // Prepare a cycles-consystency input
@@ -156,7 +161,7 @@ impl Case for RFC0035RelayTransaction {
             "Ensure the input's scripts are cycles-consistency",
         );
         assert!(
-            input.transaction_info.as_ref().unwrap().block_number < RFC0035_BLOCK_NUMBER - 20,
+            input.transaction_info.as_ref().unwrap().block_number < rfc0035_activation_number - 20,
             "Ensure the input is valid for all cases scenarioes"
         );
 
@@ -185,7 +190,7 @@ impl Case for RFC0035RelayTransaction {
                 // Let `node_used_to_dry_run_txs` activates fork2021, so that
                 // it allows data1-transactions
                 node.pull_node(node2021).unwrap();
-                node.mine_to(RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1);
+                node.mine_to(rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1);
                 node
             };
 
@@ -196,7 +201,7 @@ impl Case for RFC0035RelayTransaction {
             vm1_cycles = node_used_to_dry_run_txs.get_transaction_cycles(&data1_tx);
         };
 
-        for case in self.cases_params() {
+        for case in self.cases_params(rfc0035_activation_number) {
             let tx = match case.tx_script_hash_type {
                 ScriptHashType::Data => data0_tx.clone(),
                 ScriptHashType::Type => type_tx.clone(),
@@ -385,11 +390,11 @@ impl RFC0035RelayTransaction {
         }
     }
 
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn cases_params(&self, rfc0035_activation_number: BlockNumber) -> Vec<CaseParams> {
         vec![
             CaseParams {
                 id: 1,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -398,7 +403,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 2,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -407,7 +412,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 3,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -416,7 +421,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 4,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -425,7 +430,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 5,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -434,7 +439,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 6,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -443,7 +448,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 7,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -452,7 +457,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 8,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -461,7 +466,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 9,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -470,7 +475,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 10,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -479,7 +484,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 11,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -488,7 +493,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 12,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -497,7 +502,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 13,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -506,7 +511,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 14,
-                height: RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1,
+                height: rfc0035_activation_number - HARDFORK_DELAY_WINDOW - 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -515,7 +520,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 15,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -524,7 +529,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 16,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -533,7 +538,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 17,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -542,7 +547,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 18,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -551,7 +556,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 19,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -560,7 +565,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 20,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_43(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -569,7 +574,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 21,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::Relay,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -578,7 +583,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 22,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -587,7 +592,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 23,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data,
@@ -596,7 +601,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 24,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -605,7 +610,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 25,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Type,
@@ -614,7 +619,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 26,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -623,7 +628,7 @@ impl RFC0035RelayTransaction {
             },
             CaseParams {
                 id: 27,
-                height: RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1,
+                height: rfc0035_activation_number + HARDFORK_DELAY_WINDOW + 1,
                 peer_version: v0_100(),
                 protocol: SupportProtocols::RelayV2,
                 tx_script_hash_type: ScriptHashType::Data1,
@@ -49,7 +49,7 @@ impl Case for RFC0035V2019 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2019 = nodes.get_node("node2019");
         let node2021 = nodes.get_node("node2021");
 
@@ -1,4 +1,3 @@
 pub(super) mod rfc0036;
 
-const RFC0036_EPOCH_NUMBER: ckb_testkit::ckb_types::core::EpochNumber = 3;
 const ERROR_IMMATURE_HEADER: &str = "ImmatureHeader";
@@ -1,11 +1,8 @@
-use super::{ERROR_IMMATURE_HEADER, RFC0036_EPOCH_NUMBER};
+use super::ERROR_IMMATURE_HEADER;
 use crate::prelude::*;
-use crate::util::estimate_start_number_of_epoch;
 use ckb_testkit::ckb_types::core::{BlockNumber, TransactionView};
 use ckb_testkit::{assert_result_eq, BuildInstruction};
 
-const RFC0036_BLOCK_NUMBER: BlockNumber = 3000;
-
 /// ```text
 /// ┌──────────────┬───────────┬───────────┐
 /// │              │           │           │
@@ -19,6 +16,10 @@ const RFC0036_BLOCK_NUMBER: BlockNumber = 3000;
 pub struct RFC0036;
 
 impl Case for RFC0036 {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -34,17 +35,19 @@ impl Case for RFC0036 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn run(&self, nodes: &mut Nodes) {
         let node2021 = nodes.get_node("node2021");
 
         for node in nodes.nodes() {
             assert!(node.consensus().cellbase_maturity.value() > 0);
         }
 
-        let fork_switch_height = estimate_start_number_of_epoch(node2021, RFC0036_EPOCH_NUMBER);
+        let fork_switch_height = node2021
+            .rfc_activation_number("0036")
+            .unwrap_or_else(|err| panic!("{}", err));
         node2021.mine_to(fork_switch_height - 6);
 
-        for case in self.cases_params() {
+        for case in self.cases_params(fork_switch_height) {
             let node = {
                 let node = node2021.clone_node(&format!("case-{}-node", case.id));
                 node.pull_node(node2021).unwrap();
@@ -81,16 +84,16 @@ struct CaseParams {
 }
 
 impl RFC0036 {
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn cases_params(&self, fork_switch_height: BlockNumber) -> Vec<CaseParams> {
         vec![
             CaseParams {
                 id: 0,
-                height: RFC0036_BLOCK_NUMBER - 1,
+                height: fork_switch_height - 1,
                 expected_result: Err(ERROR_IMMATURE_HEADER),
             },
             CaseParams {
                 id: 1,
-                height: RFC0036_BLOCK_NUMBER,
+                height: fork_switch_height,
                 expected_result: Ok(()),
             },
         ]
@@ -0,0 +1,68 @@
+use super::{assert_committed, assert_relayed, node2019_options, upgrade_node};
+use crate::case::{Case, CaseOptions};
+use ckb_testkit::util::Waiter;
+use ckb_testkit::Nodes;
+use std::time::Duration;
+
+/// Upgrade the nodes from ckb2019 to ckb2021 one by one across the hardfork:
+///
+/// 1. Upgrade node1 and node2 before the hardfork, then mine beyond the activation of RFC0032;
+///    node2 keeps syncing and relaying with node1, node3 (ckb2019) is disconnected;
+/// 2. Upgrade node3 after the hardfork, it catches up with the chain and relays transactions.
+pub struct RollingUpgradeAcrossHardfork;
+
+impl Case for RollingUpgradeAcrossHardfork {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: true,
+            node_options: vec![
+                node2019_options("node1"),
+                node2019_options("node2"),
+                node2019_options("node3"),
+            ],
+        }
+    }
+
+    fn run(&self, nodes: &mut Nodes) {
+        let mut inputs = nodes
+            .get_node("node1")
+            .get_spendable_always_success_cells()
+            .into_iter();
+
+        upgrade_node(nodes, "node1", &["node2", "node3"]);
+        upgrade_node(nodes, "node2", &["node1", "node3"]);
+        nodes.waiting_for_sync().expect("nodes should be synced");
+        let input = inputs.next().expect("spendable cell");
+        let tx = assert_relayed(nodes, "node1", &["node2", "node3"], &input);
+        assert_committed(nodes, "node1", &["node2", "node3"], &tx);
+
+        // Mine beyond the hardfork, node3 is left behind
+        let node1 = nodes.get_node("node1");
+        let node2 = nodes.get_node("node2");
+        let node3 = nodes.get_node("node3");
+        assert!(!node1.is_rfc_activated("0032"));
+        let target = node1.mine_to_rfc_activation("0032", 10);
+        Waiter::new("node2 synced beyond the hardfork", Duration::from_secs(60))
+            .wait_until(|| node2.get_tip_block_number() >= target)
+            .unwrap_or_else(|err| panic!("{}", err));
+        Waiter::new(
+            "node3 disconnected after the hardfork",
+            Duration::from_secs(20),
+        )
+        .wait_until(|| node3.rpc_client().get_peers().is_empty())
+        .unwrap_or_else(|err| panic!("{}", err));
+        let input = inputs.next().expect("spendable cell");
+        let tx = assert_relayed(nodes, "node2", &["node1"], &input);
+        assert_committed(nodes, "node1", &["node2"], &tx);
+
+        // Upgrade node3 after the hardfork
+        upgrade_node(nodes, "node3", &["node1", "node2"]);
+        nodes.waiting_for_sync().expect("nodes should be synced");
+        assert!(nodes.get_node("node3").is_rfc_activated("0032"));
+        let input = inputs.next().expect("spendable cell");
+        let tx = assert_relayed(nodes, "node3", &["node1", "node2"], &input);
+        assert_committed(nodes, "node3", &["node1", "node2"], &tx);
+    }
+}
@@ -0,0 +1,65 @@
+use super::{assert_committed, assert_relayed, node2019_options, upgrade_node};
+use crate::case::{Case, CaseOptions};
+use ckb_testkit::Nodes;
+
+const NODE_NAMES: [&str; 3] = ["node1", "node2", "node3"];
+
+/// Upgrade the nodes from ckb2019 to ckb2021 one by one before the hardfork, while the network
+/// keeps running:
+///
+/// 1. A transaction pending before the upgrade of a node is committed afterwards, and the
+///    upgraded node syncs it. The tx-pool of the upgraded node is dropped, as ckb2019 does not
+///    persist it on shutdown, so the transaction is committed via the other nodes;
+/// 2. The upgraded node keeps syncing with and relaying transactions to the other nodes,
+///    whatever their versions.
+pub struct RollingUpgradeBeforeHardfork;
+
+impl Case for RollingUpgradeBeforeHardfork {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: true,
+            node_options: NODE_NAMES
+                .iter()
+                .map(|node_name| node2019_options(node_name))
+                .collect(),
+        }
+    }
+
+    fn run(&self, nodes: &mut Nodes) {
+        let mut inputs = nodes
+            .get_node(NODE_NAMES[0])
+            .get_spendable_always_success_cells()
+            .into_iter();
+        for node_name in NODE_NAMES.iter() {
+            let others = NODE_NAMES
+                .iter()
+                .filter(|name| *name != node_name)
+                .cloned()
+                .collect::<Vec<_>>();
+
+            // The transaction is pending on the node before upgrading
+            let input = inputs.next().expect("spendable cell");
+            let pending_tx = assert_relayed(nodes, node_name, &others, &input);
+
+            upgrade_node(nodes, node_name, &others);
+            nodes.waiting_for_sync().expect("nodes should be synced");
+            for other in others.iter() {
+                assert!(
+                    nodes.get_node(other).is_transaction_pending(&pending_tx),
+                    "transaction {:#x} should be still pending on {} after upgrading {}",
+                    pending_tx.hash(),
+                    other,
+                    node_name
+                );
+            }
+            assert_committed(nodes, others[0], &NODE_NAMES, &pending_tx);
+
+            // The upgraded node relays and mines
+            let input = inputs.next().expect("spendable cell");
+            let relayed_tx = assert_relayed(nodes, node_name, &others, &input);
+            assert_committed(nodes, node_name, &NODE_NAMES, &relayed_tx);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+pub(super) mod across_hardfork;
+pub(super) mod before_hardfork;
+
+use crate::{CKB2019, CKB2021};
+use ckb_testkit::ckb_types::core::{cell::CellMeta, TransactionView};
+use ckb_testkit::util::Waiter;
+use ckb_testkit::{NodeOptions, Nodes};
+use std::time::Duration;
+
+// The nodes start as ckb2019 on the pre-hardfork database
+fn node2019_options(node_name: &str) -> NodeOptions {
+    NodeOptions {
+        node_name: String::from(node_name),
+        ckb_binary: CKB2019.read().unwrap().clone(),
+        initial_database: "testdata/db/Epoch2V1TestData",
+        chain_spec: "testdata/spec/ckb2019",
+        app_config: "testdata/config/ckb2019",
+    }
+}
+
+// The options to upgrade a node to ckb2021, with the same genesis plus the hardfork features
+fn node2021_options(node_name: &str) -> NodeOptions {
+    NodeOptions {
+        node_name: String::from(node_name),
+        ckb_binary: CKB2021.read().unwrap().clone(),
+        initial_database: "",
+        chain_spec: "testdata/spec/ckb2021",
+        app_config: "testdata/config/ckb2021",
+    }
+}
+
+// Upgrade the node `node_name` to ckb2021 and reconnect it to `peers`
+fn upgrade_node(nodes: &mut Nodes, node_name: &str, peers: &[&str]) {
+    nodes
+        .get_node_mut(node_name)
+        .upgrade(node2021_options(node_name), true);
+    let node = nodes.get_node(node_name);
+    for peer in peers {
+        node.p2p_connect(nodes.get_node(peer));
+    }
+}
+
+// Submit a transaction spending `input` via `from`, and wait until it is relayed to `to`
+fn assert_relayed(nodes: &Nodes, from: &str, to: &[&str], input: &CellMeta) -> TransactionView {
+    let sender = nodes.get_node(from);
+    let tx = sender.always_success_transaction(input);
+    sender.submit_transaction(&tx);
+    for node_name in to {
+        let node = nodes.get_node(node_name);
+        Waiter::new(
+            format!("transaction relayed from {} to {}", from, node_name),
+            Duration::from_secs(20),
+        )
+        .wait_until(|| node.is_transaction_pending(&tx))
+        .unwrap_or_else(|err| panic!("{}", err));
+    }
+    tx
+}
+
+// Mine via `miner` until `tx` is committed, and wait until `observers` synced it
+fn assert_committed(nodes: &Nodes, miner: &str, observers: &[&str], tx: &TransactionView) {
+    let miner = nodes.get_node(miner);
+    for _ in 0..10 {
+        if miner.is_transaction_committed(tx) {
+            break;
+        }
+        miner.mine(1);
+    }
+    assert!(
+        miner.is_transaction_committed(tx),
+        "transaction {:#x} should be committed, node name: {}",
+        tx.hash(),
+        miner.node_name()
+    );
+    for node_name in observers {
+        let node = nodes.get_node(node_name);
+        Waiter::new(
+            format!("transaction committed on {}", node_name),
+            Duration::from_secs(60),
+        )
+        .wait_until(|| node.is_transaction_committed(tx))
+        .unwrap_or_else(|err| panic!("{}", err));
+    }
+}
@@ -0,0 +1,3 @@
+pub(super) mod test_scripts;
+
+const ERROR_OUT_OF_BOUND: &str = "OutOfBound";
@@ -0,0 +1,206 @@
+use super::ERROR_OUT_OF_BOUND;
+use crate::prelude::*;
+use crate::util::deployer::Deployer;
+use crate::util::scripts::{
+    cycle_burner_args, exit_code_args, load_syscall_args, memory_growth_args, TestScript,
+    VmVersion, MEMORY_GROWTH_MAX_PAGES, SOURCE_CELL_DEP, SOURCE_GROUP_OUTPUT, SOURCE_HEADER_DEP,
+    SOURCE_INPUT, SOURCE_OUTPUT, SYSCALL_INDEX_OUT_OF_BOUND,
+};
+use ckb_testkit::assert_result_eq;
+use ckb_testkit::ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, Capacity, TransactionBuilder, TransactionView},
+    packed::{CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use ckb_testkit::util::hardfork::HARDFORK_DELAY_WINDOW;
+
+/// Verify the test scripts of `util::scripts` against a node, on both VM versions.
+///
+/// Each script runs as the type script of an output, via `ScriptHashType::Data` on VM0 and via
+/// `ScriptHashType::Data1` on VM1. The script cycles are the transaction cycles minus the cycles
+/// of the same transaction without the type script, and should equal
+/// `TestScript::expected_cycles`. A failing script should exit with the expected error.
+pub struct VMTestScripts;
+
+const DEPLOYED_SCRIPTS: [TestScript; 6] = [
+    TestScript::ExitCode,
+    TestScript::CycleBurner,
+    TestScript::LoadCell,
+    TestScript::LoadHeader,
+    TestScript::LoadWitness,
+    TestScript::MemoryGrowth,
+];
+
+impl Case for VMTestScripts {
+    fn ckb_editions(&self) -> Vec<CkbEdition> {
+        vec![CkbEdition::Ckb2021]
+    }
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.read().unwrap().clone(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/short_epoch_2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: &mut Nodes) {
+        let node2021 = nodes.get_node("node2021");
+        // Make sure the VM1 is activated
+        node2021.mine_to_rfc_activation("0032", HARDFORK_DELAY_WINDOW as i64 + 1);
+
+        let mut deployer = Deployer::open(node2021);
+        for script in DEPLOYED_SCRIPTS.iter() {
+            script.deploy(node2021, &mut deployer);
+        }
+        // The transactions are verified but never committed, so they share the same input
+        node2021.mine(1);
+        let input = {
+            let tip_cellbase = node2021.get_tip_block().transaction(0).unwrap();
+            node2021
+                .get_cell_meta(OutPoint::new(tip_cellbase.hash(), 0))
+                .expect("tip cellbase should be live")
+        };
+        let base_tx = self.build_transaction(node2021, &deployer, &input, None);
+        let base_cycles = node2021.get_transaction_cycles(&base_tx);
+
+        for vm_version in [VmVersion::V0, VmVersion::V1].iter() {
+            for (script, args, expected_error) in self.cases_params() {
+                let type_ = script.script(*vm_version, args.clone());
+                let tx = self.build_transaction(node2021, &deployer, &input, Some(type_));
+                let expected_result = match expected_error {
+                    Some(error) => Err(error),
+                    None => Ok(script
+                        .expected_cycles(*vm_version, &args)
+                        .expect("cycles of a succeeding script")),
+                };
+                let actual_result = if expected_result.is_ok() {
+                    Ok(node2021.get_transaction_cycles(&tx) - base_cycles)
+                } else {
+                    // Dry run panics on failure, take the error via `send_transaction` instead
+                    node2021
+                        .rpc_client()
+                        .send_transaction_result(tx.data().into())
+                        .map(|_| 0)
+                        .map_err(|err| err.to_string())
+                };
+                assert_result_eq!(
+                    expected_result,
+                    actual_result,
+                    "script: {}, args: {:#x}, vm: {:?}, node.log_path: {}",
+                    script.name(),
+                    args,
+                    vm_version,
+                    node2021.log_path().to_string_lossy(),
+                );
+            }
+        }
+    }
+}
+
+impl VMTestScripts {
+    fn build_transaction(
+        &self,
+        node: &Node,
+        deployer: &Deployer,
+        input: &CellMeta,
+        type_: Option<Script>,
+    ) -> TransactionView {
+        let output = CellOutput::new_builder()
+            .lock(input.cell_output.lock())
+            .type_(type_.pack())
+            .build_exact_capacity(Capacity::zero())
+            .unwrap();
+        TransactionBuilder::default()
+            .input(CellInput::new(input.out_point.clone(), 0))
+            .output(output)
+            .output_data(Default::default())
+            .witness(Default::default())
+            .cell_dep(node.always_success_cell_dep())
+            .cell_deps(
+                DEPLOYED_SCRIPTS
+                    .iter()
+                    .map(|script| deployer.cell_dep(script.name())),
+            )
+            .header_dep(node.get_tip_block().hash())
+            .build()
+    }
+
+    // (script, args, expected error)
+    fn cases_params(&self) -> Vec<(TestScript, Bytes, Option<String>)> {
+        let error_code = |code: i8| Some(format!("error code {} ", code));
+        vec![
+            (TestScript::ExitCode, Bytes::new(), None),
+            (TestScript::ExitCode, exit_code_args(5), error_code(5)),
+            (TestScript::ExitCode, exit_code_args(-1), error_code(-1)),
+            (TestScript::CycleBurner, cycle_burner_args(0), None),
+            (TestScript::CycleBurner, cycle_burner_args(1), None),
+            (TestScript::CycleBurner, cycle_burner_args(1000), None),
+            (
+                TestScript::LoadCell,
+                load_syscall_args(SOURCE_INPUT, 0),
+                None,
+            ),
+            (
+                TestScript::LoadCell,
+                load_syscall_args(SOURCE_OUTPUT, 0),
+                None,
+            ),
+            (
+                TestScript::LoadCell,
+                load_syscall_args(SOURCE_CELL_DEP, 0),
+                None,
+            ),
+            (
+                TestScript::LoadCell,
+                load_syscall_args(SOURCE_CELL_DEP, 100),
+                error_code(SYSCALL_INDEX_OUT_OF_BOUND),
+            ),
+            (
+                TestScript::LoadHeader,
+                load_syscall_args(SOURCE_HEADER_DEP, 0),
+                None,
+            ),
+            (
+                TestScript::LoadHeader,
+                load_syscall_args(SOURCE_HEADER_DEP, 1),
+                error_code(SYSCALL_INDEX_OUT_OF_BOUND),
+            ),
+            (
+                TestScript::LoadWitness,
+                load_syscall_args(SOURCE_INPUT, 0),
+                None,
+            ),
+            (
+                TestScript::LoadWitness,
+                load_syscall_args(SOURCE_GROUP_OUTPUT, 0),
+                None,
+            ),
+            (
+                TestScript::LoadWitness,
+                load_syscall_args(SOURCE_INPUT, 1),
+                error_code(SYSCALL_INDEX_OUT_OF_BOUND),
+            ),
+            (TestScript::MemoryGrowth, memory_growth_args(0), None),
+            (TestScript::MemoryGrowth, memory_growth_args(100), None),
+            (
+                TestScript::MemoryGrowth,
+                memory_growth_args(MEMORY_GROWTH_MAX_PAGES),
+                None,
+            ),
+            (
+                TestScript::MemoryGrowth,
+                memory_growth_args(MEMORY_GROWTH_MAX_PAGES + 1),
+                Some(ERROR_OUT_OF_BOUND.to_string()),
+            ),
+        ]
+    }
+}
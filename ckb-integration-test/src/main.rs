@@ -3,17 +3,30 @@ pub mod prelude;
 pub mod testdata;
 pub mod util;
 
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+#[cfg(test)]
+mod tests;
+
+use crate::util::binaries::{BinaryRegistry, CkbBinary, CkbEdition};
+use ckb_testkit::LogFormat;
+use clap::{App, Arg, ArgMatches, SubCommand};
 use lazy_static::lazy_static;
 use std::env;
 use std::path::PathBuf;
 use std::process::exit;
 use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
 
-// TODO Create a shortcut for CKB2019/CKB2021
 lazy_static! {
+    // The binaries the running case uses, bound to one of the registered binaries of each
+    // edition
     pub static ref CKB2019: RwLock<PathBuf> = RwLock::new(PathBuf::new());
     pub static ref CKB2021: RwLock<PathBuf> = RwLock::new(PathBuf::new());
+    pub static ref BINARY_REGISTRY: RwLock<BinaryRegistry> = RwLock::new(BinaryRegistry::default());
+    // The directory holding the per-case log files, `<RUN_DIR>/<case_name>/`
+    pub static ref RUN_DIR: RwLock<PathBuf> = RwLock::new(PathBuf::new());
+    pub static ref LOG_FORMAT: RwLock<LogFormat> = RwLock::new(LogFormat::Text);
+    // Keep the node working dirs after the cases finished
+    pub static ref KEEP_ARTIFACTS: RwLock<bool> = RwLock::new(false);
 }
 
 fn filter_cases(arg_matches: &ArgMatches) -> Vec<Box<dyn case::Case>> {
@@ -41,8 +54,9 @@ fn main() {
     match matches.subcommand() {
         ("run", Some(arg_matches)) => {
             crate::init_ckb_binaries(&arg_matches);
+            crate::init_run_dir(arg_matches);
             for case in filter_cases(&arg_matches) {
-                crate::case::run_case(case);
+                crate::case::run_case_matrix(case);
             }
         }
         ("generate-testdata", Some(arg_matches)) => {
@@ -101,6 +115,24 @@ fn clap_app() -> App<'static, 'static> {
                         .value_name("PATH")
                         .help("Path to ckb2021 executable"),
                 )
+                .arg(
+                    Arg::with_name("ckb")
+                        .required(false)
+                        .long("ckb")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .value_name("NAME=PATH")
+                        .help("Register a ckb executable, the edition is detected via `ckb --version`. Cases run against every combination of the registered executables of the editions they need"),
+                )
+                .arg(
+                    Arg::with_name("ckb-config")
+                        .required(false)
+                        .long("ckb-config")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .help("Register the ckb executables listed in the [ckb] table of a TOML file, as `NAME = PATH`"),
+                )
                 .arg(
                     Arg::with_name("cases")
                         .required(false)
@@ -110,6 +142,30 @@ fn clap_app() -> App<'static, 'static> {
                         .value_name("CASE_NAME")
                         .help("Only run specified cases. Run all cases if this parameter is not setting"),
                 )
+                .arg(
+                    Arg::with_name("run-dir")
+                        .required(false)
+                        .long("run-dir")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .help("Directory to write the per-case log files and node working dirs into. Use a new directory under $CKB_INTEGRATION_TEST_TMP or the system temporary directory if this parameter is not setting"),
+                )
+                .arg(
+                    Arg::with_name("log-format")
+                        .required(false)
+                        .long("log-format")
+                        .takes_value(true)
+                        .possible_values(&["text", "json"])
+                        .default_value("text")
+                        .help("Format of the per-case log files"),
+                )
+                .arg(
+                    Arg::with_name("keep-artifacts")
+                        .required(false)
+                        .long("keep-artifacts")
+                        .takes_value(false)
+                        .help("Keep the node working dirs of the finished cases. By default, they are removed, and are packed into a tarball beforehand if the case failed"),
+                )
         )
         .subcommand(
             SubCommand::with_name("generate-testdata")
@@ -155,27 +211,100 @@ fn init_logger(clap_matches: &ArgMatches) -> ckb_logger_service::LoggerInitGuard
 }
 
 fn init_ckb_binaries(matches: &ArgMatches) {
-    let ckb2019 = {
-        if let Some(ckb2019_str) = matches.value_of("ckb2019") {
-            PathBuf::from(ckb2019_str)
-        } else {
-            // Use default ckb_v0.43.2 binary according to the running system
-            match os_info::get().os_type() {
-                os_info::Type::Macos => PathBuf::from("testdata/bin/ckb_v0.43.2-macOS"),
-                os_info::Type::Windows => PathBuf::from("testdata/bin/ckb_v0.43.2-Windows"),
-                _ => PathBuf::from("testdata/bin/ckb_v0.43.2-Linux"),
-            }
+    let mut registry = BinaryRegistry::default();
+    if let Some(config_path) = matches.value_of("ckb-config") {
+        registry
+            .load_config(&PathBuf::from(config_path))
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+    if let Some(values) = matches.values_of("ckb") {
+        for value in values {
+            let (name, path) = match value.split_once('=') {
+                Some((name, path)) if !name.is_empty() && !path.is_empty() => (name, path),
+                _ => panic!("invalid --ckb \"{}\", expect NAME=PATH", value),
+            };
+            let binary = CkbBinary::new(name, absolutize(PathBuf::from(path)), None)
+                .unwrap_or_else(|err| panic!("{}", err));
+            registry
+                .register(binary)
+                .unwrap_or_else(|err| panic!("{}", err));
+        }
+    }
+    if let Some(ckb2021_str) = matches.value_of("ckb2021") {
+        let binary = CkbBinary::new(
+            "ckb2021",
+            absolutize(PathBuf::from(ckb2021_str)),
+            Some(CkbEdition::Ckb2021),
+        )
+        .unwrap_or_else(|err| panic!("--ckb2021 {}", err));
+        registry
+            .register(binary)
+            .unwrap_or_else(|err| panic!("{}", err));
+    }
+    let ckb2019 = if let Some(ckb2019_str) = matches.value_of("ckb2019") {
+        Some(PathBuf::from(ckb2019_str))
+    } else if registry.binaries_of(CkbEdition::Ckb2019).is_empty() {
+        // Use default ckb_v0.43.2 binary according to the running system
+        match os_info::get().os_type() {
+            os_info::Type::Macos => Some(PathBuf::from("testdata/bin/ckb_v0.43.2-macOS")),
+            os_info::Type::Windows => Some(PathBuf::from("testdata/bin/ckb_v0.43.2-Windows")),
+            _ => Some(PathBuf::from("testdata/bin/ckb_v0.43.2-Linux")),
         }
+    } else {
+        None
     };
-    let ckb2021 = value_t_or_exit!(matches, "ckb2021", PathBuf);
-    if !ckb2019.exists() || !ckb2019.is_file() {
-        panic!("--ckb2019 points to non-executable")
+    if let Some(ckb2019) = ckb2019 {
+        let binary = CkbBinary::new("ckb2019", absolutize(ckb2019), Some(CkbEdition::Ckb2019))
+            .unwrap_or_else(|err| panic!("--ckb2019 {}", err));
+        registry
+            .register(binary)
+            .unwrap_or_else(|err| panic!("{}", err));
     }
-    if !ckb2021.exists() || !ckb2021.is_file() {
-        panic!("--ckb2021 points to non-executable")
+    if registry.binaries_of(CkbEdition::Ckb2021).is_empty() {
+        panic!("no ckb2021 executable, specify --ckb2021, --ckb or --ckb-config");
     }
-    *CKB2019.write().unwrap() = absolutize(ckb2019);
-    *CKB2021.write().unwrap() = absolutize(ckb2021);
+    *CKB2019.write().unwrap() = registry.binaries_of(CkbEdition::Ckb2019)[0]
+        .path()
+        .to_path_buf();
+    *CKB2021.write().unwrap() = registry.binaries_of(CkbEdition::Ckb2021)[0]
+        .path()
+        .to_path_buf();
+    for binary in registry.binaries() {
+        println!(
+            "registered ckb binary {} ({:?}): {}",
+            binary.name(),
+            binary.edition(),
+            binary.path().display()
+        );
+    }
+    *BINARY_REGISTRY.write().unwrap() = registry;
+}
+
+fn init_run_dir(matches: &ArgMatches) {
+    let run_dir = if let Some(run_dir_str) = matches.value_of("run-dir") {
+        PathBuf::from(run_dir_str)
+    } else {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("timestamp")
+            .as_secs();
+        let root = env::var_os("CKB_INTEGRATION_TEST_TMP")
+            .map(PathBuf::from)
+            .unwrap_or_else(env::temp_dir);
+        root.join(format!("ckb-integration-test-{}", timestamp))
+    };
+    let run_dir = absolutize(run_dir);
+    let log_format = match matches.value_of("log-format") {
+        Some("json") => LogFormat::Json,
+        _ => LogFormat::Text,
+    };
+    println!("writing case logs into {}", run_dir.display());
+    // `ckb_testkit::util::temp_path` creates the node working dirs under
+    // `$CKB_INTEGRATION_TEST_TMP/<case_name>/`
+    env::set_var("CKB_INTEGRATION_TEST_TMP", &run_dir);
+    *RUN_DIR.write().unwrap() = run_dir;
+    *LOG_FORMAT.write().unwrap() = log_format;
+    *KEEP_ARTIFACTS.write().unwrap() = matches.is_present("keep-artifacts");
 }
 
 fn absolutize(path: PathBuf) -> PathBuf {
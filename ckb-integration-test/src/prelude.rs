@@ -1,3 +1,4 @@
 pub use crate::case::{Case, CaseOptions};
+pub use crate::util::binaries::CkbEdition;
 pub use crate::{CKB2019, CKB2021};
 pub use ckb_testkit::{Node, NodeOptions, Nodes};
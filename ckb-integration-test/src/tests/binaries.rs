@@ -0,0 +1,141 @@
+use crate::util::binaries::{detect_edition, BinaryRegistry, CkbBinary, CkbEdition};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::sync::Mutex;
+
+lazy_static::lazy_static! {
+    // Executing a script while another thread holds it open for writing fails with
+    // "Text file busy", so the fake binaries are written and executed one at a time
+    static ref FAKE_BINARY_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// Register a fake ckb binary printing `version` on `--version`
+fn fake_binary(
+    dir: &Path,
+    name: &str,
+    version: &str,
+    edition: Option<CkbEdition>,
+) -> Result<CkbBinary, String> {
+    let _guard = FAKE_BINARY_LOCK.lock().unwrap();
+    let path = dir.join(name);
+    fs::write(&path, format!("#!/bin/sh\necho \"{}\"\n", version)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    CkbBinary::new(name, path, edition)
+}
+
+fn registry(dir: &Path, versions: &[(&str, &str)]) -> BinaryRegistry {
+    let mut registry = BinaryRegistry::default();
+    for (name, version) in versions {
+        let binary = fake_binary(dir, name, version, None).unwrap();
+        registry.register(binary).unwrap();
+    }
+    registry
+}
+
+#[test]
+fn test_detect_edition() {
+    assert_eq!(
+        detect_edition("ckb 0.43.2 (7d8c4d3 2021-06-17)"),
+        Some(CkbEdition::Ckb2019)
+    );
+    assert_eq!(
+        detect_edition("ckb 0.99.0 (5bd3e9b 2021-08-04)"),
+        Some(CkbEdition::Ckb2019)
+    );
+    assert_eq!(
+        detect_edition("ckb 0.100.0 (8b0e4a5 2021-08-19)"),
+        Some(CkbEdition::Ckb2021)
+    );
+    assert_eq!(detect_edition("ckb 1.0.0"), Some(CkbEdition::Ckb2021));
+    assert_eq!(detect_edition(""), None);
+    assert_eq!(detect_edition("ckb"), None);
+    assert_eq!(detect_edition("ckb 0"), None);
+    assert_eq!(detect_edition("ckb x.y.z"), None);
+}
+
+#[test]
+fn test_ckb_binary_new() {
+    let dir = tempfile::tempdir().unwrap();
+    let binary = fake_binary(dir.path(), "v0.100.0", "ckb 0.100.0 (8b0e4a5)", None).unwrap();
+    assert_eq!(binary.edition(), CkbEdition::Ckb2021);
+    assert_eq!(binary.version().trim(), "ckb 0.100.0 (8b0e4a5)");
+
+    // The given edition takes precedence over the detected one
+    let binary = fake_binary(
+        dir.path(),
+        "v0.100.0-as-2019",
+        "ckb 0.100.0 (8b0e4a5)",
+        Some(CkbEdition::Ckb2019),
+    )
+    .unwrap();
+    assert_eq!(binary.edition(), CkbEdition::Ckb2019);
+
+    assert!(fake_binary(dir.path(), "unknown", "unknown", None).is_err());
+    assert!(CkbBinary::new("missing", dir.path().join("missing"), None).is_err());
+}
+
+#[test]
+fn test_registry_rejects_duplicated_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut registry = registry(dir.path(), &[("v0.43.2", "ckb 0.43.2")]);
+    let duplicated = fake_binary(dir.path(), "v0.43.2", "ckb 0.43.2", None).unwrap();
+    assert!(registry.register(duplicated).is_err());
+}
+
+#[test]
+fn test_combinations() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(
+        dir.path(),
+        &[
+            ("v0.43.2", "ckb 0.43.2"),
+            ("v0.44.0", "ckb 0.44.0"),
+            ("v0.100.0", "ckb 0.100.0"),
+            ("v0.101.0", "ckb 0.101.0"),
+        ],
+    );
+
+    let combinations = registry.combinations(&[CkbEdition::Ckb2019, CkbEdition::Ckb2021]);
+    let names = combinations
+        .iter()
+        .map(|combination| combination.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(
+        names,
+        vec![
+            "v0.43.2+v0.100.0",
+            "v0.43.2+v0.101.0",
+            "v0.44.0+v0.100.0",
+            "v0.44.0+v0.101.0",
+        ]
+    );
+
+    // The edition not required is bound to its first binary, and omitted from the name
+    let combinations = registry.combinations(&[CkbEdition::Ckb2021]);
+    let names = combinations
+        .iter()
+        .map(|combination| combination.to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["v0.100.0", "v0.101.0"]);
+    for combination in combinations {
+        assert_eq!(combination.ckb2019.unwrap().name(), "v0.43.2");
+    }
+}
+
+#[test]
+fn test_combinations_with_missing_edition() {
+    let dir = tempfile::tempdir().unwrap();
+    let registry = registry(dir.path(), &[("v0.100.0", "ckb 0.100.0")]);
+
+    // A required edition without binaries expands to nothing
+    assert!(registry
+        .combinations(&[CkbEdition::Ckb2019, CkbEdition::Ckb2021])
+        .is_empty());
+
+    // A missing edition not required is left unbound
+    let combinations = registry.combinations(&[CkbEdition::Ckb2021]);
+    assert_eq!(combinations.len(), 1);
+    assert!(combinations[0].ckb2019.is_none());
+    assert_eq!(combinations[0].to_string(), "v0.100.0");
+}
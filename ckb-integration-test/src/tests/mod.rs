@@ -0,0 +1,2 @@
+mod binaries;
+mod scripts;
@@ -0,0 +1,52 @@
+use crate::util::scripts::{
+    cycle_burner_args, load_syscall_args, memory_growth_args, TestScript, VmVersion,
+    MEMORY_GROWTH_MAX_PAGES, SOURCE_INPUT,
+};
+use std::collections::HashSet;
+
+#[test]
+fn test_test_scripts_are_distinct() {
+    let scripts = TestScript::all();
+    let names = scripts
+        .iter()
+        .map(|script| script.name())
+        .collect::<HashSet<_>>();
+    let data_hashes = scripts
+        .iter()
+        .map(|script| script.data_hash())
+        .collect::<HashSet<_>>();
+    assert_eq!(names.len(), scripts.len());
+    assert_eq!(data_hashes.len(), scripts.len());
+}
+
+#[test]
+fn test_test_scripts_expected_cycles() {
+    let burner = |vm_version, rounds| {
+        TestScript::CycleBurner
+            .expected_cycles(vm_version, &cycle_burner_args(rounds))
+            .unwrap()
+    };
+    for vm_version in [VmVersion::V0, VmVersion::V1].iter() {
+        assert_eq!(burner(*vm_version, 1000) - burner(*vm_version, 1), 8 * 999);
+    }
+    assert_eq!(burner(VmVersion::V1, 0), burner(VmVersion::V0, 0) + 2);
+
+    let memory_growth =
+        |pages| TestScript::MemoryGrowth.expected_cycles(VmVersion::V0, &memory_growth_args(pages));
+    assert!(memory_growth(MEMORY_GROWTH_MAX_PAGES).is_some());
+    assert_eq!(memory_growth(MEMORY_GROWTH_MAX_PAGES + 1), None);
+
+    let load_args = load_syscall_args(SOURCE_INPUT, 0);
+    assert!(TestScript::LoadCell
+        .expected_cycles(VmVersion::V0, &load_args)
+        .is_some());
+    assert_eq!(
+        TestScript::LoadCell.expected_cycles(VmVersion::V0, &load_args[..8]),
+        None
+    );
+    // The cycles of the exec scripts depend on the exec params
+    assert_eq!(
+        TestScript::ExecCaller.expected_cycles(VmVersion::V0, &[]),
+        None
+    );
+}
@@ -0,0 +1,109 @@
+//! The retention policy of the case dirs. A case dir, `<run_dir>/<case_name>/`, holds the case
+//! log, the case report and the working dirs of the nodes started by the case.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The tarball of the node working dirs of a failed case, under the case dir.
+pub const ARTIFACTS_TARBALL: &str = "artifacts.tar.gz";
+
+/// Clean up the node working dirs under `case_dir` after the case finished and its nodes
+/// stopped.
+///
+/// The working dirs of a passed case are removed. The working dirs of a failed case are packed
+/// into `artifacts.tar.gz`, excluding the indexer data, and then removed. Nothing is removed if
+/// `keep_artifacts` is true.
+pub fn collect_case_artifacts(case_dir: &Path, passed: bool, keep_artifacts: bool) {
+    let working_dirs = working_dirs(case_dir);
+    if working_dirs.is_empty() {
+        return;
+    }
+    if !passed && !pack_working_dirs(case_dir, &working_dirs) {
+        // Keep the raw working dirs as the artifacts
+        return;
+    }
+    if keep_artifacts {
+        return;
+    }
+    for working_dir in working_dirs {
+        if let Err(err) = fs::remove_dir_all(&working_dir) {
+            ckb_testkit::warn!("failed to remove {}, error: {}", working_dir.display(), err);
+        }
+    }
+    remove_dangling_links(case_dir);
+}
+
+fn working_dirs(case_dir: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(case_dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut working_dirs = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_type()
+                .map(|file_type| file_type.is_dir())
+                .unwrap_or(false)
+        })
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    working_dirs.sort();
+    working_dirs
+}
+
+// Pack the working dirs via `tar`, return whether it succeeded
+fn pack_working_dirs(case_dir: &Path, working_dirs: &[PathBuf]) -> bool {
+    let tarball = case_dir.join(ARTIFACTS_TARBALL);
+    let status = Command::new("tar")
+        .arg("--exclude=indexer")
+        .arg("-czf")
+        .arg(&tarball)
+        .arg("-C")
+        .arg(case_dir)
+        .args(
+            working_dirs
+                .iter()
+                .filter_map(|working_dir| working_dir.file_name()),
+        )
+        .status();
+    match status {
+        Ok(status) if status.success() => {
+            ckb_testkit::info!("collected the case artifacts into {}", tarball.display());
+            true
+        }
+        Ok(status) => {
+            ckb_testkit::error!(
+                "failed to pack the case artifacts into {}, tar exited with {}",
+                tarball.display(),
+                status
+            );
+            false
+        }
+        Err(err) => {
+            ckb_testkit::error!(
+                "failed to pack the case artifacts into {}, error: {}",
+                tarball.display(),
+                err
+            );
+            false
+        }
+    }
+}
+
+// Remove the links to the removed node logs, e.g. `<node_name>.run.log`
+fn remove_dangling_links(case_dir: &Path) {
+    if let Ok(entries) = fs::read_dir(case_dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let is_link = entry
+                .file_type()
+                .map(|file_type| file_type.is_symlink())
+                .unwrap_or(false);
+            if is_link && !path.exists() {
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! The registry of the ckb binaries to run the cases against.
+//!
+//! Binaries are registered via `--ckb2019 PATH`, `--ckb2021 PATH`, repeated `--ckb NAME=PATH` and
+//! `--ckb-config PATH`, a TOML file mapping names to paths:
+//!
+//! ```toml
+//! [ckb]
+//! "v0.43.2" = "testdata/bin/ckb_v0.43.2-Linux"
+//! "v0.100.0" = "/usr/local/bin/ckb-v0.100.0"
+//! ```
+//!
+//! A case declares the editions it needs via `Case::ckb_editions`, and the runner expands it
+//! across every combination of the registered binaries of these editions.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The first minor version of the ckb2021 releases, i.e. v0.100.0.
+const CKB2021_MINOR_VERSION: u64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CkbEdition {
+    /// The releases before the 2021 hardfork, bound to `CKB2019`
+    Ckb2019,
+    /// The releases supporting the 2021 hardfork, bound to `CKB2021`
+    Ckb2021,
+}
+
+#[derive(Debug, Clone)]
+pub struct CkbBinary {
+    name: String,
+    path: PathBuf,
+    // The output of `ckb --version`
+    version: String,
+    edition: CkbEdition,
+}
+
+impl CkbBinary {
+    /// Register `path` as `name`. The edition is detected via `ckb --version` unless given.
+    pub fn new(name: &str, path: PathBuf, edition: Option<CkbEdition>) -> Result<Self, String> {
+        if !path.is_file() {
+            return Err(format!(
+                "ckb binary {} points to non-executable {}",
+                name,
+                path.display()
+            ));
+        }
+        let output = Command::new(&path)
+            .arg("--version")
+            .output()
+            .map_err(|err| format!("failed to execute {}, error: {}", path.display(), err))?;
+        let version = String::from_utf8_lossy(&output.stdout).to_string();
+        let edition = match edition.or_else(|| detect_edition(&version)) {
+            Some(edition) => edition,
+            None => {
+                return Err(format!(
+                "unknown version \"{}\" of ckb binary {}, register it via --ckb2019 or --ckb2021",
+                version.trim(),
+                name
+            ))
+            }
+        };
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            version,
+            edition,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    pub fn edition(&self) -> CkbEdition {
+        self.edition
+    }
+}
+
+// "ckb 0.100.0 (8b0e4a5 2021-08-19)" => 0.100 => ckb2021
+pub(crate) fn detect_edition(version: &str) -> Option<CkbEdition> {
+    let version = version.split_whitespace().nth(1)?;
+    let mut numbers = version.split('.');
+    let major = numbers.next()?.parse::<u64>().ok()?;
+    let minor = numbers.next()?.parse::<u64>().ok()?;
+    if major > 0 || minor >= CKB2021_MINOR_VERSION {
+        Some(CkbEdition::Ckb2021)
+    } else {
+        Some(CkbEdition::Ckb2019)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BinaryRegistry {
+    binaries: Vec<CkbBinary>,
+}
+
+impl BinaryRegistry {
+    pub fn register(&mut self, binary: CkbBinary) -> Result<(), String> {
+        if self.binaries.iter().any(|b| b.name == binary.name) {
+            return Err(format!("duplicated ckb binary name \"{}\"", binary.name));
+        }
+        self.binaries.push(binary);
+        Ok(())
+    }
+
+    /// Register the binaries listed in the `[ckb]` table of the TOML file `config_path`.
+    /// Relative paths are relative to the current directory.
+    pub fn load_config(&mut self, config_path: &Path) -> Result<(), String> {
+        let content = fs::read_to_string(config_path).map_err(|err| {
+            format!(
+                "failed to read ckb config {}, error: {}",
+                config_path.display(),
+                err
+            )
+        })?;
+        let config = content.parse::<toml::Value>().map_err(|err| {
+            format!(
+                "failed to parse ckb config {}, error: {}",
+                config_path.display(),
+                err
+            )
+        })?;
+        let table = config
+            .get("ckb")
+            .and_then(|ckb| ckb.as_table())
+            .ok_or_else(|| format!("no [ckb] table in {}", config_path.display()))?;
+        for (name, path) in table {
+            let path = path.as_str().ok_or_else(|| {
+                format!(
+                    "expect the path of ckb binary {} to be a string in {}",
+                    name,
+                    config_path.display()
+                )
+            })?;
+            self.register(CkbBinary::new(name, crate::absolutize(path.into()), None)?)?;
+        }
+        Ok(())
+    }
+
+    pub fn binaries(&self) -> &[CkbBinary] {
+        &self.binaries
+    }
+
+    pub fn binaries_of(&self, edition: CkbEdition) -> Vec<&CkbBinary> {
+        self.binaries
+            .iter()
+            .filter(|binary| binary.edition == edition)
+            .collect()
+    }
+
+    pub fn find_by_path(&self, path: &Path) -> Option<&CkbBinary> {
+        self.binaries.iter().find(|binary| binary.path == path)
+    }
+
+    /// Expand `editions` into the combinations of the registered binaries. The edition not
+    /// required is bound to its first registered binary, if any. Return nothing if a required
+    /// edition has no binaries.
+    pub fn combinations(&self, editions: &[CkbEdition]) -> Vec<BinaryCombination> {
+        let choices = |edition: CkbEdition| -> Vec<Option<CkbBinary>> {
+            let binaries = self.binaries_of(edition);
+            if editions.contains(&edition) {
+                binaries.into_iter().cloned().map(Some).collect()
+            } else {
+                vec![binaries.first().cloned().cloned()]
+            }
+        };
+        let mut combinations = Vec::new();
+        for ckb2019 in choices(CkbEdition::Ckb2019) {
+            for ckb2021 in choices(CkbEdition::Ckb2021) {
+                combinations.push(BinaryCombination {
+                    editions: editions.to_vec(),
+                    ckb2019: ckb2019.clone(),
+                    ckb2021,
+                });
+            }
+        }
+        combinations
+    }
+}
+
+/// The binaries bound to `CKB2019` and `CKB2021` for one run of a case.
+#[derive(Debug, Clone)]
+pub struct BinaryCombination {
+    editions: Vec<CkbEdition>,
+    pub ckb2019: Option<CkbBinary>,
+    pub ckb2021: Option<CkbBinary>,
+}
+
+// Join the names of the required binaries, e.g. "v0.43.2+v0.100.0"
+impl fmt::Display for BinaryCombination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = [
+            (CkbEdition::Ckb2019, &self.ckb2019),
+            (CkbEdition::Ckb2021, &self.ckb2021),
+        ]
+        .iter()
+        .filter(|(edition, _)| self.editions.contains(edition))
+        .filter_map(|(_, binary)| binary.as_ref().map(|binary| binary.name()))
+        .collect::<Vec<_>>();
+        write!(f, "{}", names.join("+"))
+    }
+}
@@ -1,21 +1,107 @@
-use ckb_testkit::ckb_types::core::cell::CellMeta;
-use ckb_testkit::ckb_types::core::TransactionBuilder;
-use ckb_testkit::ckb_types::packed::{Bytes, CellInput, CellOutput, OutPoint};
+use ckb_testkit::ckb_jsonrpc_types as json_types;
+use ckb_testkit::ckb_types::core::{Capacity, DepType, ScriptHashType};
+use ckb_testkit::ckb_types::packed::{
+    Byte32, Bytes, CellDep, CellInput, CellOutput, OutPoint, OutPointVec, Script,
+};
 use ckb_testkit::ckb_types::prelude::*;
-use ckb_testkit::{BuildInstruction, Node};
-use std::collections::HashMap;
+use ckb_testkit::ckb_types::H256;
+use ckb_testkit::{BuildInstruction, Node, TxBuilder, User, TYPE_ID_CODE_HASH};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
 
+/// The registry file name inside the node working directory.
+pub const REGISTRY_FILE_NAME: &str = "deployed_cells.json";
+
+/// A deployed cell recorded in the registry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeployedCell {
+    pub out_point: json_types::OutPoint,
+    pub output: json_types::CellOutput,
+    pub data_hash: H256,
+    pub dep_type: json_types::DepType,
+}
+
+impl DeployedCell {
+    pub fn out_point(&self) -> OutPoint {
+        self.out_point.clone().into()
+    }
+
+    pub fn output(&self) -> CellOutput {
+        self.output.clone().into()
+    }
+
+    pub fn data_hash(&self) -> Byte32 {
+        self.data_hash.pack()
+    }
+
+    pub fn cell_dep(&self) -> CellDep {
+        let dep_type: DepType = self.dep_type.clone().into();
+        CellDep::new_builder()
+            .out_point(self.out_point())
+            .dep_type(dep_type.into())
+            .build()
+    }
+
+    /// Return the script referencing the deployed code, via the type hash if the cell is typed,
+    /// otherwise via the data hash.
+    pub fn script(&self) -> Script {
+        match self.output().type_().to_opt() {
+            Some(type_) => Script::new_builder()
+                .code_hash(type_.calc_script_hash())
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+            None => Script::new_builder()
+                .code_hash(self.data_hash())
+                .hash_type(ScriptHashType::Data.into())
+                .build(),
+        }
+    }
+}
+
+/// Deployer deploys cells with always-success inputs and records them by name.
+///
+/// The registry opened via `Deployer::open` is persisted to `REGISTRY_FILE_NAME` inside the
+/// node working directory after every change, so that it survives node restarts.
 #[derive(Debug, Clone, Default)]
 pub struct Deployer {
-    // #{ name => cell-meta }
-    deployed_cells: HashMap<String, CellMeta>,
+    // #{ name => deployed cell }
+    deployed_cells: BTreeMap<String, DeployedCell>,
+    registry_path: Option<PathBuf>,
 }
 
 impl Deployer {
+    /// Create an in-memory deployer.
     pub fn new() -> Deployer {
         Default::default()
     }
 
+    /// Open the registry persisted inside the node working directory, create an empty one if
+    /// not exists.
+    pub fn open(node: &Node) -> Deployer {
+        let registry_path = node.working_dir().join(REGISTRY_FILE_NAME);
+        let deployed_cells = if registry_path.exists() {
+            let content = fs::read_to_string(&registry_path).unwrap_or_else(|err| {
+                panic!("failed to read {}, error: {}", registry_path.display(), err)
+            });
+            serde_json::from_str(&content).unwrap_or_else(|err| {
+                panic!(
+                    "failed to parse {}, error: {}",
+                    registry_path.display(),
+                    err
+                )
+            })
+        } else {
+            BTreeMap::new()
+        };
+        Deployer {
+            deployed_cells,
+            registry_path: Some(registry_path),
+        }
+    }
+
+    /// Deploy a cell with `output` and `output_data`.
     pub fn deploy<S: ToString>(
         &mut self,
         node: &Node,
@@ -24,42 +110,187 @@ impl Deployer {
         output_data: Bytes,
     ) {
         let cell_name = cell_name.to_string();
-        ckb_testkit::debug!(
-            "[Node {}] deploying cell \"{}\"",
-            node.node_name(),
-            cell_name
+        self.assert_not_deployed(&cell_name);
+        self.commit(node, cell_name, None, output, output_data, DepType::Code);
+    }
+
+    /// Deploy a cell with `output` and `output_data`, typed with a type-id script, so that it
+    /// can be referenced via `ScriptHashType::Type` and be upgraded later.
+    pub fn deploy_type_id<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        output: CellOutput,
+        output_data: Bytes,
+    ) {
+        let cell_name = cell_name.to_string();
+        self.assert_not_deployed(&cell_name);
+        // The type-id args are calculated from the first input, which is unknown yet
+        let output = output
+            .as_builder()
+            .type_(Some(type_id_script(Default::default())).pack())
+            .build();
+        self.commit(node, cell_name, None, output, output_data, DepType::Code);
+    }
+
+    /// Upgrade the type-id typed cell `cell_name` with `output_data`, the new cell keeps the same
+    /// lock and type-id.
+    pub fn upgrade<S: ToString>(&mut self, node: &Node, cell_name: S, output_data: Bytes) {
+        let cell_name = cell_name.to_string();
+        let deployed = self.get_cell(&cell_name);
+        let output = deployed.output();
+        assert!(
+            output
+                .type_()
+                .to_opt()
+                .map(|type_| type_.code_hash() == TYPE_ID_CODE_HASH.pack())
+                .unwrap_or(false),
+            "cell \"{}\" is not typed with type-id, cannot be upgraded",
+            cell_name,
+        );
+        self.commit(
+            node,
+            cell_name,
+            Some(deployed.out_point()),
+            output,
+            output_data,
+            DepType::Code,
+        );
+    }
+
+    /// Deploy a dep group cell which contains the out-points of deployed cells `members`.
+    pub fn deploy_dep_group<S: ToString>(&mut self, node: &Node, cell_name: S, members: &[&str]) {
+        let cell_name = cell_name.to_string();
+        self.assert_not_deployed(&cell_name);
+        let output_data = OutPointVec::new_builder()
+            .set(
+                members
+                    .iter()
+                    .map(|member| self.get_out_point(member))
+                    .collect(),
+            )
+            .build()
+            .as_bytes()
+            .pack();
+        let output = CellOutput::new_builder()
+            .lock(node.always_success_script())
+            .build();
+        self.commit(
+            node,
+            cell_name,
+            None,
+            output,
+            output_data,
+            DepType::DepGroup,
         );
+    }
+
+    pub fn get_out_point<S: ToString>(&self, cell_name: S) -> OutPoint {
+        self.get_cell(cell_name).out_point()
+    }
+
+    pub fn get_cell<S: ToString>(&self, cell_name: S) -> DeployedCell {
+        let cell_name = cell_name.to_string();
+        self.deployed_cells
+            .get(&cell_name)
+            .unwrap_or_else(|| panic!("deployer cannot find cell {}", cell_name))
+            .clone()
+    }
+
+    pub fn get_cells(&self) -> BTreeMap<String, DeployedCell> {
+        self.deployed_cells.clone()
+    }
+
+    /// Return the cell dep of `cell_name`, `DepType::DepGroup` for dep group cells.
+    pub fn cell_dep<S: ToString>(&self, cell_name: S) -> CellDep {
+        self.get_cell(cell_name).cell_dep()
+    }
+
+    /// Return the script referencing the code of `cell_name`, see `DeployedCell::script`.
+    pub fn script<S: ToString>(&self, cell_name: S) -> Script {
+        self.get_cell(cell_name).script()
+    }
+
+    fn assert_not_deployed(&self, cell_name: &str) {
         assert!(
-            !self.deployed_cells.contains_key(&cell_name.to_string()),
+            !self.deployed_cells.contains_key(cell_name),
             "cell \"{}\" already deployed",
             cell_name,
         );
+    }
+
+    // Commit a transaction creating the cell `cell_name` as the first output, consuming
+    // `consumed` first if any and paid by always-success cells. The output capacity is raised
+    // to the occupied capacity. For type-id cells, the type-id args are kept on upgrading and
+    // calculated on creating.
+    fn commit(
+        &mut self,
+        node: &Node,
+        cell_name: String,
+        consumed: Option<OutPoint>,
+        output: CellOutput,
+        output_data: Bytes,
+        dep_type: DepType,
+    ) {
+        ckb_testkit::debug!(
+            "[Node {}] deploying cell \"{}\"",
+            node.node_name(),
+            cell_name
+        );
 
-        // Pick inputs
-        let mut output_capacity: u64 = output.capacity().unpack();
-        let mut inputs = Vec::new();
-        for cell in node.get_spendable_always_success_cells() {
-            let capacity: u64 = cell.cell_output.capacity().unpack();
-            if output_capacity >= capacity {
-                output_capacity -= capacity;
-                inputs.push(cell);
-            } else {
-                inputs.push(cell);
-                break;
+        // Pick the first input, which the type-id is calculated from
+        let payer = User::new_always_success(node.genesis_block().clone());
+        let mut cells = node.get_spendable_always_success_cells();
+        let first_input = match consumed {
+            Some(out_point) => node
+                .get_cell_meta(out_point.clone())
+                .unwrap_or_else(|| panic!("deployed cell {} should be live", out_point)),
+            None => {
+                assert!(
+                    !cells.is_empty(),
+                    "insufficient always-success cells to deploy \"{}\"",
+                    cell_name,
+                );
+                cells.remove(0)
             }
-        }
+        };
+
+        let output = match output.type_().to_opt() {
+            Some(type_)
+                if type_.code_hash() == TYPE_ID_CODE_HASH.pack() && type_.args().is_empty() =>
+            {
+                let type_id = {
+                    let mut preimage = CellInput::new(first_input.out_point.clone(), 0)
+                        .as_slice()
+                        .to_vec();
+                    preimage.extend_from_slice(&0u64.to_le_bytes());
+                    CellOutput::calc_data_hash(&preimage)
+                };
+                output
+                    .as_builder()
+                    .type_(Some(type_id_script(type_id.as_bytes().pack())).pack())
+                    .build()
+            }
+            _ => output,
+        };
+
+        // Size the output after the type-id args are filled
+        let occupied = output
+            .occupied_capacity(Capacity::bytes(output_data.len()).unwrap())
+            .unwrap();
+        let capacity = Capacity::shannons(::std::cmp::max(
+            output.capacity().unpack(),
+            occupied.as_u64(),
+        ));
+        let output = output.as_builder().capacity(capacity.pack()).build();
 
         // Construct transaction
-        let tx = TransactionBuilder::default()
-            .inputs(
-                inputs
-                    .into_iter()
-                    .map(|input| CellInput::new(input.out_point, 0)),
-            )
-            .output(output)
-            .output_data(output_data)
-            .cell_dep(node.always_success_cell_dep())
-            .build();
+        let tx = TxBuilder::new(&payer)
+            .input(first_input, 0)
+            .cells(cells)
+            .output(output.clone(), output_data.raw_data())
+            .build()
+            .unwrap_or_else(|err| panic!("failed to deploy \"{}\", error: {}", cell_name, err));
 
         // Make sure transaction committed
         let tip_number = node.get_tip_block_number();
@@ -78,31 +309,36 @@ impl Deployer {
         )
         .unwrap_or_else(|err| panic!("failed to deploy \"{}\", error: {}", cell_name, err));
 
-        // Save cell-meta inside deployer
-        {
-            let _ = node.indexer();
-        }
-        let out_point = OutPoint::new(tx.hash(), 0);
-        let cell_meta = node.get_cell_meta(out_point).expect(&format!(
-            "deployer should already committed tx {:#x}",
-            tx.hash()
-        ));
-        self.deployed_cells.insert(cell_name, cell_meta);
-    }
-
-    pub fn get_out_point<S: ToString>(&self, cell_name: S) -> OutPoint {
-        self.get_cell(cell_name).out_point
+        // Save the deployed cell inside deployer
+        let deployed = DeployedCell {
+            out_point: OutPoint::new(tx.hash(), 0).into(),
+            output: output.into(),
+            data_hash: CellOutput::calc_data_hash(&output_data.raw_data()).unpack(),
+            dep_type: dep_type.into(),
+        };
+        self.deployed_cells.insert(cell_name, deployed);
+        self.save();
     }
 
-    pub fn get_cell<S: ToString>(&self, cell_name: S) -> CellMeta {
-        let cell_name = cell_name.to_string();
-        self.deployed_cells
-            .get(&cell_name)
-            .expect(&format!("deployer cannot find cell {}", cell_name))
-            .clone()
+    fn save(&self) {
+        if let Some(registry_path) = self.registry_path.as_ref() {
+            let content = serde_json::to_string_pretty(&self.deployed_cells)
+                .expect("serialize deployed cells");
+            fs::write(registry_path, content).unwrap_or_else(|err| {
+                panic!(
+                    "failed to write {}, error: {}",
+                    registry_path.display(),
+                    err
+                )
+            });
+        }
     }
+}
 
-    pub fn get_cells(&self) -> HashMap<String, CellMeta> {
-        self.deployed_cells.clone()
-    }
+fn type_id_script(args: Bytes) -> Script {
+    Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(args)
+        .build()
 }
@@ -1,4 +1,7 @@
+pub mod artifacts;
+pub mod binaries;
 pub mod deployer;
+pub mod scripts;
 pub mod shortcuts;
 
 pub use shortcuts::{v0_100, v0_43};
@@ -6,11 +9,9 @@ pub use shortcuts::{v0_100, v0_43};
 use ckb_testkit::ckb_types::core::{BlockNumber, EpochNumber};
 use ckb_testkit::Node;
 
+/// Return the start number of epoch `epoch_number` on the live chain of `node`, see
+/// `Node::epoch_start_number`.
 pub fn estimate_start_number_of_epoch(node: &Node, epoch_number: EpochNumber) -> BlockNumber {
-    assert!(node.consensus().permanent_difficulty_in_dummy);
-    let genesis_epoch = node
-        .rpc_client()
-        .get_epoch_by_number(0)
-        .expect("genesis epoch should exist");
-    genesis_epoch.length.value() * epoch_number
+    node.epoch_start_number(epoch_number)
+        .unwrap_or_else(|err| panic!("{}", err))
 }
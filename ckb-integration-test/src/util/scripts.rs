@@ -0,0 +1,260 @@
+//! The catalog of the prebuilt test RISC-V binaries under `testdata/script`.
+//!
+//! The binaries built from `testdata/script/source` are parameterized via their script args, see
+//! the `*_args` functions. Their cycles are measured on CKB-VM, `expected_cycles` returns the
+//! cycles of the script group when the script is referenced via `TestScript::script`.
+
+use crate::util::deployer::Deployer;
+use ckb_testkit::ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, Cycle, ScriptHashType},
+    packed::{Byte32, CellOutput, Script},
+    prelude::*,
+};
+use ckb_testkit::Node;
+
+/// The syscall sources, the `source` argument of `load_cell`, `load_header` and `load_witness`.
+pub const SOURCE_INPUT: u64 = 1;
+pub const SOURCE_OUTPUT: u64 = 2;
+pub const SOURCE_CELL_DEP: u64 = 3;
+pub const SOURCE_HEADER_DEP: u64 = 4;
+pub const SOURCE_GROUP_INPUT: u64 = 0x0100_0000_0000_0001;
+pub const SOURCE_GROUP_OUTPUT: u64 = 0x0100_0000_0000_0002;
+
+/// The syscall results the exercisers exit with.
+pub const SYSCALL_SUCCESS: i8 = 0;
+pub const SYSCALL_INDEX_OUT_OF_BOUND: i8 = 1;
+pub const SYSCALL_ITEM_MISSING: i8 = 2;
+
+// Loading the program and its script args
+const EXIT_CODE_BASE_CYCLES: Cycle = 3046;
+const LOAD_SYSCALL_BASE_CYCLES: Cycle = 3705;
+const CYCLE_BURNER_BASE_CYCLES: Cycle = 3090;
+const MEMORY_GROWTH_BASE_CYCLES: Cycle = 3090;
+// Entering the loops costs a few more cycles than skipping them
+const CYCLE_BURNER_LOOP_CYCLES: Cycle = 2;
+const MEMORY_GROWTH_LOOP_CYCLES: Cycle = 8;
+const CYCLES_PER_ROUND: Cycle = 8;
+const CYCLES_PER_PAGE: Cycle = 8;
+// VM1 initializes the stack in 2 more cycles
+const VM1_EXTRA_CYCLES: Cycle = 2;
+
+/// The CKB-VM version running a script. VM0 runs the scripts referenced via
+/// `ScriptHashType::Data`, and VM1, introduced by RFC0032, runs the scripts referenced via
+/// `ScriptHashType::Data1`, or via `ScriptHashType::Type` after the 2021 hardfork.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum VmVersion {
+    V0,
+    V1,
+}
+
+impl VmVersion {
+    /// Return the hash type referencing a script by data hash on this VM version.
+    pub fn data_hash_type(&self) -> ScriptHashType {
+        match self {
+            VmVersion::V0 => ScriptHashType::Data,
+            VmVersion::V1 => ScriptHashType::Data1,
+        }
+    }
+}
+
+/// The prebuilt test RISC-V binaries shipped with the integration tests.
+///
+/// There is no spawn script, the `spawn` syscall comes with VM2, which none of the ckb editions
+/// supports. The exec variants are `ExecCaller` and `ExecCallee`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TestScript {
+    /// `testdata/spec/ckb2021/cells/always_success`, the same as the genesis always-success cell.
+    AlwaysSuccess,
+    /// `testdata/spec/ckb2021/cells/another_always_success`, an always-success binary with a
+    /// different data hash from `AlwaysSuccess`.
+    AnotherAlwaysSuccess,
+    /// `testdata/script/exec_caller`, executes the callee specified by `ExecParams` in its
+    /// script args via the `exec` syscall.
+    ExecCaller,
+    /// `testdata/script/exec_callee`, the binary executed by `ExecCaller`.
+    ExecCallee,
+    /// `testdata/script/exit_code`, exits with the code in its args, see `exit_code_args`.
+    ExitCode,
+    /// `testdata/script/cycle_burner`, spins the rounds in its args, see `cycle_burner_args`.
+    CycleBurner,
+    /// `testdata/script/load_cell`, calls `load_cell` on the item in its args and exits with the
+    /// syscall result, see `load_syscall_args`.
+    LoadCell,
+    /// `testdata/script/load_header`, like `LoadCell` but calls `load_header`.
+    LoadHeader,
+    /// `testdata/script/load_witness`, like `LoadCell` but calls `load_witness`.
+    LoadWitness,
+    /// `testdata/script/memory_growth`, touches the pages in its args, see
+    /// `memory_growth_args`.
+    MemoryGrowth,
+}
+
+impl TestScript {
+    pub fn all() -> Vec<TestScript> {
+        vec![
+            TestScript::AlwaysSuccess,
+            TestScript::AnotherAlwaysSuccess,
+            TestScript::ExecCaller,
+            TestScript::ExecCallee,
+            TestScript::ExitCode,
+            TestScript::CycleBurner,
+            TestScript::LoadCell,
+            TestScript::LoadHeader,
+            TestScript::LoadWitness,
+            TestScript::MemoryGrowth,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestScript::AlwaysSuccess => "always_success",
+            TestScript::AnotherAlwaysSuccess => "another_always_success",
+            TestScript::ExecCaller => "exec_caller",
+            TestScript::ExecCallee => "exec_callee",
+            TestScript::ExitCode => "exit_code",
+            TestScript::CycleBurner => "cycle_burner",
+            TestScript::LoadCell => "load_cell",
+            TestScript::LoadHeader => "load_header",
+            TestScript::LoadWitness => "load_witness",
+            TestScript::MemoryGrowth => "memory_growth",
+        }
+    }
+
+    pub fn data(&self) -> Bytes {
+        let data: &'static [u8] = match self {
+            TestScript::AlwaysSuccess => {
+                include_bytes!("../../testdata/spec/ckb2021/cells/always_success")
+            }
+            TestScript::AnotherAlwaysSuccess => {
+                include_bytes!("../../testdata/spec/ckb2021/cells/another_always_success")
+            }
+            TestScript::ExecCaller => include_bytes!("../../testdata/script/exec_caller"),
+            TestScript::ExecCallee => include_bytes!("../../testdata/script/exec_callee"),
+            TestScript::ExitCode => include_bytes!("../../testdata/script/exit_code"),
+            TestScript::CycleBurner => include_bytes!("../../testdata/script/cycle_burner"),
+            TestScript::LoadCell => include_bytes!("../../testdata/script/load_cell"),
+            TestScript::LoadHeader => include_bytes!("../../testdata/script/load_header"),
+            TestScript::LoadWitness => include_bytes!("../../testdata/script/load_witness"),
+            TestScript::MemoryGrowth => include_bytes!("../../testdata/script/memory_growth"),
+        };
+        Bytes::from_static(data)
+    }
+
+    pub fn data_hash(&self) -> Byte32 {
+        CellOutput::calc_data_hash(&self.data())
+    }
+
+    /// Return where the script is built from.
+    pub fn source(&self) -> &'static str {
+        match self {
+            TestScript::AlwaysSuccess | TestScript::AnotherAlwaysSuccess => {
+                "https://github.com/nervosnetwork/ckb/tree/develop/script/testdata"
+            }
+            TestScript::ExecCaller => {
+                "https://github.com/keroro520/ckb_exec_test_script/tree/master/contracts/exec_caller"
+            }
+            TestScript::ExecCallee => {
+                "https://github.com/keroro520/ckb_exec_test_script/tree/master/contracts/exec_callee"
+            }
+            TestScript::ExitCode => "testdata/script/source/src/bin/exit_code.rs",
+            TestScript::CycleBurner => "testdata/script/source/src/bin/cycle_burner.rs",
+            TestScript::LoadCell => "testdata/script/source/src/bin/load_cell.rs",
+            TestScript::LoadHeader => "testdata/script/source/src/bin/load_header.rs",
+            TestScript::LoadWitness => "testdata/script/source/src/bin/load_witness.rs",
+            TestScript::MemoryGrowth => "testdata/script/source/src/bin/memory_growth.rs",
+        }
+    }
+
+    /// Return the script referencing the binary by data hash, run on `vm_version`.
+    pub fn script(&self, vm_version: VmVersion, args: Bytes) -> Script {
+        Script::new_builder()
+            .code_hash(self.data_hash())
+            .hash_type(vm_version.data_hash_type().into())
+            .args(args.pack())
+            .build()
+    }
+
+    /// Return the cycles consumed by running the script once with `args` on `vm_version`, given
+    /// the script succeeds. `None` if the script fails with these args, or its cycles depend on
+    /// the transaction, e.g. the exec params.
+    pub fn expected_cycles(&self, vm_version: VmVersion, args: &[u8]) -> Option<Cycle> {
+        let cycles = match self {
+            TestScript::AlwaysSuccess => 537,
+            TestScript::ExitCode if args.is_empty() => EXIT_CODE_BASE_CYCLES,
+            TestScript::CycleBurner => {
+                let rounds = arg_u64(args)?;
+                if rounds == 0 {
+                    CYCLE_BURNER_BASE_CYCLES
+                } else {
+                    CYCLE_BURNER_BASE_CYCLES
+                        + CYCLE_BURNER_LOOP_CYCLES
+                        + rounds.checked_mul(CYCLES_PER_ROUND)?
+                }
+            }
+            TestScript::LoadCell | TestScript::LoadHeader | TestScript::LoadWitness
+                if args.len() == 16 =>
+            {
+                LOAD_SYSCALL_BASE_CYCLES
+            }
+            TestScript::MemoryGrowth => {
+                let pages = arg_u64(args)?;
+                if pages == 0 {
+                    MEMORY_GROWTH_BASE_CYCLES
+                } else if pages <= MEMORY_GROWTH_MAX_PAGES {
+                    MEMORY_GROWTH_BASE_CYCLES + MEMORY_GROWTH_LOOP_CYCLES + pages * CYCLES_PER_PAGE
+                } else {
+                    return None;
+                }
+            }
+            _ => return None,
+        };
+        match vm_version {
+            VmVersion::V0 => Some(cycles),
+            VmVersion::V1 => Some(cycles + VM1_EXTRA_CYCLES),
+        }
+    }
+
+    /// Deploy the script as a code cell locked by always-success, named `self.name()`.
+    pub fn deploy(&self, node: &Node, deployer: &mut Deployer) {
+        let data = self.data();
+        let output = CellOutput::new_builder()
+            .lock(node.always_success_script())
+            .build_exact_capacity(Capacity::bytes(data.len()).unwrap())
+            .unwrap();
+        deployer.deploy(node, self.name(), output, data.pack());
+    }
+}
+
+/// The most pages `MemoryGrowth` can touch, the ones between its program image and the 4MB
+/// VM memory top. Touching more aborts the script with an out-of-bound error.
+pub const MEMORY_GROWTH_MAX_PAGES: u64 = 1006;
+
+/// Return the args of `TestScript::ExitCode`, exiting with `code`.
+pub fn exit_code_args(code: i8) -> Bytes {
+    Bytes::from(vec![code as u8])
+}
+
+/// Return the args of `TestScript::CycleBurner`, spinning `rounds` rounds.
+pub fn cycle_burner_args(rounds: u64) -> Bytes {
+    Bytes::from(rounds.to_le_bytes().to_vec())
+}
+
+/// Return the args of `TestScript::LoadCell`, `TestScript::LoadHeader` and
+/// `TestScript::LoadWitness`, loading the item at `index` of `source`.
+pub fn load_syscall_args(source: u64, index: u64) -> Bytes {
+    let mut args = source.to_le_bytes().to_vec();
+    args.extend_from_slice(&index.to_le_bytes());
+    Bytes::from(args)
+}
+
+/// Return the args of `TestScript::MemoryGrowth`, touching `pages` 4KB pages.
+pub fn memory_growth_args(pages: u64) -> Bytes {
+    Bytes::from(pages.to_le_bytes().to_vec())
+}
+
+fn arg_u64(args: &[u8]) -> Option<u64> {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(args.get(0..8)?);
+    Some(u64::from_le_bytes(value))
+}
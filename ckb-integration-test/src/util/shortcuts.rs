@@ -1,29 +1,24 @@
 use crate::prelude::*;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref V0_43: String = {
-        let stdout = ::std::process::Command::new(CKB2019.read().unwrap().clone())
-            .arg("--version")
-            .output()
-            .expect("failed to execute process")
-            .stdout;
-        String::from_utf8(stdout).unwrap()
-    };
-    static ref V0_100: String = {
-        let stdout = ::std::process::Command::new(CKB2021.read().unwrap().clone())
-            .arg("--version")
-            .output()
-            .expect("failed to execute process")
-            .stdout;
-        String::from_utf8(stdout).unwrap()
-    };
-}
+use std::path::Path;
 
+/// Return `ckb --version` of the bound `CKB2019` binary.
 pub fn v0_43() -> String {
-    V0_43.clone()
+    version_of(&CKB2019.read().unwrap())
 }
 
+/// Return `ckb --version` of the bound `CKB2021` binary.
 pub fn v0_100() -> String {
-    V0_100.clone()
+    version_of(&CKB2021.read().unwrap())
+}
+
+fn version_of(path: &Path) -> String {
+    if let Some(binary) = crate::BINARY_REGISTRY.read().unwrap().find_by_path(path) {
+        return binary.version().to_string();
+    }
+    let stdout = ::std::process::Command::new(path)
+        .arg("--version")
+        .output()
+        .expect("failed to execute process")
+        .stdout;
+    String::from_utf8(stdout).unwrap()
 }
@@ -0,0 +1,19 @@
+//! Spin `args[0..8]` (little-endian u64) rounds then exit 0. The consumed cycles grow linearly
+//! with the rounds.
+#![no_std]
+#![no_main]
+
+use ckb_integration_test_scripts::{arg_u64, entry, load_script_args, new_script_buffer};
+use core::hint::black_box;
+
+entry!(main);
+
+fn main() -> i8 {
+    let mut buffer = new_script_buffer();
+    let rounds = arg_u64(load_script_args(&mut buffer), 0);
+    let mut round = 0u64;
+    while round < rounds {
+        round = black_box(round + 1);
+    }
+    0
+}
@@ -0,0 +1,13 @@
+//! Exit with the code `args[0] as i8`, 0 if args is empty.
+#![no_std]
+#![no_main]
+
+use ckb_integration_test_scripts::{entry, load_script_args, new_script_buffer};
+
+entry!(main);
+
+fn main() -> i8 {
+    let mut buffer = new_script_buffer();
+    let args = load_script_args(&mut buffer);
+    args.first().map(|code| *code as i8).unwrap_or(0)
+}
@@ -0,0 +1,16 @@
+//! Call `load_cell` on the item `args[8..16]` (index) of `args[0..8]` (source), both
+//! little-endian u64, and exit with the syscall result.
+#![no_std]
+#![no_main]
+
+use ckb_integration_test_scripts::{
+    arg_u64, entry, load_cell, load_script_args, new_script_buffer,
+};
+
+entry!(main);
+
+fn main() -> i8 {
+    let mut buffer = new_script_buffer();
+    let args = load_script_args(&mut buffer);
+    load_cell(arg_u64(args, 0), arg_u64(args, 1)) as i8
+}
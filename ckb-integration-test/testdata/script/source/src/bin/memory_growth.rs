@@ -0,0 +1,26 @@
+//! Write one byte into each of the `args[0..8]` (little-endian u64) 4KB pages following the
+//! program image, then exit 0. Touching more pages than the VM memory aborts the script.
+#![no_std]
+#![no_main]
+
+use ckb_integration_test_scripts::{arg_u64, entry, load_script_args, new_script_buffer};
+
+const PAGE_SIZE: usize = 4096;
+
+extern "C" {
+    // The end of the program image, defined by the linker
+    static _end: u8;
+}
+
+entry!(main);
+
+fn main() -> i8 {
+    let mut buffer = new_script_buffer();
+    let pages = arg_u64(load_script_args(&mut buffer), 0) as usize;
+    let start = unsafe { &_end as *const u8 as usize };
+    let start = (start + PAGE_SIZE - 1) / PAGE_SIZE * PAGE_SIZE;
+    for page in 0..pages {
+        unsafe { ((start + page * PAGE_SIZE) as *mut u8).write_volatile(1) };
+    }
+    0
+}
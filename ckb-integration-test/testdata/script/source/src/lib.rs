@@ -0,0 +1,131 @@
+//! Syscalls and helpers shared by the test scripts, see `src/bin/` for the scripts.
+#![no_std]
+
+use core::arch::asm;
+
+const SYS_EXIT: u64 = 93;
+const SYS_LOAD_SCRIPT: u64 = 2052;
+const SYS_LOAD_CELL: u64 = 2071;
+const SYS_LOAD_HEADER: u64 = 2072;
+const SYS_LOAD_WITNESS: u64 = 2074;
+
+const SCRIPT_BUFFER_SIZE: usize = 1024;
+
+/// Define `_start`, which exits with the code returned by `$main`.
+#[macro_export]
+macro_rules! entry {
+    ($main:path) => {
+        #[no_mangle]
+        pub extern "C" fn _start() -> ! {
+            $crate::exit($main())
+        }
+    };
+}
+
+#[panic_handler]
+fn panic(_: &core::panic::PanicInfo) -> ! {
+    exit(-1)
+}
+
+unsafe fn syscall(a0: u64, a1: u64, a2: u64, a3: u64, a4: u64, n: u64) -> u64 {
+    let mut ret = a0;
+    asm!(
+        "ecall",
+        inout("a0") ret,
+        in("a1") a1,
+        in("a2") a2,
+        in("a3") a3,
+        in("a4") a4,
+        in("a7") n,
+    );
+    ret
+}
+
+pub fn exit(code: i8) -> ! {
+    unsafe {
+        syscall(code as u64, 0, 0, 0, 0, SYS_EXIT);
+    }
+    loop {}
+}
+
+/// Read the `args` of the running script into `buffer`, return the args. The script is
+/// molecule-encoded: `total_size | offsets of code_hash, hash_type, args | fields`, and args is
+/// `length | data`.
+pub fn load_script_args(buffer: &mut [u8; SCRIPT_BUFFER_SIZE]) -> &[u8] {
+    let mut len = SCRIPT_BUFFER_SIZE as u64;
+    let ret = unsafe {
+        syscall(
+            buffer.as_mut_ptr() as u64,
+            &mut len as *mut u64 as u64,
+            0,
+            0,
+            0,
+            SYS_LOAD_SCRIPT,
+        )
+    };
+    if ret != 0 || len as usize > SCRIPT_BUFFER_SIZE {
+        exit(-2);
+    }
+    // Checked indexing, a panicking one links the core panic machinery, which is not relaxed
+    let args_offset = read_u32(&buffer[..], 12).unwrap_or(u32::MAX) as usize;
+    let args_len = read_u32(&buffer[..], args_offset).unwrap_or(u32::MAX) as usize;
+    let start = args_offset.saturating_add(4);
+    match buffer.get(start..start.saturating_add(args_len)) {
+        Some(args) => args,
+        None => exit(-2),
+    }
+}
+
+pub fn new_script_buffer() -> [u8; SCRIPT_BUFFER_SIZE] {
+    [0u8; SCRIPT_BUFFER_SIZE]
+}
+
+/// Read the `index`-th little-endian u64 of `args`, 0 if absent.
+pub fn arg_u64(args: &[u8], index: usize) -> u64 {
+    match args.get(index * 8..index * 8 + 8) {
+        Some(bytes) => {
+            let mut value = [0u8; 8];
+            value.copy_from_slice(bytes);
+            u64::from_le_bytes(value)
+        }
+        None => 0,
+    }
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let mut value = [0u8; 4];
+    value.copy_from_slice(bytes.get(offset..offset + 4)?);
+    Some(u32::from_le_bytes(value))
+}
+
+/// The syscalls loading the item at `index` of `source`, return the syscall result, 0 on
+/// success, 1 if the index is out of bound, 2 if the item is missing.
+///
+/// The item is loaded with a zero-length buffer, which reports the item length without
+/// transferring any bytes, so that the consumed cycles do not depend on the item size.
+pub fn load_cell(source: u64, index: u64) -> u64 {
+    load_partial(SYS_LOAD_CELL, source, index)
+}
+
+pub fn load_header(source: u64, index: u64) -> u64 {
+    load_partial(SYS_LOAD_HEADER, source, index)
+}
+
+pub fn load_witness(source: u64, index: u64) -> u64 {
+    load_partial(SYS_LOAD_WITNESS, source, index)
+}
+
+fn load_partial(syscall_number: u64, source: u64, index: u64) -> u64 {
+    let mut buffer = [0u8; 1];
+    let mut len = 0u64;
+    unsafe {
+        syscall(
+            buffer.as_mut_ptr() as u64,
+            &mut len as *mut u64 as u64,
+            0,
+            index,
+            source,
+            syscall_number,
+        )
+    }
+}
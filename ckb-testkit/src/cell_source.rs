@@ -0,0 +1,315 @@
+use crate::rpc::{Order, RpcClient, ScriptType, SearchKey};
+use crate::Node;
+use ckb_types::{
+    core::{
+        cell::{CellMeta, CellMetaBuilder},
+        BlockView, TransactionInfo, TransactionView,
+    },
+    packed::{Byte32, OutPoint, Script},
+    prelude::*,
+};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The page size used to paginate the indexer RPC `get_cells`.
+const GET_CELLS_PAGE_SIZE: u32 = 100;
+
+/// CellSource looks up the live cells of a node, see `Node::set_cell_source`.
+pub trait CellSource: Send + Sync {
+    /// Return the live cells locked by `lock_script`.
+    fn get_live_cells_by_lock_script(&self, node: &Node, lock_script: &Script) -> Vec<CellMeta>;
+
+    /// Return the live cell `out_point`, `None` if it is dead or unknown.
+    fn get_cell_meta(&self, node: &Node, out_point: &OutPoint) -> Option<CellMeta>;
+
+    /// Called after `node` accepts `transaction` via `Node::submit_transaction`.
+    fn on_transaction_submitted(&self, _node: &Node, _transaction: &TransactionView) {}
+}
+
+/// The embedded `ckb_indexer::Indexer` under the node working directory. It is the default
+/// cell source of the nodes with a working directory.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmbeddedIndexer;
+
+impl CellSource for EmbeddedIndexer {
+    fn get_live_cells_by_lock_script(&self, node: &Node, lock_script: &Script) -> Vec<CellMeta> {
+        node.indexer()
+            .get_live_cells_by_lock_script(lock_script)
+            .expect("indexer get_live_cells_by_lock_script")
+            .into_iter()
+            .filter_map(|out_point| self.get_cell_meta(node, &out_point))
+            .collect()
+    }
+
+    // NOTICE: This function use `indexer_unchecked`
+    fn get_cell_meta(&self, node: &Node, out_point: &OutPoint) -> Option<CellMeta> {
+        let detail = node
+            .indexer_unchecked()
+            .get_detailed_live_cell(out_point)
+            .expect("indexer get_detailed_live_cell")?;
+        let block_epoch = node.get_block_epoch(detail.block_hash.clone());
+        let txinfo = TransactionInfo::new(
+            detail.block_number,
+            block_epoch,
+            detail.block_hash,
+            detail.tx_index as usize,
+        );
+        Some(
+            CellMetaBuilder::from_cell_output(detail.cell_output, detail.cell_data.raw_data())
+                .out_point(out_point.clone())
+                .transaction_info(txinfo)
+                .build(),
+        )
+    }
+}
+
+/// The indexer RPC `get_cells`, served by the node built-in indexer or a ckb-indexer server.
+/// It is the default cell source of the nodes without a working directory.
+#[derive(Clone, Default)]
+pub struct IndexerRpc {
+    // `None` means the node RPC
+    rpc_client: Option<RpcClient>,
+}
+
+impl IndexerRpc {
+    /// Use the built-in indexer of the node.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Use the ckb-indexer server at `url`, which indexes the chain of the node.
+    pub fn with_url(url: &str) -> Self {
+        Self {
+            rpc_client: Some(RpcClient::new(url, true)),
+        }
+    }
+}
+
+impl CellSource for IndexerRpc {
+    fn get_live_cells_by_lock_script(&self, node: &Node, lock_script: &Script) -> Vec<CellMeta> {
+        let rpc_client = self
+            .rpc_client
+            .as_ref()
+            .unwrap_or_else(|| node.rpc_client());
+        let search_key = SearchKey {
+            script: lock_script.clone().into(),
+            script_type: ScriptType::Lock,
+        };
+        let mut cells = Vec::new();
+        let mut after = None;
+        loop {
+            let page =
+                rpc_client.get_cells(search_key.clone(), Order::Asc, GET_CELLS_PAGE_SIZE, after);
+            if page.objects.is_empty() {
+                break;
+            }
+            for cell in page.objects {
+                let block_number = cell.block_number.value();
+                let block_hash = node
+                    .rpc_client()
+                    .get_block_hash(block_number)
+                    .expect("indexed block exists");
+                let txinfo = TransactionInfo::new(
+                    block_number,
+                    node.get_block_epoch(block_hash.clone()),
+                    block_hash,
+                    cell.tx_index.value() as usize,
+                );
+                cells.push(
+                    CellMetaBuilder::from_cell_output(
+                        cell.output.into(),
+                        cell.output_data.into_bytes(),
+                    )
+                    .out_point(cell.out_point.into())
+                    .transaction_info(txinfo)
+                    .build(),
+                );
+            }
+            after = Some(page.last_cursor);
+        }
+        cells
+    }
+
+    fn get_cell_meta(&self, node: &Node, out_point: &OutPoint) -> Option<CellMeta> {
+        let cell_info = node
+            .rpc_client()
+            .get_live_cell(out_point.clone().into(), true)
+            .cell?;
+        let data = cell_info
+            .data
+            .map(|data| data.content.into_bytes())
+            .unwrap_or_default();
+        let txinfo = committed_transaction_info(node, out_point.tx_hash())?;
+        Some(
+            CellMetaBuilder::from_cell_output(cell_info.output.into(), data)
+                .out_point(out_point.clone())
+                .transaction_info(txinfo)
+                .build(),
+        )
+    }
+}
+
+/// An in-memory live cell set, fed from the submitted transactions and the tracked blocks.
+///
+/// The outputs of the submitted transactions have no transaction info until the block
+/// committing them is tracked via `track_block`. Feeding the same transaction again, e.g.
+/// submitting it and then tracking its block, only fills the transaction info, and never
+/// revives the outputs spent meanwhile. When two transactions spend the same cell, the latest
+/// one wins: the earlier one is dropped along with its outputs and their spenders, and its
+/// other inputs are live again.
+#[derive(Default)]
+pub struct LiveCellTracker {
+    inner: Mutex<LiveCells>,
+}
+
+#[derive(Default)]
+struct LiveCells {
+    // #{ out_point => live_cell }
+    cells: HashMap<OutPoint, CellMeta>,
+    // #{ out_point => (spending tx hash, the spent cell if it was live) }
+    spent: HashMap<OutPoint, (Byte32, Option<CellMeta>)>,
+}
+
+impl LiveCellTracker {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Track `cell` as live, e.g. the cells known before the tracker is set.
+    pub fn track_cell(&self, cell: CellMeta) {
+        self.inner
+            .lock()
+            .unwrap()
+            .cells
+            .insert(cell.out_point.clone(), cell);
+    }
+
+    /// Mark the inputs of `transaction` dead and its outputs live.
+    pub fn track_transaction(&self, transaction: &TransactionView) {
+        self.inner
+            .lock()
+            .unwrap()
+            .apply_transaction(transaction, None);
+    }
+
+    /// Track the transactions committed in `block`, filling their outputs' transaction info.
+    pub fn track_block(&self, block: &BlockView) {
+        let mut inner = self.inner.lock().unwrap();
+        for (tx_index, transaction) in block.transactions().iter().enumerate() {
+            let txinfo =
+                TransactionInfo::new(block.number(), block.epoch(), block.hash(), tx_index);
+            inner.apply_transaction(transaction, Some(txinfo));
+        }
+    }
+
+    /// Return the tracked live cell `out_point`.
+    pub fn live_cell(&self, out_point: &OutPoint) -> Option<CellMeta> {
+        self.inner.lock().unwrap().cells.get(out_point).cloned()
+    }
+
+    /// Return the tracked live cells locked by `lock_script`, ordered by out-point.
+    pub fn live_cells_by_lock_script(&self, lock_script: &Script) -> Vec<CellMeta> {
+        let mut cells = self
+            .inner
+            .lock()
+            .unwrap()
+            .cells
+            .values()
+            .filter(|cell| &cell.cell_output.lock() == lock_script)
+            .cloned()
+            .collect::<Vec<_>>();
+        cells.sort_by(|a, b| a.out_point.as_slice().cmp(b.out_point.as_slice()));
+        cells
+    }
+}
+
+impl LiveCells {
+    fn apply_transaction(
+        &mut self,
+        transaction: &TransactionView,
+        txinfo: Option<TransactionInfo>,
+    ) {
+        let tx_hash = transaction.hash();
+        if !transaction.is_cellbase() {
+            for out_point in transaction.input_pts_iter() {
+                if let Some((spender, _)) = self.spent.get(&out_point) {
+                    if spender == &tx_hash {
+                        // Fed again
+                        continue;
+                    }
+                    // Conflicting with an earlier transaction, which revives `out_point`
+                    let spender = spender.clone();
+                    self.evict(spender);
+                }
+                let cell = self.cells.remove(&out_point);
+                self.spent.insert(out_point, (tx_hash.clone(), cell));
+            }
+        }
+        for (index, (output, data)) in transaction.outputs_with_data_iter().enumerate() {
+            let out_point = OutPoint::new(tx_hash.clone(), index as u32);
+            // Skip the spent outputs, and keep the transaction info of the committed ones
+            if self.spent.contains_key(&out_point)
+                || (txinfo.is_none() && self.cells.contains_key(&out_point))
+            {
+                continue;
+            }
+            let mut builder =
+                CellMetaBuilder::from_cell_output(output, data).out_point(out_point.clone());
+            if let Some(txinfo) = txinfo.clone() {
+                builder = builder.transaction_info(txinfo);
+            }
+            self.cells.insert(out_point, builder.build());
+        }
+    }
+
+    // Drop transaction `tx_hash` and its descendants, reviving the cells they spent
+    fn evict(&mut self, tx_hash: Byte32) {
+        let mut evicting = vec![tx_hash];
+        while let Some(tx_hash) = evicting.pop() {
+            self.cells
+                .retain(|out_point, _| out_point.tx_hash() != tx_hash);
+            for (out_point, (spender, cell)) in std::mem::take(&mut self.spent) {
+                if spender == tx_hash {
+                    if let Some(cell) = cell {
+                        self.cells.insert(out_point, cell);
+                    }
+                } else if out_point.tx_hash() == tx_hash {
+                    evicting.push(spender);
+                } else {
+                    self.spent.insert(out_point, (spender, cell));
+                }
+            }
+        }
+    }
+}
+
+impl CellSource for LiveCellTracker {
+    fn get_live_cells_by_lock_script(&self, _node: &Node, lock_script: &Script) -> Vec<CellMeta> {
+        self.live_cells_by_lock_script(lock_script)
+    }
+
+    fn get_cell_meta(&self, _node: &Node, out_point: &OutPoint) -> Option<CellMeta> {
+        self.live_cell(out_point)
+    }
+
+    fn on_transaction_submitted(&self, _node: &Node, transaction: &TransactionView) {
+        self.track_transaction(transaction);
+    }
+}
+
+// Return the transaction info of the committed transaction `tx_hash`.
+fn committed_transaction_info(node: &Node, tx_hash: Byte32) -> Option<TransactionInfo> {
+    let block_hash = node
+        .rpc_client()
+        .get_transaction(tx_hash.clone())?
+        .tx_status
+        .block_hash?;
+    let block = node.get_block(block_hash.pack());
+    let tx_index = block.tx_hashes().iter().position(|hash| hash == &tx_hash)?;
+    Some(TransactionInfo::new(
+        block.number(),
+        block.epoch(),
+        block.hash(),
+        tx_index,
+    ))
+}
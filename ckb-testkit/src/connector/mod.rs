@@ -9,9 +9,10 @@ mod support_protocols;
 pub use compress::{compress, decompress};
 pub use shared::SharedState;
 pub use simple_protocol_handler::SimpleProtocolHandler;
-pub use simple_service_handler::SimpleServiceHandler;
+pub use simple_service_handler::{SessionCallback, SimpleServiceHandler};
 pub use support_protocols::SupportProtocols;
 
+use crate::util::waiter::scaled;
 use crate::Node;
 use ckb_stop_handler::{SignalSender, StopHandler};
 use futures::prelude::*;
@@ -45,6 +46,7 @@ pub struct ConnectorBuilder {
 pub struct Connector {
     #[allow(dead_code)]
     key_pair: SecioKeyPair,
+    listening_addresses: Vec<Multiaddr>,
     shared: Arc<RwLock<SharedState>>,
     p2p_service_controller: P2PServiceControl,
     _stop_handler: StopHandler<tokio::sync::oneshot::Sender<()>>,
@@ -128,6 +130,7 @@ impl ConnectorBuilder {
             "Sync protocol is the most underlying protocol to establish connection and must be contained in protocols",
         );
         let listening_addresses = self.listening_addresses.clone();
+        let connector_listening_addresses = self.listening_addresses.clone();
         let key_pair = self.key_pair.clone();
 
         // Start P2P Service and maintain the controller
@@ -135,8 +138,8 @@ impl ConnectorBuilder {
 
         let p2p_service_controller = p2p_service.control().to_owned();
         let (stopped_signal_sender, mut stopped_signal_receiver) = tokio::sync::oneshot::channel();
-        ::std::thread::spawn(move || {
-            let rt = tokio::runtime::Runtime::new().unwrap();
+        crate::logger::spawn(move || {
+            let rt = crate::logger::tokio_runtime();
             rt.block_on(async move {
                 if !listening_addresses.is_empty() {
                     for listening_address in listening_addresses {
@@ -161,6 +164,7 @@ impl ConnectorBuilder {
 
         Connector {
             key_pair,
+            listening_addresses: connector_listening_addresses,
             shared,
             p2p_service_controller,
             _stop_handler: StopHandler::new(
@@ -206,19 +210,9 @@ impl Connector {
         // Wait for all protocols connections establishment
         let start_time = Instant::now();
         let mut last_logging_time = Instant::now();
-        while start_time.elapsed() <= Duration::from_secs(5) {
+        while start_time.elapsed() <= scaled(Duration::from_secs(5)) {
             if let Some(opened_protocol_ids) = self.get_opened_protocol_ids(node) {
-                let expected_opened = self
-                    .p2p_service_controller
-                    .protocols()
-                    .iter()
-                    .filter(|(protocol_id, _)| {
-                        // TODO Filter out short-running protocols. Which protocols are short-running?
-                        protocol_id != &&SupportProtocols::Identify.protocol_id()
-                            && protocol_id != &&SupportProtocols::DisconnectMessage.protocol_id()
-                    })
-                    .count();
-                if opened_protocol_ids.len() >= expected_opened {
+                if opened_protocol_ids.len() >= self.expected_opened_protocols_count() {
                     return Ok(());
                 }
 
@@ -248,6 +242,55 @@ impl Connector {
         ))
     }
 
+    /// Wait for `node` dialing in, i.e. an inbound session from `node` is established and all
+    /// protocols opened. Usually it is used after advertising the connector's listening
+    /// addresses to `node`, e.g. via `Connector::send_discovery_nodes`.
+    ///
+    /// NOTE: The connector must be built with
+    /// [`ConnectorBuilder::listening_addresses`](ConnectorBuilder::listening_addresses).
+    pub fn wait_for_inbound(
+        &self,
+        node: &Node,
+        timeout: Duration,
+    ) -> Result<SessionContext, String> {
+        assert!(
+            !self.listening_addresses.is_empty(),
+            "Connector does not listen on any address, inbound connections are impossible"
+        );
+        let timeout = scaled(timeout);
+        let start_time = Instant::now();
+        let mut last_logging_time = Instant::now();
+        while start_time.elapsed() <= timeout {
+            let inbound_session = self
+                .shared
+                .read()
+                .ok()
+                .and_then(|shared| shared.get_session_by_peer_id(node.node_id()))
+                .filter(|session| session.ty.is_inbound());
+            if let Some(session) = inbound_session {
+                let opened_protocol_ids = self.get_opened_protocol_ids(node).unwrap_or_default();
+                if opened_protocol_ids.len() >= self.expected_opened_protocols_count() {
+                    return Ok(session);
+                }
+            }
+
+            if last_logging_time.elapsed() > Duration::from_secs(1) {
+                last_logging_time = Instant::now();
+                crate::debug!(
+                    "Connector is waiting inbound session from node \"{}\", listening addresses: {:?}",
+                    node.node_name(),
+                    self.listening_addresses,
+                );
+            }
+            sleep(Duration::from_millis(100));
+        }
+
+        Err(format!(
+            "Connector is timeout when waiting inbound connection from {}",
+            node.node_name()
+        ))
+    }
+
     /// Send `data` through the protocol of the session
     pub fn send(&self, node: &Node, protocol: SupportProtocols, data: Bytes) -> Result<(), String> {
         let session = self.get_session(node).ok_or_else(|| {
@@ -268,24 +311,43 @@ impl Connector {
             })
     }
 
-    /// Return the session corresponding to the `node` if connected.
+    /// Return the session corresponding to the `node` if connected, either outbound or inbound.
     pub fn get_session(&self, node: &Node) -> Option<SessionContext> {
         if let Ok(shared) = self.shared.read() {
             let node_connected_addr = node.p2p_address_with_node_id().parse().unwrap();
-            return shared.get_session(&node_connected_addr);
+            return shared
+                .get_session(&node_connected_addr)
+                .or_else(|| shared.get_session_by_peer_id(node.node_id()));
         }
         unreachable!()
     }
 
     /// Return the opened protocols of the session corresponding to the `node` if connected
     pub fn get_opened_protocol_ids(&self, node: &Node) -> Option<Vec<ProtocolId>> {
-        if let Ok(shared) = self.shared.read() {
-            let node_connected_addr = node.p2p_address_with_node_id().parse().unwrap();
-            return shared
-                .get_session(&node_connected_addr)
-                .and_then(|session| shared.get_opened_protocol_ids(&session.id));
-        }
-        unreachable!()
+        self.get_session(node).and_then(|session| {
+            self.shared
+                .read()
+                .ok()
+                .and_then(|shared| shared.get_opened_protocol_ids(&session.id))
+        })
+    }
+
+    /// Return the listening addresses of the connector
+    pub fn listening_addresses(&self) -> &[Multiaddr] {
+        &self.listening_addresses
+    }
+
+    // The number of protocols expected to be kept opened after session establishment.
+    fn expected_opened_protocols_count(&self) -> usize {
+        self.p2p_service_controller
+            .protocols()
+            .iter()
+            .filter(|(protocol_id, _)| {
+                // TODO Filter out short-running protocols. Which protocols are short-running?
+                protocol_id != &&SupportProtocols::Identify.protocol_id()
+                    && protocol_id != &&SupportProtocols::DisconnectMessage.protocol_id()
+            })
+            .count()
     }
 
     /// Return the shared state
@@ -45,6 +45,23 @@ impl SharedState {
         None
     }
 
+    /// Get session by remote peer id, e.g. "QmaPV8Ly4YZe2L8B11b2Rvy8YLsvKo4TtfuqhJQzfPcK5T".
+    ///
+    /// Inbound sessions are dialed from the remote's ephemeral port, so they can only be
+    /// identified by the remote public key instead of the address.
+    pub fn get_session_by_peer_id(&self, peer_id: &str) -> Option<SessionContext> {
+        for (session, _) in self.session_manager.values() {
+            let remote_peer_id = session
+                .remote_pubkey
+                .as_ref()
+                .map(|pubkey| pubkey.peer_id().to_base58());
+            if remote_peer_id.as_deref() == Some(peer_id) {
+                return Some(session.clone());
+            }
+        }
+        None
+    }
+
     pub fn add_protocol(&mut self, session: &SessionContext, protocol_id: ProtocolId) {
         let (protocol_mailbox_sender, protocol_mailbox_receiver) = unbounded::<Bytes>();
         self.session_manager
@@ -1,20 +1,58 @@
 use super::SharedState;
 use p2p::{
-    context::ServiceContext as P2PServiceContext, service::ServiceError as P2PServiceError,
-    service::ServiceEvent as P2PServiceEvent, traits::ServiceHandle as P2PServiceHandle,
+    context::ServiceContext as P2PServiceContext, context::SessionContext,
+    service::ServiceError as P2PServiceError, service::ServiceEvent as P2PServiceEvent,
+    traits::ServiceHandle as P2PServiceHandle,
 };
 use std::sync::{Arc, RwLock};
 
+/// Callback invoked on session-wise events, see [`SimpleServiceHandler::on_session_open`].
+pub type SessionCallback = Arc<dyn Fn(&SessionContext) + Send + Sync>;
+
 /// TestServiceHandler is an implementation of `P2PServiceHandle` which handle service-wise
 /// events and errors.
 #[derive(Clone)]
 pub struct SimpleServiceHandler {
     shared: Arc<RwLock<SharedState>>,
+    session_open_callbacks: Vec<SessionCallback>,
+    session_close_callbacks: Vec<SessionCallback>,
 }
 
 impl SimpleServiceHandler {
     pub fn new(shared: Arc<RwLock<SharedState>>) -> Self {
-        Self { shared }
+        Self {
+            shared,
+            session_open_callbacks: Vec::new(),
+            session_close_callbacks: Vec::new(),
+        }
+    }
+
+    /// Register a callback which is invoked after a session, no matter inbound or outbound,
+    /// is opened and recorded into the shared state.
+    ///
+    /// ```ignore
+    /// let handler = SimpleServiceHandler::new(Arc::clone(&shared)).on_session_open(|session| {
+    ///     if session.ty.is_inbound() {
+    ///         ckb_testkit::info!("node dialed us from {}", session.address);
+    ///     }
+    /// });
+    /// ```
+    pub fn on_session_open<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SessionContext) + Send + Sync + 'static,
+    {
+        self.session_open_callbacks.push(Arc::new(callback));
+        self
+    }
+
+    /// Register a callback which is invoked after a session is closed and removed from the
+    /// shared state.
+    pub fn on_session_close<F>(mut self, callback: F) -> Self
+    where
+        F: Fn(&SessionContext) + Send + Sync + 'static,
+    {
+        self.session_close_callbacks.push(Arc::new(callback));
+        self
     }
 }
 
@@ -35,6 +73,9 @@ impl P2PServiceHandle for SimpleServiceHandler {
                     .shared
                     .write()
                     .map(|mut shared| shared.add_session(session.as_ref().to_owned()));
+                for callback in self.session_open_callbacks.iter() {
+                    callback(session.as_ref());
+                }
             }
             P2PServiceEvent::SessionClose {
                 session_context: session,
@@ -44,9 +85,15 @@ impl P2PServiceHandle for SimpleServiceHandler {
                     .shared
                     .write()
                     .map(|mut shared| shared.remove_session(&session.id));
+                for callback in self.session_close_callbacks.iter() {
+                    callback(session.as_ref());
+                }
+            }
+            P2PServiceEvent::ListenStarted { address } => {
+                crate::debug!("TestServiceHandler start listening on {}", address);
             }
-            _ => {
-                unimplemented!()
+            P2PServiceEvent::ListenClose { address } => {
+                crate::debug!("TestServiceHandler stop listening on {}", address);
             }
         }
     }
@@ -1,18 +1,31 @@
+pub mod cell_source;
 pub mod connector;
 pub mod logger;
 mod node;
 mod nodes;
+pub mod proxy;
 mod rpc;
 #[cfg(feature = "with_subscribe")]
 mod subscribe;
+mod tx_builder;
 mod user;
 pub mod util;
 
+#[cfg(test)]
+mod tests;
+
+pub use cell_source::{CellSource, EmbeddedIndexer, IndexerRpc, LiveCellTracker};
 pub use connector::{compress, decompress, Connector, ConnectorBuilder, SupportProtocols};
-pub use logger::LOG_TARGET;
-pub use node::{BuildInstruction, Node, NodeOptions};
-pub use nodes::Nodes;
-pub use user::User;
+pub use logger::{LogContext, LogFile, LogFormat};
+pub use node::{
+    BlockAssembler, BlockMutator, BuildInstruction, IndexerMetrics, LogWatcher, Node, NodeOptions,
+    Scenario, DEFAULT_INDEXER_POLL_INTERVAL,
+};
+pub use nodes::{Divergence, Fork, ForkBranch, Nodes};
+pub use proxy::{Proxy, ProxyFaults};
+pub use rpc::{IndexerCell, Order, Pagination, ScriptType, SearchKey};
+pub use tx_builder::{TxBuilder, DEFAULT_FEE_RATE};
+pub use user::{AnyoneCanPayScript, MultisigConfig, User, UserLock};
 
 pub use ckb_crypto;
 pub use ckb_jsonrpc_types;
@@ -24,7 +37,12 @@ use ckb_types::{h256, H256};
 pub const SYSTEM_CELL_ALWAYS_SUCCESS_INDEX: u32 = 5;
 pub const GENESIS_DEP_GROUP_TRANSACTION_INDEX: usize = 1;
 pub const GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX: usize = 0;
+pub const GENESIS_MULTISIG_ALL_DEP_GROUP_CELL_INDEX: usize = 1;
 pub const SIGHASH_ALL_TYPE_HASH: H256 =
     h256!("0x9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8");
+pub const MULTISIG_ALL_TYPE_HASH: H256 =
+    h256!("0x5c5069eb0857efc65e1bca0c07df34c31663b3622fd3876c876320fc9634e2a8");
 pub const SIGHASH_ALL_DATA_HASH: H256 =
     h256!("0x709f3fda12f561cfacf92273c57a98fede188a3f1a59b1f888d113f9cce08649");
+pub const TYPE_ID_CODE_HASH: H256 =
+    h256!("0x00000000000000000000000000000000000000000000000000545950455f4944");
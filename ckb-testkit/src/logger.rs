@@ -1,75 +1,212 @@
 pub use log;
+use log::Level;
 use std::cell::RefCell;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 
-// TODO LOG_TARGET should not be thread-local
 thread_local! {
-    // Initialize at beginning of running case
-    pub static LOG_TARGET: RefCell<String> = RefCell::new(String::new());
+    // Initialize at beginning of running case, inherited by the threads spawned via
+    // `logger::spawn` or wrapped by `logger::propagate`
+    static LOG_CONTEXT: RefCell<LogContext> = RefCell::new(LogContext::default());
+}
+
+/// The format of the lines written into a `LogFile`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// `<timestamp> <thread> <LEVEL> <target>  <message>`, the same as ckb.
+    Text,
+    /// One JSON object per line, with fields `timestamp`, `thread`, `level`, `target` and
+    /// `message`.
+    Json,
+}
+
+/// A log file receiving the messages logged under a `LogContext`, e.g. the per-case log file.
+pub struct LogFile {
+    file: Mutex<File>,
+    format: LogFormat,
+}
+
+impl LogFile {
+    /// Create or append to the log file at `path`.
+    pub fn open<P: AsRef<Path>>(path: P, format: LogFormat) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            format,
+        })
+    }
+
+    fn write(&self, level: Level, target: &str, args: &fmt::Arguments) {
+        let timestamp = chrono::Local::now();
+        let thread = thread::current();
+        let thread_name = thread.name().unwrap_or("unnamed");
+        let line = match self.format {
+            LogFormat::Text => format!(
+                "{} {} {} {}  {}",
+                timestamp.format("%Y-%m-%d %H:%M:%S%.3f %:z"),
+                thread_name,
+                level,
+                target,
+                args
+            ),
+            LogFormat::Json => serde_json::json!({
+                "timestamp": timestamp.to_rfc3339(),
+                "thread": thread_name,
+                "level": level.to_string(),
+                "target": target,
+                "message": args.to_string(),
+            })
+            .to_string(),
+        };
+        let mut file = self.file.lock().unwrap();
+        let _ignore = writeln!(file, "{}", line);
+    }
+}
+
+/// The logging context of the current thread: the log target, usually the running case name,
+/// and the optional log file, which receives the messages as well as the global logger.
+#[derive(Clone, Default)]
+pub struct LogContext {
+    target: String,
+    file: Option<Arc<LogFile>>,
+}
+
+impl LogContext {
+    pub fn new<S: ToString>(target: S) -> Self {
+        Self {
+            target: target.to_string(),
+            file: None,
+        }
+    }
+
+    pub fn with_file(mut self, file: LogFile) -> Self {
+        self.file = Some(Arc::new(file));
+        self
+    }
+
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+/// Set the logging context of the current thread.
+pub fn set_context(context: LogContext) {
+    LOG_CONTEXT.with(|c| *c.borrow_mut() = context);
+}
+
+/// Return the logging context of the current thread.
+pub fn context() -> LogContext {
+    LOG_CONTEXT.with(|c| c.borrow().clone())
+}
+
+/// Wrap `f` to run under the logging context of the current thread, for the threads not
+/// spawned via `logger::spawn`, e.g. `thread::Builder::new().spawn(logger::propagate(f))`.
+pub fn propagate<F, T>(f: F) -> impl FnOnce() -> T
+where
+    F: FnOnce() -> T,
+{
+    let context = context();
+    move || {
+        set_context(context);
+        f()
+    }
+}
+
+/// Like `std::thread::spawn`, but the spawned thread inherits the logging context.
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    thread::spawn(propagate(f))
+}
+
+/// Build a multi-thread tokio runtime whose worker threads inherit the logging context.
+pub fn tokio_runtime() -> tokio::runtime::Runtime {
+    let context = context();
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .on_thread_start(move || set_context(context.clone()))
+        .build()
+        .expect("build tokio runtime")
+}
+
+#[doc(hidden)]
+pub fn __log(level: Level, module_path: &'static str, args: fmt::Arguments) {
+    LOG_CONTEXT.with(|c| {
+        let context = c.borrow();
+        let target = if context.target.is_empty() {
+            module_path
+        } else {
+            context.target.as_str()
+        };
+        if level == Level::Error {
+            ::std::eprintln!("{}", args);
+        }
+        log::log!(target: target, level, "{}", args);
+        if let Some(file) = context.file.as_ref() {
+            if level <= log::max_level() {
+                file.write(level, target, &args);
+            }
+        }
+    })
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! trace {
     ($( $args:tt )*) => {
-        $crate::logger::LOG_TARGET.with(|c| {
-            if !c.borrow().is_empty() {
-                $crate::logger::log::trace!(target: &c.borrow(), $( $args )*);
-            } else {
-                $crate::logger::log::trace!($( $args )*);
-            }
-        });
+        $crate::logger::__log(
+            $crate::logger::log::Level::Trace,
+            ::std::module_path!(),
+            ::std::format_args!($( $args )*),
+        )
     }
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! debug {
     ($( $args:tt )*) => {
-        $crate::logger::LOG_TARGET.with(|c| {
-            if !c.borrow().is_empty() {
-                $crate::logger::log::debug!(target: &c.borrow(), $( $args )*);
-            } else {
-                $crate::logger::log::debug!($( $args )*);
-            }
-        });
+        $crate::logger::__log(
+            $crate::logger::log::Level::Debug,
+            ::std::module_path!(),
+            ::std::format_args!($( $args )*),
+        )
     }
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! info {
     ($( $args:tt )*) => {
-        $crate::logger::LOG_TARGET.with(|c| {
-            if !c.borrow().is_empty() {
-                $crate::logger::log::info!(target: &c.borrow(), $( $args )*);
-            } else {
-                $crate::logger::log::info!($( $args )*);
-            }
-        });
+        $crate::logger::__log(
+            $crate::logger::log::Level::Info,
+            ::std::module_path!(),
+            ::std::format_args!($( $args )*),
+        )
     }
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! warn {
     ($( $args:tt )*) => {
-        $crate::logger::LOG_TARGET.with(|c| {
-            if !c.borrow().is_empty() {
-                $crate::logger::log::warn!(target: &c.borrow(), $( $args )*);
-            } else {
-                $crate::logger::log::warn!($( $args )*);
-            }
-        });
+        $crate::logger::__log(
+            $crate::logger::log::Level::Warn,
+            ::std::module_path!(),
+            ::std::format_args!($( $args )*),
+        )
     }
 }
 
 #[macro_export(local_inner_macros)]
 macro_rules! error {
     ($( $args:tt )*) => {
-        $crate::logger::LOG_TARGET.with(|c| {
-            if !c.borrow().is_empty() {
-                ::std::eprintln!($( $args )*);
-                $crate::logger::log::error!(target: &c.borrow(), $( $args )*);
-            } else {
-                ::std::eprintln!($( $args )*);
-                $crate::logger::log::error!($( $args )*);
-            }
-        })
+        $crate::logger::__log(
+            $crate::logger::log::Level::Error,
+            ::std::module_path!(),
+            ::std::format_args!($( $args )*),
+        )
     }
 }
@@ -50,20 +50,9 @@ impl Node {
     }
 
     pub fn get_spendable_always_success_cells(&self) -> Vec<CellMeta> {
-        let live_out_points = self
-            .indexer()
-            .get_live_cells_by_lock_script(&self.always_success_script())
-            .expect("indexer get_live_cells_by_lock_script");
-        live_out_points
+        self.get_live_cells_by_lock_script(&self.always_success_script())
             .into_iter()
-            .filter_map(|out_point| {
-                let cell_meta = self.get_cell_meta(out_point)?;
-                if cell_meta.data_bytes == 0 {
-                    Some(cell_meta)
-                } else {
-                    None
-                }
-            })
+            .filter(|cell_meta| cell_meta.data_bytes == 0 && self.is_mature(cell_meta))
             .collect()
     }
 }
@@ -0,0 +1,320 @@
+use ckb_jsonrpc_types::Consensus;
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        BlockBuilder, BlockNumber, BlockView, Capacity, EpochNumberWithFraction, HeaderView,
+        TransactionBuilder, TransactionView, UncleBlockView,
+    },
+    packed::{Byte32, CellInput, CellOutput, CellbaseWitness, OutPoint, ProposalShortId, Script},
+    prelude::*,
+};
+use std::collections::HashMap;
+
+/// BlockAssembler assembles valid blocks locally, without asking a node for block templates.
+/// It is used to craft blocks for fake sync peers and relay tests, see `Connector`.
+///
+/// The assembler calculates the epoch, cellbase, dao field, merkle roots and the dummy PoW
+/// fields by itself. There are limitations as it knows nothing about the chain but the parent
+/// header:
+///   - Only chains running with `permanent_difficulty_in_dummy` are supported, on which the
+///     epoch length and the difficulty keep constant.
+///   - The cellbase reward is validated against the history of the chain, so it must be given
+///     via `cellbase_reward` after the finalization delay, e.g. from
+///     `get_block_economic_state` and the cellbase witness of the finalized block of a mirror
+///     node. Otherwise the cellbase has no outputs.
+///   - Transactions withdrawing from NervosDAO are not supported, as calculating the interests
+///     requires the deposit headers.
+///
+/// ```ignore
+/// let parent = node.get_tip_block().header();
+/// let block = BlockAssembler::new(node.consensus(), &parent)
+///     .transaction(&tx)
+///     .assemble(|out_point| {
+///         let cell = node.rpc_client().get_live_cell(out_point.clone().into(), true).cell?;
+///         Some((cell.output.into(), cell.data?.content.into_bytes()))
+///     })?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlockAssembler {
+    consensus: Consensus,
+    parent: HeaderView,
+    transactions: Vec<TransactionView>,
+    proposals: Vec<ProposalShortId>,
+    uncles: Vec<UncleBlockView>,
+    extension: Option<Bytes>,
+    timestamp: Option<u64>,
+    cellbase_lock: Script,
+    cellbase_message: Bytes,
+    cellbase_reward: Option<(Capacity, Script)>,
+}
+
+impl BlockAssembler {
+    pub fn new(consensus: &Consensus, parent: &HeaderView) -> Self {
+        Self {
+            consensus: consensus.clone(),
+            parent: parent.clone(),
+            transactions: Vec::new(),
+            proposals: Vec::new(),
+            uncles: Vec::new(),
+            extension: None,
+            timestamp: None,
+            cellbase_lock: Script::default(),
+            cellbase_message: Bytes::new(),
+            cellbase_reward: None,
+        }
+    }
+
+    /// Commit `transaction`. Transactions are committed in the order they are given, so a
+    /// transaction may spend the outputs of the previous ones.
+    pub fn transaction(mut self, transaction: &TransactionView) -> Self {
+        self.transactions.push(transaction.clone());
+        self
+    }
+
+    pub fn transactions(mut self, transactions: Vec<TransactionView>) -> Self {
+        self.transactions.extend(transactions);
+        self
+    }
+
+    pub fn proposal(mut self, proposal_short_id: ProposalShortId) -> Self {
+        self.proposals.push(proposal_short_id);
+        self
+    }
+
+    pub fn uncle(mut self, uncle: &UncleBlockView) -> Self {
+        self.uncles.push(uncle.clone());
+        self
+    }
+
+    /// Set the block extension, which is only allowed after CKB2021 activated.
+    pub fn extension(mut self, extension: Bytes) -> Self {
+        self.extension = Some(extension);
+        self
+    }
+
+    /// Set the header timestamp, default is `parent.timestamp() + 1`.
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Set the lock script in the cellbase witness. The reward of this block is paid to it by
+    /// the cellbase of block `number + finalization_delay_length`, see `cellbase_reward`.
+    pub fn cellbase_lock(mut self, lock: Script) -> Self {
+        self.cellbase_lock = lock;
+        self
+    }
+
+    pub fn cellbase_message(mut self, message: Bytes) -> Self {
+        self.cellbase_message = message;
+        self
+    }
+
+    /// Set the cellbase output, which pays the finalized reward of block
+    /// `number - finalization_delay_length` to `lock`, the lock script in the cellbase witness
+    /// of that block.
+    pub fn cellbase_reward(mut self, reward: Capacity, lock: Script) -> Self {
+        self.cellbase_reward = Some((reward, lock));
+        self
+    }
+
+    /// Assemble the block. `resolve_cell` returns the cell output and data of the out-point
+    /// consumed by the committed transactions; the outputs of transactions within this block
+    /// are resolved by the assembler itself.
+    pub fn assemble<F>(self, resolve_cell: F) -> Result<BlockView, String>
+    where
+        F: Fn(&OutPoint) -> Option<(CellOutput, Bytes)>,
+    {
+        if !self.consensus.permanent_difficulty_in_dummy {
+            return Err(
+                "BlockAssembler only supports chains running with permanent_difficulty_in_dummy"
+                    .to_string(),
+            );
+        }
+        let number = self.parent.number() + 1;
+        let epoch = next_epoch(self.parent.epoch());
+        let cellbase = self.build_cellbase(number);
+        let dao = self.calculate_dao(number, epoch, &cellbase, &resolve_cell)?;
+        let timestamp = self
+            .timestamp
+            .unwrap_or_else(|| self.parent.timestamp() + 1);
+
+        let block = BlockBuilder::default()
+            .version(self.consensus.block_version.value().pack())
+            .parent_hash(self.parent.hash())
+            .number(number.pack())
+            .epoch(epoch.pack())
+            .timestamp(timestamp.pack())
+            .compact_target(self.parent.compact_target().pack())
+            .dao(dao)
+            .nonce(0u128.pack())
+            .transaction(cellbase)
+            .transactions(self.transactions)
+            .proposals(self.proposals)
+            .uncles(self.uncles)
+            .extension(self.extension.map(|extension| extension.pack()))
+            .build();
+        crate::trace!(
+            "BlockAssembler::assemble {} {:#x} upon {:#x}",
+            block.number(),
+            block.hash(),
+            block.parent_hash()
+        );
+        Ok(block)
+    }
+
+    fn build_cellbase(&self, number: BlockNumber) -> TransactionView {
+        let witness = CellbaseWitness::new_builder()
+            .lock(self.cellbase_lock.clone())
+            .message(self.cellbase_message.pack())
+            .build();
+        let outputs = self.cellbase_reward.as_ref().map(|(reward, lock)| {
+            CellOutput::new_builder()
+                .capacity(reward.pack())
+                .lock(lock.clone())
+                .build()
+        });
+        TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(number))
+            .witness(witness.as_bytes().pack())
+            .outputs(outputs)
+            .outputs_data(self.cellbase_reward.as_ref().map(|_| Bytes::new().pack()))
+            .build()
+    }
+
+    // The same calculation as `DaoCalculator::dao_field` of ckb
+    fn calculate_dao<F>(
+        &self,
+        number: BlockNumber,
+        epoch: EpochNumberWithFraction,
+        cellbase: &TransactionView,
+        resolve_cell: &F,
+    ) -> Result<Byte32, String>
+    where
+        F: Fn(&OutPoint) -> Option<(CellOutput, Bytes)>,
+    {
+        let dao_type_hash = self.consensus.dao_type_hash.clone().map(|hash| hash.pack());
+        let mut block_cells: HashMap<OutPoint, (CellOutput, Bytes)> = HashMap::new();
+        let mut added_occupied = 0u64;
+        let mut freed_occupied = 0u64;
+        for tx in ::std::iter::once(cellbase).chain(self.transactions.iter()) {
+            if !tx.is_cellbase() {
+                for out_point in tx.input_pts_iter() {
+                    let (output, data) = block_cells
+                        .get(&out_point)
+                        .cloned()
+                        .or_else(|| resolve_cell(&out_point))
+                        .ok_or_else(|| {
+                            format!(
+                                "failed to resolve input {:?} of transaction {:#x}",
+                                out_point,
+                                tx.hash()
+                            )
+                        })?;
+                    let is_dao_withdrawing = dao_type_hash.is_some()
+                        && output.type_().to_opt().map(|script| script.code_hash())
+                            == dao_type_hash
+                        && data.iter().any(|byte| *byte != 0);
+                    if is_dao_withdrawing {
+                        return Err(format!(
+                            "transaction {:#x} withdraws from NervosDAO, which is not supported",
+                            tx.hash()
+                        ));
+                    }
+                    freed_occupied = checked_add(freed_occupied, occupied(&output, &data)?)?;
+                }
+            }
+            for (index, (output, data)) in tx.outputs_with_data_iter().enumerate() {
+                added_occupied = checked_add(added_occupied, occupied(&output, &data)?)?;
+                block_cells.insert(OutPoint::new(tx.hash(), index as u32), (output, data));
+            }
+        }
+
+        let (parent_ar, parent_c, parent_s, parent_u) = extract_dao_data(&self.parent.dao());
+        let start_number = number - epoch.index();
+        let primary_epoch_reward = self.consensus.initial_primary_epoch_reward.value()
+            >> (epoch.number() / self.consensus.primary_epoch_reward_halving_interval.value());
+        let current_g2 = block_issuance(
+            self.consensus.secondary_epoch_reward.value(),
+            epoch.length(),
+            start_number,
+            number,
+        );
+        let current_g = checked_add(
+            block_issuance(primary_epoch_reward, epoch.length(), start_number, number),
+            current_g2,
+        )?;
+        let miner_issuance =
+            (u128::from(current_g2) * u128::from(parent_u) / u128::from(parent_c)) as u64;
+        let nervosdao_issuance = current_g2 - miner_issuance;
+        let current_c = checked_add(parent_c, current_g)?;
+        let current_u = checked_add(parent_u, added_occupied)?
+            .checked_sub(freed_occupied)
+            .ok_or_else(|| "occupied capacities underflow".to_string())?;
+        let current_s = checked_add(parent_s, nervosdao_issuance)?;
+        let ar_increase =
+            (u128::from(parent_ar) * u128::from(current_g2) / u128::from(parent_c)) as u64;
+        let current_ar = checked_add(parent_ar, ar_increase)?;
+        Ok(pack_dao_data(current_ar, current_c, current_s, current_u))
+    }
+}
+
+fn next_epoch(parent_epoch: EpochNumberWithFraction) -> EpochNumberWithFraction {
+    if parent_epoch.index() + 1 == parent_epoch.length() {
+        EpochNumberWithFraction::new(parent_epoch.number() + 1, 0, parent_epoch.length())
+    } else {
+        EpochNumberWithFraction::new(
+            parent_epoch.number(),
+            parent_epoch.index() + 1,
+            parent_epoch.length(),
+        )
+    }
+}
+
+// The epoch reward is divided equally among the blocks, and the remainder goes to the first
+// blocks of the epoch
+fn block_issuance(
+    epoch_reward: u64,
+    epoch_length: u64,
+    start_number: BlockNumber,
+    number: BlockNumber,
+) -> u64 {
+    let remainder = epoch_reward % epoch_length;
+    if number < start_number + remainder {
+        epoch_reward / epoch_length + 1
+    } else {
+        epoch_reward / epoch_length
+    }
+}
+
+fn occupied(output: &CellOutput, data: &Bytes) -> Result<u64, String> {
+    Capacity::bytes(data.len())
+        .and_then(|data_capacity| output.occupied_capacity(data_capacity))
+        .map(|capacity| capacity.as_u64())
+        .map_err(|err| format!("failed to calculate occupied capacity, error: {}", err))
+}
+
+fn checked_add(a: u64, b: u64) -> Result<u64, String> {
+    a.checked_add(b)
+        .ok_or_else(|| format!("capacity overflow, {} + {}", a, b))
+}
+
+// dao field layout: C(8 bytes) | AR(8 bytes) | S(8 bytes) | U(8 bytes), little-endian
+pub(crate) fn extract_dao_data(dao: &Byte32) -> (u64, u64, u64, u64) {
+    let data = dao.raw_data();
+    let field = |i: usize| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&data[i * 8..(i + 1) * 8]);
+        u64::from_le_bytes(buf)
+    };
+    (field(1), field(0), field(2), field(3))
+}
+
+fn pack_dao_data(ar: u64, c: u64, s: u64, u: u64) -> Byte32 {
+    let mut buf = [0u8; 32];
+    buf[0..8].copy_from_slice(&c.to_le_bytes());
+    buf[8..16].copy_from_slice(&ar.to_le_bytes());
+    buf[16..24].copy_from_slice(&s.to_le_bytes());
+    buf[24..32].copy_from_slice(&u.to_le_bytes());
+    buf.pack()
+}
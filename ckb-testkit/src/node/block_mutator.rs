@@ -0,0 +1,236 @@
+use ckb_jsonrpc_types::{BlockTemplate, CellbaseTemplate, TransactionTemplate, UncleTemplate};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        BlockNumber, BlockView, EpochNumberWithFraction, HeaderView, TransactionView,
+        UncleBlockView, Version,
+    },
+    packed::{self, Byte32, ProposalShortId},
+    prelude::*,
+};
+
+/// BlockMutator builds a block from a `BlockTemplate` with some fields overridden, so that cases
+/// can produce deliberately invalid blocks to test header and body verification.
+///
+/// The fields carried by the template are modified on the template directly, see
+/// `BlockMutator::template`. The merkle roots are recalculated by `build` unless overridden.
+///
+/// NOTE: `submit_block` recalculates the merkle roots on the node side, so the overridden roots
+/// only take effect when the block is relayed via a `Connector`.
+///
+/// ```ignore
+/// let template = node.rpc_client().get_block_template(None, None, None);
+/// let block = BlockMutator::new(template).version(1).nonce(42).build();
+/// let result = node.rpc_client().submit_block("".to_owned(), block.data().into());
+/// ```
+#[derive(Debug, Clone)]
+pub struct BlockMutator {
+    template: BlockTemplate,
+    nonce: Option<u128>,
+    transactions_root: Option<Byte32>,
+    proposals_hash: Option<Byte32>,
+    extra_hash: Option<Byte32>,
+}
+
+impl BlockMutator {
+    pub fn new(template: BlockTemplate) -> Self {
+        Self {
+            template,
+            nonce: None,
+            transactions_root: None,
+            proposals_hash: None,
+            extra_hash: None,
+        }
+    }
+
+    /// Return the modified template, e.g. to calculate the dao field via RPC.
+    pub fn template(&self) -> &BlockTemplate {
+        &self.template
+    }
+
+    pub fn version(mut self, version: Version) -> Self {
+        self.template.version = version.into();
+        self
+    }
+
+    pub fn compact_target(mut self, compact_target: u32) -> Self {
+        self.template.compact_target = compact_target.into();
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.template.current_time = timestamp.into();
+        self
+    }
+
+    pub fn number(mut self, number: BlockNumber) -> Self {
+        self.template.number = number.into();
+        self
+    }
+
+    pub fn epoch(mut self, epoch: EpochNumberWithFraction) -> Self {
+        self.template.epoch = epoch.full_value().into();
+        self
+    }
+
+    pub fn parent_hash(mut self, parent_hash: Byte32) -> Self {
+        self.template.parent_hash = parent_hash.unpack();
+        self
+    }
+
+    pub fn dao(mut self, dao: Byte32) -> Self {
+        self.template.dao = dao.into();
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u128) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Set the block extension, `None` removes it.
+    pub fn extension(mut self, extension: Option<Bytes>) -> Self {
+        self.template.extension = extension.map(|extension| extension.pack().into());
+        self
+    }
+
+    /// Include `uncle`, unless it is included already.
+    pub fn uncle(mut self, uncle: &UncleBlockView) -> Self {
+        let uncle_template = uncle_template(uncle);
+        if !self
+            .template
+            .uncles
+            .iter()
+            .any(|u| u.hash == uncle_template.hash)
+        {
+            self.template.uncles.push(uncle_template);
+        }
+        self
+    }
+
+    /// Replace all the uncles, duplicates are kept as is.
+    pub fn uncles(mut self, uncles: &[UncleBlockView]) -> Self {
+        self.template.uncles = uncles.iter().map(uncle_template).collect();
+        self
+    }
+
+    /// Propose `proposal_short_id`, unless it is proposed already.
+    pub fn proposal(mut self, proposal_short_id: ProposalShortId) -> Self {
+        let proposal_short_id = proposal_short_id.into();
+        if !self.template.proposals.contains(&proposal_short_id) {
+            self.template.proposals.push(proposal_short_id);
+        }
+        self
+    }
+
+    /// Replace all the proposals, duplicates are kept as is.
+    pub fn proposals(mut self, proposal_short_ids: Vec<ProposalShortId>) -> Self {
+        self.template.proposals = proposal_short_ids.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Commit `transaction`, unless it is committed already.
+    pub fn transaction(mut self, transaction: &TransactionView) -> Self {
+        if !self
+            .template
+            .transactions
+            .iter()
+            .any(|tx| tx.hash.as_bytes() == transaction.hash().as_bytes())
+        {
+            self.template
+                .transactions
+                .push(transaction_template(transaction));
+        }
+        self
+    }
+
+    /// Replace all the committed transactions except cellbase, duplicates are kept as is.
+    pub fn transactions(mut self, transactions: &[TransactionView]) -> Self {
+        self.template.transactions = transactions.iter().map(transaction_template).collect();
+        self
+    }
+
+    pub fn cellbase(mut self, cellbase: &TransactionView) -> Self {
+        self.template.cellbase = CellbaseTemplate {
+            hash: cellbase.hash().unpack(),
+            cycles: None,
+            data: cellbase.data().into(),
+        };
+        self
+    }
+
+    /// Replace the witnesses of cellbase with the single `witness`.
+    pub fn cellbase_witness(self, witness: Bytes) -> Self {
+        let cellbase = packed::Transaction::from(self.template.cellbase.data.clone())
+            .into_view()
+            .as_advanced_builder()
+            .set_witnesses(vec![witness.pack()])
+            .build();
+        self.cellbase(&cellbase)
+    }
+
+    pub fn transactions_root(mut self, transactions_root: Byte32) -> Self {
+        self.transactions_root = Some(transactions_root);
+        self
+    }
+
+    pub fn proposals_hash(mut self, proposals_hash: Byte32) -> Self {
+        self.proposals_hash = Some(proposals_hash);
+        self
+    }
+
+    pub fn extra_hash(mut self, extra_hash: Byte32) -> Self {
+        self.extra_hash = Some(extra_hash);
+        self
+    }
+
+    pub fn build(self) -> BlockView {
+        let block = packed::Block::from(self.template)
+            .into_view_without_reset_header()
+            .as_advanced_builder()
+            .build();
+        let header = block.header();
+        let header = HeaderView::new_advanced_builder()
+            .version(header.version().pack())
+            .parent_hash(header.parent_hash())
+            .timestamp(header.timestamp().pack())
+            .number(header.number().pack())
+            .epoch(header.epoch().pack())
+            .compact_target(header.compact_target().pack())
+            .dao(header.dao())
+            .nonce(self.nonce.unwrap_or_else(|| header.nonce()).pack())
+            .transactions_root(
+                self.transactions_root
+                    .unwrap_or_else(|| header.transactions_root()),
+            )
+            .proposals_hash(
+                self.proposals_hash
+                    .unwrap_or_else(|| header.proposals_hash()),
+            )
+            .extra_hash(self.extra_hash.unwrap_or_else(|| header.extra_hash()))
+            .build();
+        block.as_advanced_builder().header(header).build_unchecked()
+    }
+}
+
+fn uncle_template(uncle: &UncleBlockView) -> UncleTemplate {
+    UncleTemplate {
+        hash: uncle.hash().unpack(),
+        required: false,
+        proposals: uncle
+            .data()
+            .proposals()
+            .into_iter()
+            .map(Into::into)
+            .collect(),
+        header: uncle.header().data().into(),
+    }
+}
+
+fn transaction_template(transaction: &TransactionView) -> TransactionTemplate {
+    TransactionTemplate {
+        hash: transaction.hash().unpack(),
+        data: transaction.data().into(),
+        ..Default::default()
+    }
+}
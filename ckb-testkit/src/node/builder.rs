@@ -1,9 +1,8 @@
-use crate::{Node, NodeOptions};
-use ckb_jsonrpc_types::TransactionTemplate;
+use crate::{BlockMutator, Node, NodeOptions};
 use ckb_types::{
-    core::{BlockNumber, TransactionView},
-    packed::{self, ProposalShortId},
-    prelude::*,
+    bytes::Bytes,
+    core::{BlockNumber, TransactionView, UncleBlockView, Version},
+    packed::{self, Byte32, ProposalShortId},
 };
 use std::collections::HashMap;
 
@@ -28,6 +27,41 @@ pub enum BuildInstruction {
         template_number: BlockNumber,
         timestamp: u64,
     },
+    /// Include `uncle` into the block.
+    Uncle {
+        template_number: BlockNumber,
+        uncle: UncleBlockView,
+    },
+    /// Set the block extension, which is only allowed after CKB2021 activated.
+    Extension {
+        template_number: BlockNumber,
+        extension: Bytes,
+    },
+    /// Replace the cellbase of the block.
+    Cellbase {
+        template_number: BlockNumber,
+        cellbase: TransactionView,
+    },
+    /// Set the header version.
+    Version {
+        template_number: BlockNumber,
+        version: Version,
+    },
+    /// Set the header dao field instead of the one calculated via `calculate_dao_field`.
+    Dao {
+        template_number: BlockNumber,
+        dao: Byte32,
+    },
+    /// Replace the witnesses of cellbase with the single `witness`.
+    CellbaseWitness {
+        template_number: BlockNumber,
+        witness: Bytes,
+    },
+    /// Set the header nonce.
+    Nonce {
+        template_number: BlockNumber,
+        nonce: u128,
+    },
 }
 
 impl BuildInstruction {
@@ -46,6 +80,27 @@ impl BuildInstruction {
             BuildInstruction::HeaderTimestamp {
                 template_number, ..
             } => *template_number,
+            BuildInstruction::Uncle {
+                template_number, ..
+            } => *template_number,
+            BuildInstruction::Extension {
+                template_number, ..
+            } => *template_number,
+            BuildInstruction::Cellbase {
+                template_number, ..
+            } => *template_number,
+            BuildInstruction::Version {
+                template_number, ..
+            } => *template_number,
+            BuildInstruction::Dao {
+                template_number, ..
+            } => *template_number,
+            BuildInstruction::CellbaseWitness {
+                template_number, ..
+            } => *template_number,
+            BuildInstruction::Nonce {
+                template_number, ..
+            } => *template_number,
         }
     }
 }
@@ -81,7 +136,7 @@ impl Node {
         // build chain according to instructions
         let mut next_template_number = self.get_tip_block_number() + 1;
         loop {
-            let mut template = self.rpc_client().get_block_template(None, None, None);
+            let template = self.rpc_client().get_block_template(None, None, None);
             let number = template.number.value();
             if number > target_height {
                 break;
@@ -95,64 +150,66 @@ impl Node {
 
             if let Some(instructions) = instructions_map.remove(&number) {
                 let mut process_without_verify = false;
+                let mut dao = None;
+                let mut mutator = BlockMutator::new(template);
                 for instruction in instructions {
-                    match &instruction {
+                    mutator = match &instruction {
                         BuildInstruction::SendTransaction { transaction, .. } => {
                             self.rpc_client()
                                 .send_transaction_result(transaction.data().into())
                                 .map_err(|err| {
                                     format!("failed to execute {:?}, error: {}", instruction, err)
                                 })?;
+                            mutator
                         }
                         BuildInstruction::Propose {
                             proposal_short_id, ..
-                        } => {
-                            let proposal_short_id = proposal_short_id.to_owned().into();
-                            if !template.proposals.contains(&proposal_short_id) {
-                                template.proposals.push(proposal_short_id);
-                            }
-                        }
+                        } => mutator.proposal(proposal_short_id.to_owned()),
                         BuildInstruction::Commit { transaction, .. } => {
-                            let transaction_template = TransactionTemplate {
-                                hash: transaction.hash().unpack(),
-                                data: transaction.data().into(),
-                                ..Default::default()
-                            };
-                            if !template
-                                .transactions
-                                .iter()
-                                .any(|tx| tx.hash.as_bytes() == transaction.hash().as_bytes())
-                            {
-                                template.transactions.push(transaction_template);
-                            }
+                            mutator.transaction(transaction)
                         }
                         BuildInstruction::ProcessWithoutVerify { .. } => {
                             process_without_verify = true;
+                            mutator
                         }
                         BuildInstruction::HeaderTimestamp { timestamp, .. } => {
-                            template.current_time = ckb_jsonrpc_types::Timestamp::from(*timestamp);
+                            mutator.timestamp(*timestamp)
                         }
-                    }
+                        BuildInstruction::Uncle { uncle, .. } => mutator.uncle(uncle),
+                        BuildInstruction::Extension { extension, .. } => {
+                            mutator.extension(Some(extension.clone()))
+                        }
+                        BuildInstruction::Cellbase { cellbase, .. } => mutator.cellbase(cellbase),
+                        BuildInstruction::Version { version, .. } => mutator.version(*version),
+                        BuildInstruction::Dao { dao: dao_field, .. } => {
+                            dao = Some(dao_field.clone());
+                            mutator
+                        }
+                        BuildInstruction::CellbaseWitness { witness, .. } => {
+                            mutator.cellbase_witness(witness.clone())
+                        }
+                        BuildInstruction::Nonce { nonce, .. } => mutator.nonce(*nonce),
+                    };
                 }
-                let updated_block: packed::Block = {
-                    let dao_field = self
+                let dao = match dao {
+                    Some(dao) => dao,
+                    None => self
                         .rpc_client()
-                        .calculate_dao_field(template.clone())
+                        .calculate_dao_field(mutator.template().clone())
                         .map_err(|err| {
                             format!(
                                 "failed to calculate dao field, block number: {}, error: {}",
                                 number, err
                             )
-                        })?;
-                    template.dao = dao_field.into();
-                    template.into()
+                        })?,
                 };
+                let updated_block = mutator.dao(dao).build();
                 if process_without_verify {
                     self.rpc_client()
-                        .process_block_without_verify(updated_block.into(), true);
+                        .process_block_without_verify(updated_block.data().into(), true);
                 } else {
                     self.rpc_client()
-                        .submit_block("".to_string(), updated_block.into())
+                        .submit_block("".to_string(), updated_block.data().into())
                         .map_err(|err| {
                             format!("failed to send block {}, error: {}", number, err)
                         })?;
@@ -167,7 +224,7 @@ impl Node {
         Ok(())
     }
 
-    /// Return the cloned node with `node_name`.
+    /// Return the cloned node with `node_name`. The cloned node belongs to the same case.
     pub fn clone_node(&self, node_name: &str) -> Node {
         let mut target_node = {
             let node_options = NodeOptions {
@@ -175,7 +232,7 @@ impl Node {
                 ..self.node_options().clone()
             };
             let is_ckb2021 = self.rpc_client().ckb2021;
-            Node::init("cloned_node", node_options, is_ckb2021)
+            Node::init(&self.case_name, node_options, is_ckb2021)
         };
         target_node.start();
 
@@ -0,0 +1,44 @@
+use crate::Node;
+use ckb_types::{
+    core::ScriptHashType,
+    packed::{CellDep, OutPoint, Script},
+    prelude::*,
+};
+
+impl Node {
+    /// Return the Nervos DAO type script, `code_hash` is `consensus.dao_type_hash`.
+    pub fn dao_type_script(&self) -> Script {
+        let dao_type_hash = self
+            .consensus()
+            .dao_type_hash
+            .clone()
+            .expect("consensus.dao_type_hash should be specified");
+        Script::new_builder()
+            .code_hash(dao_type_hash.pack())
+            .hash_type(ScriptHashType::Type.into())
+            .build()
+    }
+
+    /// Return the cell dep of the genesis Nervos DAO system cell.
+    pub fn dao_cell_dep(&self) -> CellDep {
+        let dao_type_hash = self.dao_type_script().code_hash();
+        let genesis_cellbase = self
+            .genesis_block()
+            .transaction(0)
+            .expect("genesis cellbase");
+        let index = genesis_cellbase
+            .outputs()
+            .into_iter()
+            .position(|output| {
+                output
+                    .type_()
+                    .to_opt()
+                    .map(|type_| type_.calc_script_hash() == dao_type_hash)
+                    .unwrap_or(false)
+            })
+            .expect("genesis cellbase should contain the dao system cell");
+        CellDep::new_builder()
+            .out_point(OutPoint::new(genesis_cellbase.hash(), index as u32))
+            .build()
+    }
+}
@@ -0,0 +1,87 @@
+use crate::util::hardfork::{is_rfc_activated, rfc_epoch_number};
+use crate::Node;
+use ckb_types::core::{BlockNumber, EpochNumber};
+
+impl Node {
+    /// Return the epoch at which `rfc` activates, e.g. `node.rfc_epoch_number("0032")`. Panic if
+    /// `rfc` is never activated on this chain.
+    pub fn rfc_epoch_number(&self, rfc: &str) -> EpochNumber {
+        rfc_epoch_number(self.consensus(), rfc).unwrap_or_else(|| {
+            panic!(
+                "rfc{} is never activated, node name: {}",
+                rfc,
+                self.node_name()
+            )
+        })
+    }
+
+    /// Return whether `rfc` is activated at the tip block.
+    pub fn is_rfc_activated(&self, rfc: &str) -> bool {
+        let tip_epoch = self.get_tip_block().epoch().number();
+        is_rfc_activated(self.consensus(), rfc, tip_epoch)
+    }
+
+    /// Return the number of the first block of epoch `epoch_number` on the live chain.
+    ///
+    /// The start numbers of the reached epochs and the next epoch are exact. The further ones
+    /// are predictable only if the epoch length is fixed, i.e. `permanent_difficulty_in_dummy`,
+    /// otherwise return an error.
+    pub fn epoch_start_number(&self, epoch_number: EpochNumber) -> Result<BlockNumber, String> {
+        if let Some(epoch) = self.rpc_client().get_epoch_by_number(epoch_number) {
+            return Ok(epoch.start_number.value());
+        }
+        let current_epoch = self.rpc_client().get_current_epoch();
+        let current_number = current_epoch.number.value();
+        let length = current_epoch.length.value();
+        let next_start_number = current_epoch.start_number.value() + length;
+        if epoch_number == current_number + 1 {
+            Ok(next_start_number)
+        } else if self.consensus().permanent_difficulty_in_dummy {
+            Ok(next_start_number + (epoch_number - current_number - 1) * length)
+        } else {
+            Err(format!(
+                "unpredictable start number of epoch {}, the current epoch is {}, node name: {}",
+                epoch_number,
+                current_number,
+                self.node_name()
+            ))
+        }
+    }
+
+    /// Return the number of the first block following the rules of `rfc`, see
+    /// `Node::epoch_start_number`.
+    pub fn rfc_activation_number(&self, rfc: &str) -> Result<BlockNumber, String> {
+        self.epoch_start_number(self.rfc_epoch_number(rfc))
+    }
+
+    /// Mine to the `offset`-th block from the activation of `rfc`, e.g.
+    /// `node.mine_to_rfc_activation("0032", -1)` mines to one block before RFC0032 activates.
+    /// Mine epoch by epoch while the activation number is unpredictable.
+    pub fn mine_to_rfc_activation(&self, rfc: &str, offset: i64) -> BlockNumber {
+        loop {
+            match self.rfc_activation_number(rfc) {
+                Ok(activation_number) => {
+                    let target = activation_number as i64 + offset;
+                    let tip_number = self.get_tip_block_number();
+                    assert!(
+                        target >= tip_number as i64,
+                        "the tip {} already passed the target {} (rfc{} activation {} {:+}), \
+                        node name: {}",
+                        tip_number,
+                        target,
+                        rfc,
+                        activation_number,
+                        offset,
+                        self.node_name()
+                    );
+                    self.mine_to(target as BlockNumber);
+                    return target as BlockNumber;
+                }
+                Err(_) => {
+                    let current_epoch = self.rpc_client().get_current_epoch();
+                    self.mine_to(current_epoch.start_number.value() + current_epoch.length.value());
+                }
+            }
+        }
+    }
+}
@@ -1,61 +1,125 @@
+use super::indexer_worker::{sync_indexer_step, SyncAction};
+use crate::util::Waiter;
 use crate::Node;
-use ckb_types::core::cell::{CellMeta, CellMetaBuilder};
-use ckb_types::core::{BlockView, EpochNumberWithFraction, TransactionInfo};
-use ckb_types::packed::OutPoint;
+use ckb_indexer::indexer::KeyPrefix;
+use ckb_indexer::store::{IteratorDirection, Store};
+use ckb_types::core::cell::CellMeta;
+use ckb_types::core::{EpochNumberWithFraction, HeaderView};
+use ckb_types::packed::{Byte32, OutPoint, Script};
+use ckb_types::prelude::*;
+use std::time::Duration;
+
+// How long `Node::indexer` waits for the indexer worker to catch up with the node tip
+const INDEXER_SYNC_TIMEOUT: Duration = Duration::from_secs(60);
 
 impl Node {
-    // NOTICE: This function use `indexer_unchecked`
+    /// Return the live cell `out_point` from the cell source, see `Node::set_cell_source`.
     pub fn get_cell_meta(&self, out_point: OutPoint) -> Option<CellMeta> {
-        let detail = self
-            .indexer_unchecked()
-            .get_detailed_live_cell(&out_point)
-            .expect("indexer get_detailed_live_cell")?;
-        // FIXME now the transaction_info.block_epoch is fake
-        // let block_epoch = self.get_block(detail.block_hash.clone()).epoch();
-        let block_epoch = EpochNumberWithFraction::new_unchecked(0, 0, 0);
-        let txinfo = TransactionInfo::new(
-            detail.block_number,
-            block_epoch,
-            detail.block_hash,
-            detail.tx_index as usize,
-        );
-        Some(
-            CellMetaBuilder::from_cell_output(detail.cell_output, detail.cell_data.raw_data())
-                .out_point(out_point)
-                .transaction_info(txinfo)
-                .build(),
-        )
+        self.cell_source.get_cell_meta(self, &out_point)
+    }
+
+    /// Return the live cells locked by `lock_script` from the cell source.
+    pub fn get_live_cells_by_lock_script(&self, lock_script: &Script) -> Vec<CellMeta> {
+        self.cell_source
+            .get_live_cells_by_lock_script(self, lock_script)
+    }
+
+    /// Return the epoch of block `block_hash`. The epochs are cached as they never change for
+    /// a given block hash.
+    pub fn get_block_epoch(&self, block_hash: Byte32) -> EpochNumberWithFraction {
+        if let Some(epoch) = self.block_epochs.lock().unwrap().get(&block_hash) {
+            return *epoch;
+        }
+        let header: HeaderView = self
+            .rpc_client()
+            .get_header(block_hash.clone())
+            .unwrap_or_else(|| panic!("block {} should exist", block_hash))
+            .into();
+        let epoch = header.epoch();
+        self.block_epochs.lock().unwrap().insert(block_hash, epoch);
+        epoch
+    }
+
+    /// Return whether `cell_meta` can be spent in the block following the current tip, i.e.
+    /// it is not a cellbase output or it has passed `consensus.cellbase_maturity`.
+    pub fn is_mature(&self, cell_meta: &CellMeta) -> bool {
+        let txinfo = match cell_meta.transaction_info.as_ref() {
+            Some(txinfo) if txinfo.is_cellbase() => txinfo,
+            _ => return true,
+        };
+        let cellbase_maturity =
+            EpochNumberWithFraction::from_full_value(self.consensus().cellbase_maturity.into());
+        let tip_epoch = self.get_tip_block().epoch();
+        let next_epoch = if tip_epoch.index() + 1 < tip_epoch.length() {
+            EpochNumberWithFraction::new(
+                tip_epoch.number(),
+                tip_epoch.index() + 1,
+                tip_epoch.length(),
+            )
+        } else {
+            // The next epoch length is unknown yet; the next block is at the start of the
+            // next epoch, whose rational value does not depend on the length
+            EpochNumberWithFraction::new(tip_epoch.number() + 1, 0, 1)
+        };
+        next_epoch.to_rational()
+            >= txinfo.block_epoch.to_rational() + cellbase_maturity.to_rational()
+    }
+
+    /// Return all the live out-points recorded by the indexer, at the indexer tip.
+    pub fn get_live_out_points(&self) -> Vec<OutPoint> {
+        let key_prefix = vec![KeyPrefix::OutPoint as u8];
+        self.indexer()
+            .store()
+            .iter(&key_prefix, IteratorDirection::Forward)
+            .expect("indexer iterate out-points")
+            .take_while(|(key, _)| key.starts_with(&key_prefix))
+            .map(|(key, _)| OutPoint::from_slice(&key[1..]).expect("stored out-point"))
+            .collect()
     }
 
     pub(super) fn wait_for_indexer_synced(&self) {
         let indexer = self.indexer.as_ref().expect("uninitialized indexer");
-        loop {
-            if let Some((tip_number, tip_hash)) = indexer.tip().expect("indexer tip") {
-                let block_opt = self.rpc_client().get_block_by_number(tip_number + 1);
-                if let Some(block) = block_opt {
-                    let block: BlockView = block.into();
-                    if block.parent_hash() != tip_hash {
-                        indexer.rollback().expect("indexer rollback")
-                    } else {
-                        indexer.append(&block).expect("indexer append");
-                    }
-                } else {
-                    let block_hash_opt = self.rpc_client().get_block_hash(tip_number);
-                    if block_hash_opt != Some(tip_hash) {
-                        indexer.rollback().expect("indexer rollback");
-                    } else {
-                        break;
-                    }
+        let worker = match self.indexer_worker.as_ref() {
+            Some(worker) if !worker.is_stopped() => worker,
+            // Sync the indexer by ourselves if no worker is syncing it, e.g. the worker was
+            // stopped via another clone of this node
+            _ => return self.sync_indexer(),
+        };
+
+        // The indexer worker is syncing, wait for it to catch up with the current tip
+        let tip_number = self.get_tip_block_number();
+        let worker_synced = Waiter::new(
+            format!("node {} indexer synced to {}", self.node_name(), tip_number),
+            INDEXER_SYNC_TIMEOUT,
+        )
+        .initial_interval(Duration::from_millis(10))
+        .max_interval(worker.poll_interval())
+        .wait_for(|| {
+            if worker.is_stopped() {
+                return Ok(false);
+            }
+            let indexed = indexer.tip().expect("indexer tip");
+            match indexed {
+                Some((indexed_number, ref indexed_hash))
+                    if indexed_number >= tip_number
+                        && self.rpc_client().get_block_hash(indexed_number).as_ref()
+                            == Some(indexed_hash) =>
+                {
+                    Ok(true)
                 }
-            } else {
-                let block = self
-                    .rpc_client()
-                    .get_block_by_number(0)
-                    .expect("rpc get genesis block");
-                indexer
-                    .append(&block.into())
-                    .expect("indexer append genesis block");
+                // (indexed number, tip number)
+                _ => Err((indexed.map(|(number, _)| number), tip_number)),
             }
+        })
+        .unwrap_or_else(|err| panic!("{}, node.log: {}", err, self.log_path().display()));
+        if !worker_synced {
+            // The worker was stopped while waiting
+            self.sync_indexer();
         }
     }
+
+    fn sync_indexer(&self) {
+        let indexer = self.indexer.as_ref().expect("uninitialized indexer");
+        while sync_indexer_step(self.rpc_client(), indexer).action != SyncAction::Synced {}
+    }
 }
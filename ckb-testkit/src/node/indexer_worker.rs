@@ -0,0 +1,230 @@
+use crate::rpc::RpcClient;
+use crate::Node;
+use ckb_indexer::{indexer::Indexer, store::RocksdbStore};
+use ckb_types::core::{BlockNumber, BlockView};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep, JoinHandle};
+use std::time::Duration;
+
+/// The default interval at which `IndexerWorker` polls the node once the indexer catches up.
+pub const DEFAULT_INDEXER_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// The progress of an `IndexerWorker`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexerMetrics {
+    /// The number of the latest block appended to the indexer.
+    pub indexed_number: BlockNumber,
+    /// The tip number of the node observed by the worker at the latest poll.
+    pub chain_tip_number: BlockNumber,
+    /// The total number of appended blocks.
+    pub appended_blocks: u64,
+    /// The total number of rolled-back blocks.
+    pub rollbacks: u64,
+}
+
+impl IndexerMetrics {
+    /// Return how many blocks the indexer lags behind the observed chain tip.
+    pub fn lag(&self) -> BlockNumber {
+        self.chain_tip_number.saturating_sub(self.indexed_number)
+    }
+}
+
+/// A background thread that keeps the node indexer following the chain, by polling the node
+/// via RPC. The worker is shared among the node clones; the thread stops on `Node::stop`, or
+/// when the node and all its clones are dropped.
+pub(crate) struct IndexerWorker {
+    metrics: Arc<Mutex<IndexerMetrics>>,
+    stopped: Arc<AtomicBool>,
+    handle: Mutex<Option<JoinHandle<()>>>,
+    poll_interval: Duration,
+}
+
+impl IndexerWorker {
+    pub(crate) fn spawn(
+        node_name: String,
+        rpc_client: RpcClient,
+        indexer: Indexer<RocksdbStore>,
+        poll_interval: Duration,
+    ) -> Self {
+        let indexed_number = indexer
+            .tip()
+            .expect("indexer tip")
+            .map(|(number, _)| number)
+            .unwrap_or(0);
+        let metrics = Arc::new(Mutex::new(IndexerMetrics {
+            indexed_number,
+            ..Default::default()
+        }));
+        let stopped: Arc<AtomicBool> = Default::default();
+        let handle = {
+            let metrics = Arc::clone(&metrics);
+            let stopped = Arc::clone(&stopped);
+            thread::Builder::new()
+                .name(format!("indexer-worker-{}", node_name))
+                .spawn(crate::logger::propagate(move || {
+                    while !stopped.load(Ordering::SeqCst) {
+                        // RpcClient panics on RPC errors, e.g. the node is stopping. Keep
+                        // retrying rather than dying silently.
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                            sync_indexer_step(&rpc_client, &indexer)
+                        }));
+                        match result {
+                            Ok(step) => {
+                                let mut metrics = metrics.lock().unwrap();
+                                metrics.indexed_number = step.indexed_number;
+                                metrics.chain_tip_number = step.chain_tip_number;
+                                match step.action {
+                                    SyncAction::Appended => metrics.appended_blocks += 1,
+                                    SyncAction::RolledBack => metrics.rollbacks += 1,
+                                    SyncAction::Synced => {
+                                        drop(metrics);
+                                        sleep(poll_interval);
+                                    }
+                                }
+                            }
+                            Err(_) => {
+                                crate::error!(
+                                    "[Node {}] indexer worker failed to sync, retry later",
+                                    node_name
+                                );
+                                sleep(poll_interval);
+                            }
+                        }
+                    }
+                }))
+                .expect("spawn indexer worker")
+        };
+        Self {
+            metrics,
+            stopped,
+            handle: Mutex::new(Some(handle)),
+            poll_interval,
+        }
+    }
+
+    pub(crate) fn metrics(&self) -> IndexerMetrics {
+        *self.metrics.lock().unwrap()
+    }
+
+    pub(crate) fn poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+
+    /// Stop the thread and wait for it to exit. The clones sharing this worker see it stopped
+    /// as well, and their metrics are frozen.
+    pub(crate) fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Return whether the worker is stopped, via `stop` on any of the clones sharing it.
+    pub(crate) fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for IndexerWorker {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SyncAction {
+    Appended,
+    RolledBack,
+    Synced,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SyncStep {
+    pub(super) action: SyncAction,
+    pub(super) indexed_number: BlockNumber,
+    pub(super) chain_tip_number: BlockNumber,
+}
+
+/// Move the indexer one block towards the node tip: append the next block, roll back the
+/// indexer tip if it is not on the main chain any more, or report synced.
+pub(super) fn sync_indexer_step(
+    rpc_client: &RpcClient,
+    indexer: &Indexer<RocksdbStore>,
+) -> SyncStep {
+    let chain_tip_number = rpc_client.get_tip_block_number();
+    let (action, indexed_number) = match indexer.tip().expect("indexer tip") {
+        Some((tip_number, tip_hash)) => {
+            if let Some(block) = rpc_client.get_block_by_number(tip_number + 1) {
+                let block: BlockView = block.into();
+                if block.parent_hash() != tip_hash {
+                    indexer.rollback().expect("indexer rollback");
+                    (SyncAction::RolledBack, tip_number.saturating_sub(1))
+                } else {
+                    indexer.append(&block).expect("indexer append");
+                    (SyncAction::Appended, tip_number + 1)
+                }
+            } else if rpc_client.get_block_hash(tip_number) != Some(tip_hash) {
+                indexer.rollback().expect("indexer rollback");
+                (SyncAction::RolledBack, tip_number.saturating_sub(1))
+            } else {
+                (SyncAction::Synced, tip_number)
+            }
+        }
+        None => {
+            let block = rpc_client
+                .get_block_by_number(0)
+                .expect("rpc get genesis block");
+            indexer
+                .append(&block.into())
+                .expect("indexer append genesis block");
+            (SyncAction::Appended, 0)
+        }
+    };
+    SyncStep {
+        action,
+        indexed_number,
+        chain_tip_number,
+    }
+}
+
+impl Node {
+    /// Start a background worker that keeps the indexer following the chain. Afterwards
+    /// `Node::indexer` waits for the worker instead of syncing the indexer by itself.
+    pub fn start_indexer_worker(&mut self, poll_interval: Duration) {
+        if self.indexer_worker.is_some() {
+            return;
+        }
+        let indexer = self
+            .indexer
+            .clone()
+            .expect("uninitialized indexer, start the node first");
+        let worker = IndexerWorker::spawn(
+            self.node_name().to_string(),
+            self.rpc_client().clone(),
+            indexer,
+            poll_interval,
+        );
+        self.indexer_worker = Some(Arc::new(worker));
+    }
+
+    /// Return the metrics of the indexer worker, `None` if the worker is not started.
+    pub fn indexer_metrics(&self) -> Option<IndexerMetrics> {
+        self.indexer_worker.as_ref().map(|worker| worker.metrics())
+    }
+
+    /// Return the indexer if it has indexed block `number`, without waiting or syncing.
+    pub fn indexer_at_least(&self, number: BlockNumber) -> Option<&Indexer<RocksdbStore>> {
+        let indexer = self.indexer.as_ref().expect("uninitialized indexer");
+        let indexed_number = match self.indexer_metrics() {
+            Some(metrics) => metrics.indexed_number,
+            None => indexer.tip().expect("indexer tip")?.0,
+        };
+        if indexed_number >= number {
+            Some(indexer)
+        } else {
+            None
+        }
+    }
+}
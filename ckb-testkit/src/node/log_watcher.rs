@@ -0,0 +1,163 @@
+use crate::util::waiter::scaled;
+use crate::Node;
+use regex::Regex;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// The interval at which `LogWatcher::expect_log` re-reads the log file.
+const LOG_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// LogWatcher tails a ckb `run.log`, returning the lines appended since the last read.
+pub struct LogWatcher {
+    log_path: PathBuf,
+    // The offset of the first unread byte
+    position: u64,
+    // The trailing incomplete line of the last read
+    partial: Vec<u8>,
+    // The lines read but not consumed yet
+    pending: VecDeque<String>,
+}
+
+impl LogWatcher {
+    /// Watch `log_path` from the beginning.
+    pub fn new<P: AsRef<Path>>(log_path: P) -> Self {
+        Self {
+            log_path: log_path.as_ref().to_path_buf(),
+            position: 0,
+            partial: Vec::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Watch `log_path` from its current end, i.e. only the lines logged afterwards.
+    pub fn from_end<P: AsRef<Path>>(log_path: P) -> Self {
+        let position = log_path
+            .as_ref()
+            .metadata()
+            .map(|metadata| metadata.len())
+            .unwrap_or(0);
+        Self {
+            position,
+            ..Self::new(log_path)
+        }
+    }
+
+    pub fn log_path(&self) -> &Path {
+        &self.log_path
+    }
+
+    /// Return the complete lines appended since the last read. Return nothing if the log file
+    /// does not exist yet.
+    pub fn read_lines(&mut self) -> Vec<String> {
+        let mut lines = self.pending.drain(..).collect::<Vec<_>>();
+        let mut file = match File::open(&self.log_path) {
+            Ok(file) => file,
+            Err(_) => return lines,
+        };
+        if file.seek(SeekFrom::Start(self.position)).is_err() {
+            return lines;
+        }
+        let mut reader = BufReader::new(file);
+        loop {
+            let mut buf = Vec::new();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    self.position += n as u64;
+                    self.partial.extend(buf);
+                    if self.partial.ends_with(b"\n") {
+                        let line = String::from_utf8_lossy(&self.partial)
+                            .trim_end()
+                            .to_string();
+                        self.partial.clear();
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    /// Wait until a line matching `pattern` is logged, return the line. `timeout` is stretched by
+    /// the time-scale factor. The lines read while waiting are consumed, so the next call starts
+    /// after the matched line.
+    pub fn expect_log(&mut self, pattern: &str, timeout: Duration) -> Result<String, String> {
+        let regex = Regex::new(pattern)
+            .unwrap_or_else(|err| panic!("invalid pattern \"{}\", error: {}", pattern, err));
+        let timeout = scaled(timeout);
+        let start_time = Instant::now();
+        loop {
+            let mut lines = self.read_lines().into_iter();
+            if let Some(line) = lines.by_ref().find(|line| regex.is_match(line)) {
+                // Keep the unchecked lines for the next read
+                self.pending.extend(lines);
+                return Ok(line);
+            }
+            if start_time.elapsed() > timeout {
+                return Err(format!(
+                    "timeout to wait for log \"{}\" in {}",
+                    pattern,
+                    self.log_path.display()
+                ));
+            }
+            sleep(LOG_POLL_INTERVAL);
+        }
+    }
+
+    /// Panic if any unread line matches `pattern`, e.g. `assert_no_log("panicked at")`.
+    pub fn assert_no_log(&mut self, pattern: &str) {
+        let regex = Regex::new(pattern)
+            .unwrap_or_else(|err| panic!("invalid pattern \"{}\", error: {}", pattern, err));
+        let matched = self
+            .read_lines()
+            .into_iter()
+            .filter(|line| regex.is_match(line))
+            .collect::<Vec<_>>();
+        assert!(
+            matched.is_empty(),
+            "unexpected log \"{}\" in {}:\n{}",
+            pattern,
+            self.log_path.display(),
+            matched.join("\n")
+        );
+    }
+
+    /// Return the unread ERROR and WARN lines.
+    pub fn read_problems(&mut self) -> Vec<String> {
+        self.read_lines()
+            .into_iter()
+            .filter(|line| is_problem(line))
+            .collect()
+    }
+}
+
+// ckb log lines look like "2021-08-11 17:05:21.123 +08:00 ChainService ERROR ckb_chain  ..."
+fn is_problem(line: &str) -> bool {
+    line.split_whitespace()
+        .take(6)
+        .any(|word| word == "ERROR" || word == "WARN")
+}
+
+impl Node {
+    /// Return a watcher of the node `run.log`, starting from its current end. Create it before
+    /// triggering the expected event, so that the lines logged before are not matched:
+    ///
+    /// ```ignore
+    /// let mut watcher = node.log_watcher();
+    /// node.start();
+    /// watcher.expect_log("Listen HTTP RPCServer", Duration::from_secs(10))?;
+    /// watcher.assert_no_log("panicked at");
+    /// ```
+    pub fn log_watcher(&self) -> LogWatcher {
+        LogWatcher::from_end(self.log_path())
+    }
+
+    /// Return the ERROR and WARN lines of the whole node `run.log`.
+    pub fn log_problems(&self) -> Vec<String> {
+        LogWatcher::new(self.log_path()).read_problems()
+    }
+}
@@ -1,17 +1,31 @@
 mod always_success;
+mod block_assembler;
+mod block_mutator;
 mod builder;
+mod dao;
 mod genesis_block_info;
 mod get_transaction;
 mod get_transaction_cycles;
+mod hardfork;
 mod indexer;
+mod indexer_worker;
+mod log_watcher;
 mod mining;
 mod node;
 mod node_options;
 mod p2p;
 mod rpc;
+mod scenario;
 #[cfg(feature = "with_subscribe")]
 mod subscribe;
 
+pub(crate) use block_assembler::extract_dao_data;
+pub use block_assembler::BlockAssembler;
+pub use block_mutator::BlockMutator;
 pub use builder::BuildInstruction;
+pub(crate) use indexer_worker::IndexerWorker;
+pub use indexer_worker::{IndexerMetrics, DEFAULT_INDEXER_POLL_INTERVAL};
+pub use log_watcher::LogWatcher;
 pub use node::Node;
 pub use node_options::NodeOptions;
+pub use scenario::Scenario;
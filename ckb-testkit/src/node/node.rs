@@ -1,20 +1,25 @@
+use super::IndexerWorker;
+use crate::cell_source::{CellSource, EmbeddedIndexer, IndexerRpc};
 use crate::error;
 use crate::rpc::RpcClient;
-use crate::util::{find_available_port, temp_path};
+use crate::util::{find_available_port, temp_path, Waiter};
 use crate::NodeOptions;
 use ckb_indexer::{
     indexer::Indexer,
     store::{RocksdbStore, Store},
 };
 use ckb_jsonrpc_types::{Consensus, LocalNode};
-use ckb_types::core::BlockView;
+use ckb_types::core::{BlockView, EpochNumberWithFraction};
+use ckb_types::packed::Byte32;
 use fs_extra::dir::CopyOptions;
 use reqwest::Url;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
 use std::process::{self, Child, Command, Stdio};
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 struct ProcessGuard(pub Child);
 
@@ -29,6 +34,7 @@ impl Drop for ProcessGuard {
 }
 
 pub struct Node {
+    pub(super) case_name: String,
     pub(super) node_options: NodeOptions,
 
     pub(super) working_dir: PathBuf,
@@ -65,11 +71,20 @@ pub struct Node {
     pub(super) node_id: Option<String>,     // initialize when node start
     pub(super) indexer: Option<Indexer<RocksdbStore>>, // initialize when node start
     _guard: Option<ProcessGuard>,           // initialize when node start
+
+    // #{ block_hash => block_epoch }, shared among the clones
+    pub(super) block_epochs: Arc<Mutex<HashMap<Byte32, EpochNumberWithFraction>>>,
+    // started via `start_indexer_worker`, shared among the clones
+    pub(super) indexer_worker: Option<Arc<IndexerWorker>>,
+    pub(super) cell_source: Arc<dyn CellSource>,
+    // shared among the clones, its strong count is the number of the live clones
+    clones: Arc<()>,
 }
 
 impl Clone for Node {
     fn clone(&self) -> Node {
         Self {
+            case_name: self.case_name.clone(),
             node_options: self.node_options.clone(),
             working_dir: self.working_dir().clone(),
             rpc_client: self.rpc_client.clone(),
@@ -78,6 +93,10 @@ impl Clone for Node {
             genesis_block: self.genesis_block.clone(),
             node_id: self.node_id.clone(),
             indexer: self.indexer.clone(),
+            block_epochs: Arc::clone(&self.block_epochs),
+            indexer_worker: self.indexer_worker.clone(),
+            cell_source: Arc::clone(&self.cell_source),
+            clones: Arc::clone(&self.clones),
             _guard: None,
             #[cfg(feature = "with_subscribe")]
             new_tip_block_subscriber: None,
@@ -100,6 +119,7 @@ impl Node {
         let p2p_port = find_available_port();
         let working_dir = prepare_working_dir(&case_name, &node_options, rpc_port, p2p_port);
         Self {
+            case_name,
             node_options,
             working_dir,
             rpc_client: RpcClient::new(&format!("http://127.0.0.1:{}/", rpc_port), is_ckb2021),
@@ -108,6 +128,10 @@ impl Node {
             genesis_block: None,
             node_id: None,
             indexer: None,
+            block_epochs: Default::default(),
+            indexer_worker: None,
+            cell_source: Arc::new(EmbeddedIndexer),
+            clones: Default::default(),
             _guard: None,
             #[cfg(feature = "with_subscribe")]
             new_tip_block_subscriber: None,
@@ -162,6 +186,11 @@ impl Node {
             let store = RocksdbStore::new(&data_path.to_string_lossy());
             Some(Indexer::new(store, 1000000, 60 * 60, None))
         };
+        let cell_source: Arc<dyn CellSource> = if indexer.is_some() {
+            Arc::new(EmbeddedIndexer)
+        } else {
+            Arc::new(IndexerRpc::new())
+        };
         let node_options = NodeOptions {
             node_name: rpc_url.to_string(),
             ..Default::default()
@@ -174,6 +203,7 @@ impl Node {
         );
         Self {
             // TODO get p2p listen address via RPC
+            case_name: String::new(),
             node_options,
             working_dir,
             rpc_client,
@@ -182,6 +212,10 @@ impl Node {
             genesis_block: Some(genesis_block.into()),
             node_id: Some(node_id),
             indexer,
+            block_epochs: Default::default(),
+            indexer_worker: None,
+            cell_source,
+            clones: Default::default(),
             _guard: None,
             #[cfg(feature = "with_subscribe")]
             new_tip_block_subscriber: None,
@@ -262,12 +296,12 @@ impl Node {
         &self.rpc_client
     }
 
-    /// P2p listen address, without node_id. E.g. "/ip4/0.0.0.0/tcp/9003"
+    /// P2p listen address, without node_id. E.g. "/ip4/127.0.35.43/tcp/9003"
     pub fn p2p_address(&self) -> String {
         self.p2p_address.as_ref().unwrap().clone()
     }
 
-    /// P2p listen address with node_id. E.g. "/ip4/0.0.0.0/tcp/9003/p2p/QmaPV8Ly4YZe2L8B11b2Rvy8YLsvKo4TtfuqhJQzfPcK5T"
+    /// P2p listen address with node_id. E.g. "/ip4/127.0.35.43/tcp/9003/p2p/QmaPV8Ly4YZe2L8B11b2Rvy8YLsvKo4TtfuqhJQzfPcK5T"
     pub fn p2p_address_with_node_id(&self) -> String {
         format!("{}/p2p/{}", self.p2p_address(), self.node_id())
     }
@@ -296,26 +330,142 @@ impl Node {
         self.indexer.as_ref().expect("uninitialized indexer")
     }
 
+    /// Replace the cell source, which defaults to `EmbeddedIndexer` for the nodes with a
+    /// working directory and `IndexerRpc` otherwise.
+    pub fn set_cell_source(&mut self, cell_source: Arc<dyn CellSource>) {
+        self.cell_source = cell_source;
+    }
+
+    pub fn cell_source(&self) -> &Arc<dyn CellSource> {
+        &self.cell_source
+    }
+
     pub fn stop(&mut self) {
         crate::info!(
             "[Node {}] STOP log_path: {}",
             self.node_name(),
             self.log_path().display(),
         );
+        // The indexer worker is bound to the current process and indexer. Stop it explicitly,
+        // as the clones of the node may keep it alive
+        if let Some(indexer_worker) = self.indexer_worker.take() {
+            indexer_worker.stop();
+        }
         if self._guard.is_some() {
             drop(self._guard.take())
         }
     }
 
+    /// Stop the node and restart it on the same data dir with `node_options`, e.g. upgrading
+    /// from ckb2019 to ckb2021. The chain spec and app config of `node_options` replace the
+    /// current ones, keeping the rpc port and p2p port; `initial_database` is ignored. The
+    /// database is migrated via `ckb migrate` if the new binary requires.
+    ///
+    /// Panics if the node has live clones, as they would keep the old rpc client, indexer
+    /// store and indexer worker.
+    pub fn upgrade(&mut self, node_options: NodeOptions, is_ckb2021: bool) {
+        let clones = Arc::strong_count(&self.clones) - 1;
+        assert_eq!(
+            clones,
+            0,
+            "upgrade node {} with {} live clones, drop them before upgrading",
+            self.node_name(),
+            clones
+        );
+        assert_eq!(
+            self.node_name(),
+            node_options.node_name,
+            "upgrade should keep the node name"
+        );
+        let rpc_port = Url::parse(self.rpc_client().url())
+            .expect("rpc url is valid")
+            .port()
+            .expect("rpc url has port");
+        let p2p_port = self.p2p_socket_address().port();
+        // `stop` shuts the indexer worker down, restart it with the same interval afterwards
+        let indexer_poll_interval = self
+            .indexer_worker
+            .as_ref()
+            .map(|worker| worker.poll_interval());
+        self.stop();
+        // Release the indexer store before reopening it in `start`
+        self.indexer = None;
+        crate::info!(
+            "[Node {}] UPGRADE from {} to {}",
+            self.node_name(),
+            self.node_options.ckb_binary.display(),
+            node_options.ckb_binary.display()
+        );
+        install_config(
+            &self.working_dir,
+            node_options.chain_spec,
+            node_options.app_config,
+            rpc_port,
+            p2p_port,
+        );
+        self.node_options = node_options;
+        self.rpc_client = RpcClient::new(self.rpc_client().url(), is_ckb2021);
+        self.migrate();
+        self.start();
+        if let Some(poll_interval) = indexer_poll_interval {
+            self.start_indexer_worker(poll_interval);
+        }
+    }
+
+    // Run `ckb migrate` if `ckb migrate --check` reports that the database requires migration
+    fn migrate(&self) {
+        let binary = &self.node_options.ckb_binary;
+        let working_dir = self.working_dir().to_string_lossy().to_string();
+        let check = Command::new(binary)
+            .args(["-C", &working_dir, "migrate", "--check"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to run ckb migrate --check, binary: {}, error: {}",
+                    binary.display(),
+                    err
+                )
+            });
+        // `ckb migrate --check` exits with 0 if migration is required
+        if !check.success() {
+            return;
+        }
+        crate::info!("[Node {}] MIGRATE database", self.node_name());
+        let output = Command::new(binary)
+            .args(["-C", &working_dir, "migrate", "--force"])
+            .stdin(Stdio::null())
+            .output()
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to run ckb migrate, binary: {}, error: {}",
+                    binary.display(),
+                    err
+                )
+            });
+        assert!(
+            output.status.success(),
+            "failed to migrate the database of node {}, {}, stderr: {}",
+            self.node_name(),
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
     fn wait_for_node_up(&self, child_process: &mut Child) -> LocalNode {
-        let start_time = Instant::now();
-        while start_time.elapsed() <= Duration::from_secs(60) {
+        let waiter = Waiter::new(
+            format!("node {} up", self.node_name()),
+            Duration::from_secs(60),
+        );
+        let waited = waiter.wait_for(|| {
             if let Ok(local_node_info) = self.rpc_client().inner().local_node_info() {
                 let _x = self.rpc_client().tx_pool_info();
-                return local_node_info;
+                return Ok(local_node_info);
             }
             match child_process.try_wait() {
-                Ok(None) => sleep(std::time::Duration::from_secs(1)),
+                Ok(None) => Err(()),
                 Ok(Some(status)) => {
                     error!(
                         "{} node crashed, {}, log_path: {}",
@@ -335,8 +485,8 @@ impl Node {
                     process::exit(255);
                 }
             }
-        }
-        panic!("timeout to start node process")
+        });
+        waited.unwrap_or_else(|err| panic!("{}", err))
     }
 }
 
@@ -375,11 +525,32 @@ fn prepare_working_dir(
             err
         )
     });
+    install_config(
+        &working_dir,
+        source_chain_spec,
+        source_app_config,
+        rpc_port,
+        p2p_port,
+    );
+
+    working_dir
+}
+
+// Copy the chain spec and app config into `working_dir`, with the given rpc port and p2p port.
+// The p2p host is derived from the p2p port, see `p2p_host`.
+fn install_config(
+    working_dir: &Path,
+    source_chain_spec: &str,
+    source_app_config: &str,
+    rpc_port: u16,
+    p2p_port: u16,
+) {
     fs_extra::dir::copy(
         source_chain_spec,
-        &working_dir,
+        working_dir,
         &CopyOptions {
             content_only: true,
+            overwrite: true,
             ..Default::default()
         },
     )
@@ -393,9 +564,10 @@ fn prepare_working_dir(
     });
     fs_extra::dir::copy(
         source_app_config,
-        &working_dir,
+        working_dir,
         &CopyOptions {
             content_only: true,
+            overwrite: true,
             ..Default::default()
         },
     )
@@ -408,15 +580,20 @@ fn prepare_working_dir(
         )
     });
 
-    // Modify rpc port and p2p port in ckb.toml
+    // Modify rpc port, p2p host and p2p port in ckb.toml
     let app_config = working_dir.join("ckb.toml");
     let content = fs::read_to_string(&app_config)
         .unwrap_or_else(|err| panic!("failed to read {}, error: {}", app_config.display(), err));
     let content = content
         .replace("__RPC_PORT__", &rpc_port.to_string())
+        .replace("__P2P_HOST__", &p2p_host(p2p_port).to_string())
         .replace("__P2P_PORT__", &p2p_port.to_string());
     fs::write(&app_config, content)
         .unwrap_or_else(|err| panic!("failed to write {}, error: {}", app_config.display(), err));
+}
 
-    working_dir
+// Every node listens on its own loopback address, so that a node can be banned by IP, via
+// `set_ban`, without banning the other nodes. E.g. p2p port 9003 => 127.0.35.43
+fn p2p_host(p2p_port: u16) -> Ipv4Addr {
+    Ipv4Addr::new(127, 0, (p2p_port >> 8) as u8, p2p_port as u8)
 }
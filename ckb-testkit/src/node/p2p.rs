@@ -1,7 +1,32 @@
-use crate::util::wait_until;
-use crate::Node;
+use crate::util::Waiter;
+use crate::{Node, Proxy};
+use p2p::multiaddr::{Multiaddr, Protocol};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
 
 impl Node {
+    /// The socket address of `p2p_address`. E.g. "/ip4/127.0.35.43/tcp/9003" => 127.0.35.43:9003
+    pub fn p2p_socket_address(&self) -> SocketAddr {
+        let p2p_address = self.p2p_address();
+        let multiaddr: Multiaddr = p2p_address
+            .parse()
+            .unwrap_or_else(|err| panic!("invalid p2p address {}, error: {:?}", p2p_address, err));
+        let mut ip = None;
+        let mut port = None;
+        for protocol in multiaddr.iter() {
+            match protocol {
+                Protocol::Ip4(ip4) => ip = Some(IpAddr::V4(ip4)),
+                Protocol::Ip6(ip6) => ip = Some(IpAddr::V6(ip6)),
+                Protocol::Tcp(tcp) => port = Some(tcp),
+                _ => {}
+            }
+        }
+        match (ip, port) {
+            (Some(ip), Some(port)) => SocketAddr::new(ip, port),
+            _ => panic!("p2p address {} has no ip or tcp port", p2p_address),
+        }
+    }
+
     pub fn is_p2p_connected(&self, other: &Node) -> bool {
         self.rpc_client()
             .get_peers()
@@ -19,16 +44,12 @@ impl Node {
         let other_p2p_address = other.p2p_address();
 
         self.rpc_client().add_node(other_node_id, other_p2p_address);
-        let connected = wait_until(20, || {
-            self.rpc_client()
-                .get_peers()
-                .iter()
-                .any(|remote_node| remote_node.node_id == other.node_id())
-        });
-        if !connected {
+        let connected = Waiter::new("outbound peer connected", Duration::from_secs(20))
+            .wait_until(|| self.is_p2p_connected(other));
+        if let Err(err) = connected {
             panic!(
-                "timeout to connect outbound peer, \
-                self node name: {}, self p2p address: {}, other node name: {}, other p2p address: {}",
+                "{}, self node name: {}, self p2p address: {}, other node name: {}, other p2p address: {}",
+                err,
                 self.node_name(),
                 self.p2p_address(),
                 other.node_name(),
@@ -38,6 +59,30 @@ impl Node {
         crate::trace!("Node::p2p_connect end");
     }
 
+    /// Connect to `other` through `proxy`, which should be started in front of `other`.
+    /// The resulting session is outbound for `self` and inbound for `other`.
+    pub fn p2p_connect_via_proxy(&self, other: &Node, proxy: &Proxy) {
+        crate::trace!(
+            "Node::p2p_connect_via_proxy(\"{}\", \"{}\") start",
+            self.node_name(),
+            other.node_name()
+        );
+        self.rpc_client()
+            .add_node(other.node_id().to_string(), proxy.p2p_address());
+        let connected = Waiter::new("outbound peer connected via proxy", Duration::from_secs(20))
+            .wait_until(|| self.is_p2p_connected(other));
+        if let Err(err) = connected {
+            panic!(
+                "{}, self node name: {}, other node name: {}, proxy address: {}",
+                err,
+                self.node_name(),
+                other.node_name(),
+                proxy.p2p_address(),
+            );
+        }
+        crate::trace!("Node::p2p_connect_via_proxy end");
+    }
+
     pub fn p2p_connect_uncheck(&self, other: &Node) {
         let other_node_id = other.node_id().to_string();
         let other_p2p_address = other.p2p_address();
@@ -49,21 +94,12 @@ impl Node {
         let other_node_id = other.node_id().to_string();
 
         self.rpc_client().remove_node(other_node_id);
-        let disconnected = wait_until(5, || {
-            self.rpc_client()
-                .get_peers()
-                .iter()
-                .all(|remote_node| remote_node.node_id != other.node_id())
-                && other
-                    .rpc_client()
-                    .get_peers()
-                    .iter()
-                    .all(|remote_node| remote_node.node_id != self.node_id())
-        });
-        if !disconnected {
+        let disconnected = Waiter::new("peer disconnected", Duration::from_secs(5))
+            .wait_until(|| !self.is_p2p_connected(other) && !other.is_p2p_connected(self));
+        if let Err(err) = disconnected {
             panic!(
-                "timeout to disconnect peer, \
-                self node name: {}, self node id: {}, other node name: {}, other node id: {}",
+                "{}, self node name: {}, self node id: {}, other node name: {}, other node id: {}",
+                err,
                 self.node_name(),
                 self.node_id(),
                 other.node_name(),
@@ -86,32 +122,38 @@ impl Node {
             "banned addresses should be empty"
         );
         rpc_client.add_node(other_node_id, other_p2p_address);
-        let banned = wait_until(10, || {
+        let banned = Waiter::new("peer banned", Duration::from_secs(10)).wait_for(|| {
             let banned_addresses = rpc_client.get_banned_addresses();
-            !banned_addresses.is_empty()
+            if banned_addresses.is_empty() {
+                Err(())
+            } else {
+                Ok(banned_addresses)
+            }
         });
 
         // Clear
-        if banned {
-            let banned_addresses = rpc_client.get_banned_addresses();
-            for banned_address in banned_addresses {
-                rpc_client.set_ban(
-                    banned_address.address,
-                    "delete".to_owned(),
-                    None,
-                    None,
-                    None,
-                )
+        match banned {
+            Ok(banned_addresses) => {
+                for banned_address in banned_addresses {
+                    rpc_client.set_ban(
+                        banned_address.address,
+                        "delete".to_owned(),
+                        None,
+                        None,
+                        None,
+                    )
+                }
+            }
+            Err(err) => {
+                panic!(
+                    "{}, self node name: {}, self node id: {}, other node name: {}, other node id: {}",
+                    err,
+                    self.node_name(),
+                    self.node_id(),
+                    other.node_name(),
+                    other.node_id(),
+                );
             }
-        } else {
-            panic!(
-                "timeout to connect_and_wait_ban peer, \
-                self node name: {}, self node id: {}, other node name: {}, other node id: {}",
-                self.node_name(),
-                self.node_id(),
-                other.node_name(),
-                other.node_id(),
-            );
         }
     }
 }
@@ -1,11 +1,11 @@
+use crate::util::Waiter;
 use crate::Node;
 use ckb_jsonrpc_types::TxPoolInfo;
 use ckb_types::{
     core::{BlockNumber, BlockView, HeaderView, TransactionView},
     packed::Byte32,
 };
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 impl Node {
     pub fn submit_block(&self, block: &BlockView) -> Byte32 {
@@ -18,8 +18,11 @@ impl Node {
     }
 
     pub fn submit_transaction(&self, transaction: &TransactionView) -> Byte32 {
-        self.rpc_client()
-            .send_transaction(transaction.data().into())
+        let tx_hash = self
+            .rpc_client()
+            .send_transaction(transaction.data().into());
+        self.cell_source.on_transaction_submitted(self, transaction);
+        tx_hash
     }
 
     pub fn get_tip_block(&self) -> BlockView {
@@ -73,48 +76,48 @@ impl Node {
     pub fn get_tip_tx_pool_info(&self) -> TxPoolInfo {
         let tip_header = self.rpc_client().get_tip_header();
         let tip_hash = &tip_header.hash;
-        let instant = Instant::now();
-        let mut recent = TxPoolInfo::default();
-        while instant.elapsed() < Duration::from_secs(10) {
-            let tx_pool_info = self.rpc_client().tx_pool_info();
-            if &tx_pool_info.tip_hash == tip_hash {
-                return tx_pool_info;
-            }
-            recent = tx_pool_info;
-        }
-        panic!(
-            "timeout to get_tip_tx_pool_info, tip_header={:?}, tx_pool_info: {:?}",
-            tip_header, recent
-        );
+        Waiter::new("tx-pool tip updated", Duration::from_secs(10))
+            .initial_interval(Duration::from_millis(10))
+            .wait_for(|| {
+                let tx_pool_info = self.rpc_client().tx_pool_info();
+                if &tx_pool_info.tip_hash == tip_hash {
+                    Ok(tx_pool_info)
+                } else {
+                    Err(tx_pool_info)
+                }
+            })
+            .unwrap_or_else(|err| panic!("{}, tip_header={:?}", err, tip_header))
     }
 
     pub fn wait_for_tx_pool(&self) {
         let rpc_client = self.rpc_client();
-        let mut chain_tip = rpc_client.get_tip_header();
-        let mut tx_pool_tip = rpc_client.tx_pool_info();
-        if chain_tip.hash == tx_pool_tip.tip_hash {
-            return;
-        }
-        let mut instant = Instant::now();
-        while instant.elapsed() < Duration::from_secs(10) {
-            sleep(std::time::Duration::from_secs(1));
-            chain_tip = rpc_client.get_tip_header();
-            let prev_tx_pool_tip = tx_pool_tip;
-            tx_pool_tip = rpc_client.tx_pool_info();
-            if chain_tip.hash == tx_pool_tip.tip_hash {
-                return;
-            } else if prev_tx_pool_tip.tip_hash != tx_pool_tip.tip_hash
-                && tx_pool_tip.tip_number.value() < chain_tip.inner.number.value()
-            {
-                instant = Instant::now();
-            }
-        }
-        panic!(
-            "timeout to wait for tx pool,\n\tchain   tip: {:?}, {:#x},\n\ttx-pool tip: {}, {:#x}",
-            chain_tip.inner.number.value(),
-            chain_tip.hash,
-            tx_pool_tip.tip_number.value(),
-            tx_pool_tip.tip_hash,
-        );
+        Waiter::new(
+            "tx-pool tip reaching the chain tip",
+            Duration::from_secs(10),
+        )
+        .initial_interval(Duration::from_millis(10))
+        .wait_for_progress(
+            || {
+                let chain_tip = rpc_client.get_tip_header();
+                let tx_pool_tip = rpc_client.tx_pool_info();
+                if chain_tip.hash == tx_pool_tip.tip_hash {
+                    Ok(())
+                } else {
+                    // (chain tip number, chain tip hash, tx-pool tip number, tx-pool tip hash)
+                    Err((
+                        chain_tip.inner.number.value(),
+                        chain_tip.hash,
+                        tx_pool_tip.tip_number.value(),
+                        tx_pool_tip.tip_hash,
+                    ))
+                }
+            },
+            // The timeout restarts as long as the tx-pool is catching up
+            |(_, _, _, prev_tx_pool_tip_hash),
+             (chain_tip_number, _, tx_pool_tip_number, tx_pool_tip_hash)| {
+                prev_tx_pool_tip_hash != tx_pool_tip_hash && tx_pool_tip_number < chain_tip_number
+            },
+        )
+        .unwrap_or_else(|err| panic!("{}", err));
     }
 }
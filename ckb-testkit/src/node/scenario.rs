@@ -0,0 +1,287 @@
+use crate::{BuildInstruction, Node};
+use ckb_types::{
+    bytes::Bytes,
+    core::{BlockNumber, BlockView, TransactionView, UncleBlockView},
+    packed::Byte32,
+};
+use std::collections::HashMap;
+
+/// Scenario describes how to build a piece of chain in relative steps, without hand-computing
+/// the template number of every instruction.
+///
+/// A scenario builds blocks one by one upon the tip of the node. Every step applies to the
+/// *current block*, which is the first block after the tip at the beginning, and is moved
+/// forward by `next_block`, `mine` and `commit`.
+///
+/// ```ignore
+/// let blocks = Scenario::new()
+///     .send_transaction(&tx)
+///     .propose(&tx)
+///     // moves to the first block in the proposal window
+///     .commit(&tx)
+///     .mine(3)
+///     .run(node)?;
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Scenario {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    SendTransaction(TransactionView),
+    Propose(TransactionView),
+    Commit(TransactionView),
+    NextBlock(BlockNumber),
+    Uncle(UncleBlockView),
+    Extension(Bytes),
+    Cellbase(TransactionView),
+    Timestamp(u64),
+    ProcessWithoutVerify,
+}
+
+impl ::std::fmt::Display for Step {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        match self {
+            Step::SendTransaction(tx) => write!(f, "send_transaction({:#x})", tx.hash()),
+            Step::Propose(tx) => write!(f, "propose({:#x})", tx.hash()),
+            Step::Commit(tx) => write!(f, "commit({:#x})", tx.hash()),
+            Step::NextBlock(n) => write!(f, "mine({})", n),
+            Step::Uncle(uncle) => write!(f, "uncle({:#x})", uncle.hash()),
+            Step::Extension(extension) => write!(f, "extension({} bytes)", extension.len()),
+            Step::Cellbase(cellbase) => write!(f, "cellbase({:#x})", cellbase.hash()),
+            Step::Timestamp(timestamp) => write!(f, "timestamp({})", timestamp),
+            Step::ProcessWithoutVerify => write!(f, "process_without_verify"),
+        }
+    }
+}
+
+// The steps and instructions of a block
+#[derive(Default)]
+pub(crate) struct PlannedBlock {
+    pub(crate) steps: Vec<usize>,
+    pub(crate) transactions: Vec<(usize, TransactionView)>,
+    pub(crate) instructions: Vec<BuildInstruction>,
+}
+
+impl Scenario {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send `transaction` into the tx-pool before building the current block.
+    pub fn send_transaction(mut self, transaction: &TransactionView) -> Self {
+        self.steps.push(Step::SendTransaction(transaction.clone()));
+        self
+    }
+
+    /// Propose `transaction` in the current block.
+    pub fn propose(mut self, transaction: &TransactionView) -> Self {
+        self.steps.push(Step::Propose(transaction.clone()));
+        self
+    }
+
+    /// Commit `transaction`, which must be proposed by a previous step. The current block is
+    /// moved forward to the first block in the proposal window if it is not in the window yet.
+    pub fn commit(mut self, transaction: &TransactionView) -> Self {
+        self.steps.push(Step::Commit(transaction.clone()));
+        self
+    }
+
+    /// Finish the current block and move to the next one.
+    pub fn next_block(self) -> Self {
+        self.mine(1)
+    }
+
+    /// Move forward `n` blocks, i.e. finish the current block and the `n - 1` blocks after it,
+    /// which are built as the node's block template. A scenario ending with `mine(n)` builds
+    /// exactly `n` blocks from the current one, as the trailing block is built only if any step
+    /// applies to it.
+    pub fn mine(mut self, n: BlockNumber) -> Self {
+        if n > 0 {
+            self.steps.push(Step::NextBlock(n));
+        }
+        self
+    }
+
+    /// Include `uncle` in the current block.
+    pub fn uncle(mut self, uncle: &UncleBlockView) -> Self {
+        self.steps.push(Step::Uncle(uncle.clone()));
+        self
+    }
+
+    /// Set the extension of the current block.
+    pub fn extension(mut self, extension: Bytes) -> Self {
+        self.steps.push(Step::Extension(extension));
+        self
+    }
+
+    /// Replace the cellbase of the current block.
+    pub fn cellbase(mut self, cellbase: &TransactionView) -> Self {
+        self.steps.push(Step::Cellbase(cellbase.clone()));
+        self
+    }
+
+    /// Set the header timestamp of the current block.
+    pub fn timestamp(mut self, timestamp: u64) -> Self {
+        self.steps.push(Step::Timestamp(timestamp));
+        self
+    }
+
+    /// Process the current block via `process_block_without_verify` instead of `submit_block`.
+    pub fn process_without_verify(mut self) -> Self {
+        self.steps.push(Step::ProcessWithoutVerify);
+        self
+    }
+
+    /// Build the scenario upon the tip of `node`, return the built blocks.
+    ///
+    /// On failure, the error message tells which step failed, e.g.
+    /// `"step #0 send_transaction(0x..) failed at block 12: ..."`, or which steps shaped the
+    /// failed block, e.g. `"block 14 shaped by steps [#2 commit(0x..)] failed: ..."`.
+    pub fn run(&self, node: &Node) -> Result<Vec<BlockView>, String> {
+        let proposal_window = &node.consensus().tx_proposal_window;
+        let initial_tip_number = node.get_tip_block_number();
+        let planned = self.plan(
+            initial_tip_number,
+            proposal_window.closest.value(),
+            proposal_window.farthest.value(),
+        )?;
+        crate::debug!(
+            "[Node {}] Scenario::run upon {}, {} steps, {} blocks",
+            node.node_name(),
+            initial_tip_number,
+            self.steps.len(),
+            planned.len()
+        );
+
+        for (i, block) in planned.into_iter().enumerate() {
+            let number = initial_tip_number + i as BlockNumber + 1;
+            for (step_index, transaction) in block.transactions.iter() {
+                node.rpc_client()
+                    .send_transaction_result(transaction.data().into())
+                    .map_err(|err| self.step_error(*step_index, number, err.to_string()))?;
+            }
+            node.build_according_to_instructions(number, block.instructions.clone())
+                .map_err(|err| {
+                    let steps = block
+                        .steps
+                        .iter()
+                        .map(|step_index| format!("#{} {}", step_index, self.steps[*step_index]))
+                        .collect::<Vec<_>>();
+                    format!(
+                        "block {} shaped by steps [{}] failed: {}",
+                        number,
+                        steps.join(", "),
+                        err
+                    )
+                })?;
+        }
+
+        let tip_number = node.get_tip_block_number();
+        Ok((initial_tip_number + 1..=tip_number)
+            .map(|number| node.get_block_by_number(number))
+            .collect())
+    }
+
+    // Assign every step to a block. Blocks are numbered relatively from 1 during planning, and
+    // instructions take the absolute number `base + relative number`.
+    pub(crate) fn plan(
+        &self,
+        base: BlockNumber,
+        closest: BlockNumber,
+        farthest: BlockNumber,
+    ) -> Result<Vec<PlannedBlock>, String> {
+        let mut blocks: Vec<PlannedBlock> = vec![PlannedBlock::default()];
+        let mut current: BlockNumber = 1;
+        // #{ tx_hash => the block proposing it }
+        let mut proposed: HashMap<Byte32, BlockNumber> = HashMap::new();
+        for (step_index, step) in self.steps.iter().enumerate() {
+            let instruction = match step {
+                Step::SendTransaction(transaction) => {
+                    blocks[current as usize - 1]
+                        .transactions
+                        .push((step_index, transaction.clone()));
+                    None
+                }
+                Step::Propose(transaction) => {
+                    proposed.insert(transaction.hash(), current);
+                    Some(BuildInstruction::Propose {
+                        template_number: base + current,
+                        proposal_short_id: transaction.proposal_short_id(),
+                    })
+                }
+                Step::Commit(transaction) => {
+                    let proposed_at = *proposed.get(&transaction.hash()).ok_or_else(|| {
+                        format!(
+                            "step #{} {} is invalid: the transaction is not proposed by previous steps",
+                            step_index, step
+                        )
+                    })?;
+                    if current > proposed_at + farthest {
+                        return Err(format!(
+                            "step #{} {} is invalid: the transaction proposed at relative block {} \
+                             is out of the proposal window [{}, {}] at relative block {}",
+                            step_index,
+                            step,
+                            proposed_at,
+                            closest,
+                            farthest,
+                            current
+                        ));
+                    }
+                    current = current.max(proposed_at + closest);
+                    Some(BuildInstruction::Commit {
+                        template_number: base + current,
+                        transaction: transaction.clone(),
+                    })
+                }
+                Step::NextBlock(n) => {
+                    current += n;
+                    None
+                }
+                Step::Uncle(uncle) => Some(BuildInstruction::Uncle {
+                    template_number: base + current,
+                    uncle: uncle.clone(),
+                }),
+                Step::Extension(extension) => Some(BuildInstruction::Extension {
+                    template_number: base + current,
+                    extension: extension.clone(),
+                }),
+                Step::Cellbase(cellbase) => Some(BuildInstruction::Cellbase {
+                    template_number: base + current,
+                    cellbase: cellbase.clone(),
+                }),
+                Step::Timestamp(timestamp) => Some(BuildInstruction::HeaderTimestamp {
+                    template_number: base + current,
+                    timestamp: *timestamp,
+                }),
+                Step::ProcessWithoutVerify => Some(BuildInstruction::ProcessWithoutVerify {
+                    template_number: base + current,
+                }),
+            };
+            while blocks.len() < current as usize {
+                blocks.push(PlannedBlock::default());
+            }
+            let block = &mut blocks[current as usize - 1];
+            if !matches!(step, Step::NextBlock(_)) {
+                block.steps.push(step_index);
+            }
+            if let Some(instruction) = instruction {
+                block.instructions.push(instruction);
+            }
+        }
+        // The current block which no step applies to, left by the trailing `mine`
+        if matches!(blocks.last(), Some(block) if block.steps.is_empty()) {
+            blocks.pop();
+        }
+        Ok(blocks)
+    }
+
+    fn step_error(&self, step_index: usize, number: BlockNumber, err: String) -> String {
+        format!(
+            "step #{} {} failed at block {}: {}",
+            step_index, self.steps[step_index], number, err
+        )
+    }
+}
@@ -1,10 +1,11 @@
-use crate::util::wait_until;
-use crate::Nodes;
+use crate::util::Waiter;
+use crate::{Divergence, Nodes};
 use ckb_types::{
     core::{BlockNumber, HeaderView},
     packed::Byte32,
 };
 use std::collections::HashSet;
+use std::time::Duration;
 
 impl Nodes {
     pub fn waiting_for_sync(&self) -> Result<(), Vec<(&str, BlockNumber, Byte32)>> {
@@ -19,15 +20,16 @@ impl Nodes {
             highest_blocks.map(|block| block.hash()).collect()
         };
 
-        // 60 seconds is a reasonable timeout to sync, even for poor CI server
-        let synced = wait_until(60, || {
+        // 60 seconds is a reasonable timeout to sync, even for poor CI server, which can stretch
+        // it further via `CKB_TESTKIT_TIME_SCALE`
+        let synced = Waiter::new("nodes synced", Duration::from_secs(60)).wait_until(|| {
             highest_hashes.iter().all(|hash| {
                 self.nodes()
                     .all(|node| node.rpc_client().get_header(hash.clone()).is_some())
             })
         });
 
-        if !synced {
+        if synced.is_err() {
             let tips = self
                 .nodes()
                 .map(|node| {
@@ -44,21 +46,50 @@ impl Nodes {
         Ok(())
     }
 
+    /// Return the highest header shared by all the nodes. Panic if the nodes do not share the
+    /// same genesis block.
     pub fn get_fixed_header(&self) -> HeaderView {
-        let maximal_number = self
-            .nodes()
+        self.try_get_fixed_header()
+            .unwrap_or_else(|divergence| panic!("Nodes::get_fixed_header, {}", divergence))
+    }
+
+    /// Like `get_fixed_header`, but return `Divergence::BlockHash` at number 0 if the nodes do
+    /// not share the same genesis block.
+    pub fn try_get_fixed_header(&self) -> Result<HeaderView, Divergence> {
+        let mut nodes = self.nodes().collect::<Vec<_>>();
+        nodes.sort_by(|a, b| a.node_name().cmp(b.node_name()));
+        let genesis_headers = nodes
+            .iter()
+            .map(|node| (node.node_name().to_string(), node.get_header_by_number(0)))
+            .collect::<Vec<_>>();
+        let genesis_header = &genesis_headers.first().expect("at least 1 node").1;
+        if genesis_headers
+            .iter()
+            .any(|(_, header)| header.hash() != genesis_header.hash())
+        {
+            return Err(Divergence::BlockHash {
+                number: 0,
+                hashes: genesis_headers
+                    .iter()
+                    .map(|(node_name, header)| (node_name.clone(), header.hash()))
+                    .collect(),
+            });
+        }
+
+        let maximal_number = nodes
+            .iter()
             .map(|node| node.get_tip_block_number())
             .min()
             .expect("at least 1 node");
-        for number in (0..=maximal_number).rev() {
-            let headers = self
-                .nodes()
+        for number in (1..=maximal_number).rev() {
+            let headers = nodes
+                .iter()
                 .map(|node| node.get_header_by_number(number))
                 .collect::<HashSet<_>>();
             if headers.len() == 1 {
-                return headers.into_iter().collect::<Vec<_>>()[0].to_owned();
+                return Ok(headers.into_iter().collect::<Vec<_>>()[0].to_owned());
             }
         }
-        unreachable!()
+        Ok(genesis_header.to_owned())
     }
 }
@@ -0,0 +1,270 @@
+use crate::util::wait_until;
+use crate::{BuildInstruction, Node, Nodes};
+use ckb_types::{
+    core::{BlockNumber, BlockView, HeaderView, TransactionView},
+    packed::Byte32,
+    utilities::compact_to_difficulty,
+    U256,
+};
+use std::collections::{HashMap, HashSet};
+
+/// A branch to build on a node, upon the common ancestor of a fork.
+#[derive(Debug, Clone)]
+pub struct ForkBranch {
+    node_name: String,
+    length: BlockNumber,
+    instructions: Vec<BuildInstruction>,
+}
+
+impl ForkBranch {
+    /// Build `length` blocks upon the ancestor on the node `node_name`.
+    pub fn new(node_name: &str, length: BlockNumber) -> Self {
+        Self {
+            node_name: node_name.to_string(),
+            length,
+            instructions: Vec::new(),
+        }
+    }
+
+    /// Build the branch according to `instructions`, see `Node::build_according_to_instructions`.
+    /// The template numbers are absolute block numbers, within
+    /// `(ancestor.number(), ancestor.number() + length]`.
+    pub fn instructions(mut self, instructions: Vec<BuildInstruction>) -> Self {
+        self.instructions = instructions;
+        self
+    }
+}
+
+/// Fork records the divergent branches built upon the common ancestor.
+#[derive(Debug, Clone)]
+pub struct Fork {
+    ancestor: HeaderView,
+    // #{ node_name => blocks after ancestor }
+    branches: HashMap<String, Vec<BlockView>>,
+}
+
+impl Fork {
+    pub fn ancestor(&self) -> &HeaderView {
+        &self.ancestor
+    }
+
+    pub fn branch(&self, node_name: &str) -> &[BlockView] {
+        self.branches
+            .get(node_name)
+            .unwrap_or_else(|| panic!("fork has no branch on node \"{}\"", node_name))
+    }
+
+    /// The tip of the branch, or the ancestor if the branch is empty.
+    pub fn branch_tip(&self, node_name: &str) -> HeaderView {
+        self.branch(node_name)
+            .last()
+            .map(|block| block.header())
+            .unwrap_or_else(|| self.ancestor.clone())
+    }
+
+    /// The total difficulty of the branch blocks after the ancestor, i.e. the work the branch
+    /// adds to the common chain.
+    pub fn branch_work(&self, node_name: &str) -> U256 {
+        self.branch(node_name)
+            .iter()
+            .fold(U256::zero(), |work, block| {
+                work + compact_to_difficulty(block.compact_target())
+            })
+    }
+
+    /// Return the name of the node whose branch is expected to win, i.e. the one with the most
+    /// total difficulty, which is not necessarily the longest one once the difficulty is
+    /// adjusted. Return `None` if multiple branches have the most, which one wins depends on
+    /// which one arrived first.
+    pub fn expected_winner(&self) -> Option<&str> {
+        let works = self
+            .branches
+            .keys()
+            .map(|node_name| (node_name.as_str(), self.branch_work(node_name)))
+            .collect::<Vec<_>>();
+        let max_work = works.iter().map(|(_, work)| work).max()?;
+        let heaviest = works
+            .iter()
+            .filter(|(_, work)| work == max_work)
+            .map(|(node_name, _)| *node_name)
+            .collect::<Vec<_>>();
+        if heaviest.len() == 1 {
+            Some(heaviest[0])
+        } else {
+            None
+        }
+    }
+
+    /// Return the transactions committed in the losing branches but not in the winner branch.
+    /// After reorg, these transactions should return to the tx-pool.
+    pub fn orphaned_transactions(&self, winner: &str) -> Vec<TransactionView> {
+        let winner_hashes = self
+            .branch(winner)
+            .iter()
+            .flat_map(|block| block.transactions())
+            .map(|tx| tx.hash())
+            .collect::<HashSet<_>>();
+        let mut seen = HashSet::new();
+        self.branches
+            .iter()
+            .filter(|(node_name, _)| node_name.as_str() != winner)
+            .flat_map(|(_, blocks)| blocks.iter())
+            .flat_map(|block| block.transactions().into_iter().skip(1))
+            .filter(|tx| !winner_hashes.contains(&tx.hash()) && seen.insert(tx.hash()))
+            .collect()
+    }
+}
+
+impl Nodes {
+    /// Build divergent branches upon `ancestor`, each on a separate node.
+    ///
+    /// All nodes are partitioned from each other during building, and keep partitioned after
+    /// return, so that cases can inspect each branch separately. Call `Nodes::resolve_fork` to
+    /// reconnect them.
+    pub fn build_fork(&mut self, ancestor: &HeaderView, branches: Vec<ForkBranch>) -> Fork {
+        crate::info!(
+            "Nodes::build_fork upon {} {:#x}, branches: {:?}",
+            ancestor.number(),
+            ancestor.hash(),
+            branches
+                .iter()
+                .map(|branch| (branch.node_name.as_str(), branch.length))
+                .collect::<Vec<_>>()
+        );
+        // Isolate every node, including the ones out of branches, otherwise they may relay the
+        // blocks across branches
+        let node_names = self.node_names().cloned().collect::<Vec<_>>();
+        let groups = node_names
+            .iter()
+            .map(|node_name| vec![node_name.as_str()])
+            .collect::<Vec<_>>();
+        let groups = groups.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        self.partition_groups(&groups);
+
+        let mut built = HashMap::new();
+        for branch in branches.iter() {
+            let node = self.get_node(&branch.node_name);
+            rollback_to(node, ancestor);
+            let target_height = ancestor.number() + branch.length;
+            node.build_according_to_instructions(target_height, branch.instructions.clone())
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "failed to build branch on node \"{}\", error: {}",
+                        branch.node_name, err
+                    )
+                });
+            let blocks = (ancestor.number() + 1..=target_height)
+                .map(|number| node.get_block_by_number(number))
+                .collect::<Vec<_>>();
+            built.insert(branch.node_name.clone(), blocks);
+        }
+
+        Fork {
+            ancestor: ancestor.to_owned(),
+            branches: built,
+        }
+    }
+
+    /// Heal the partition made by `build_fork` and wait for all nodes to sync. Return the name of
+    /// the node whose branch wins.
+    pub fn resolve_fork(&mut self, fork: &Fork) -> String {
+        self.heal();
+        // Make sure every pair of branch nodes is connected, even if they were not before the fork
+        let branch_node_names = fork.branches.keys().map(String::as_str).collect::<Vec<_>>();
+        for (i, node_a) in branch_node_names.iter().enumerate() {
+            let edges = branch_node_names[i + 1..]
+                .iter()
+                .map(|node_b| (*node_a, *node_b))
+                .collect::<Vec<_>>();
+            self.p2p_connect_edges(&edges);
+        }
+        self.waiting_for_sync().unwrap_or_else(|tips| {
+            panic!(
+                "nodes are not synced after resolving fork, tips: {:?}",
+                tips
+            )
+        });
+
+        let tip_hash = self.get_fixed_header().hash();
+        fork.branches
+            .keys()
+            .find(|node_name| fork.branch_tip(node_name).hash() == tip_hash)
+            .cloned()
+            .unwrap_or_else(|| {
+                panic!(
+                    "no branch wins, the synced tip {:#x} is not any of the branch tips",
+                    tip_hash
+                )
+            })
+    }
+
+    /// Assert all nodes' tip is the tip of the `winner` branch.
+    pub fn assert_fork_winner(&self, fork: &Fork, winner: &str) {
+        let expected_tip = fork.branch_tip(winner);
+        for node in self.nodes() {
+            let actual_tip = node.get_tip_block().header();
+            assert_eq!(
+                expected_tip.hash(),
+                actual_tip.hash(),
+                "node \"{}\" should be on branch \"{}\"(tip {}), but tip is {}",
+                node.node_name(),
+                winner,
+                expected_tip.number(),
+                actual_tip.number(),
+            );
+        }
+    }
+
+    /// Wait for the orphaned transactions of `fork` returning to the tx-pool of every node which
+    /// committed them before reorg. Return the hashes of those that did not return.
+    pub fn wait_for_orphaned_transactions_returned(
+        &self,
+        fork: &Fork,
+        winner: &str,
+    ) -> Result<(), Vec<(String, Byte32)>> {
+        let mut missing = Vec::new();
+        for (node_name, blocks) in fork.branches.iter() {
+            if node_name == winner {
+                continue;
+            }
+            let node = self.get_node(node_name);
+            let committed = blocks
+                .iter()
+                .flat_map(|block| block.transactions().into_iter().skip(1))
+                .map(|tx| tx.hash())
+                .collect::<HashSet<_>>();
+            for tx in fork.orphaned_transactions(winner) {
+                if !committed.contains(&tx.hash()) {
+                    continue;
+                }
+                let returned = wait_until(10, || {
+                    node.is_transaction_pending(&tx) || node.is_transaction_proposed(&tx)
+                });
+                if !returned {
+                    missing.push((node_name.clone(), tx.hash()));
+                }
+            }
+        }
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(missing)
+        }
+    }
+}
+
+// Rollback the chain of `node` to `ancestor`
+fn rollback_to(node: &Node, ancestor: &HeaderView) {
+    assert_eq!(
+        node.rpc_client().get_block_hash(ancestor.number()),
+        Some(ancestor.hash()),
+        "node \"{}\" does not contain the ancestor {} {:#x}",
+        node.node_name(),
+        ancestor.number(),
+        ancestor.hash(),
+    );
+    if node.get_tip_block_number() > ancestor.number() {
+        node.rpc_client().truncate(ancestor.hash());
+        node.wait_for_tx_pool();
+    }
+}
@@ -0,0 +1,341 @@
+use crate::node::extract_dao_data;
+use crate::util::Waiter;
+use crate::{Node, Nodes};
+use ckb_types::{
+    core::{BlockNumber, Capacity, EpochNumber},
+    packed::{Byte32, OutPoint},
+    prelude::*,
+};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::time::Duration;
+
+// How long `Nodes::check_invariants` waits for the tx-pools to drain, stretched by
+// `CKB_TESTKIT_TIME_SCALE`
+const TX_POOL_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The first divergence found by `Nodes::check_invariants`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Divergence {
+    /// The nodes having block `number` have different blocks there, the first such height
+    /// above the fixed header. Number 0 means the nodes do not share the same genesis block.
+    BlockHash {
+        number: BlockNumber,
+        hashes: Vec<(String, Byte32)>,
+    },
+    /// The nodes having block `number` have different dao fields there, i.e. their chain states
+    /// diverge, the first such height above the fixed header.
+    Dao {
+        number: BlockNumber,
+        daos: Vec<(String, Byte32)>,
+    },
+    /// The total issuance recorded in the dao field of block `number` does not equal the
+    /// parent's plus the block issuance.
+    DaoIssuance {
+        node_name: String,
+        number: BlockNumber,
+        expected: Capacity,
+        actual: Capacity,
+    },
+    /// The total primary or secondary issuance of the blocks in `epoch` does not equal the
+    /// epoch reward.
+    EpochIssuance {
+        node_name: String,
+        epoch: EpochNumber,
+        expected: (Capacity, Capacity),
+        actual: (Capacity, Capacity),
+    },
+    /// The nodes share the same tip but have different live cells.
+    LiveCells {
+        number: BlockNumber,
+        out_point: OutPoint,
+        present: Vec<String>,
+        absent: Vec<String>,
+    },
+    /// The tx-pool of the node still has pending or proposed transactions after waiting for it
+    /// to drain.
+    TxPoolNotEmpty {
+        node_name: String,
+        number: BlockNumber,
+        pending: u64,
+        proposed: u64,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Divergence::BlockHash { number, hashes } => {
+                write!(f, "block hashes diverge at height {}:", number)?;
+                for (node_name, hash) in hashes {
+                    write!(f, " {}={:#x}", node_name, hash)?;
+                }
+                Ok(())
+            }
+            Divergence::Dao { number, daos } => {
+                write!(f, "dao fields diverge at height {}:", number)?;
+                for (node_name, dao) in daos {
+                    write!(f, " {}={:#x}", node_name, dao)?;
+                }
+                Ok(())
+            }
+            Divergence::DaoIssuance {
+                node_name,
+                number,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node {} block {} dao field records total issuance {}, expected {}",
+                node_name, number, actual, expected
+            ),
+            Divergence::EpochIssuance {
+                node_name,
+                epoch,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "node {} epoch {} issues (primary: {}, secondary: {}), expected (primary: {}, secondary: {})",
+                node_name, epoch, actual.0, actual.1, expected.0, expected.1
+            ),
+            Divergence::LiveCells {
+                number,
+                out_point,
+                present,
+                absent,
+            } => write!(
+                f,
+                "live cell {} at height {} is present on {:?} but absent on {:?}",
+                out_point, number, present, absent
+            ),
+            Divergence::TxPoolNotEmpty {
+                node_name,
+                number,
+                pending,
+                proposed,
+            } => write!(
+                f,
+                "node {} tx-pool at height {} is not empty, pending: {}, proposed: {}",
+                node_name, number, pending, proposed
+            ),
+        }
+    }
+}
+
+impl Nodes {
+    /// Check the chain-state invariants among the nodes, return the first divergence.
+    ///
+    /// The invariants are checked in order:
+    ///   1. The nodes are on the same chain. The nodes share the same genesis block, and above
+    ///      the fixed header, see `get_fixed_header`, the nodes having a block at the same
+    ///      height have the same dao field and block hash, up to each node's tip;
+    ///   2. The dao field of every block records the total issuance, i.e. the parent's plus the
+    ///      block issuance reported by `get_block_economic_state`;
+    ///   3. The blocks of every finalized epoch issue exactly the primary epoch reward, halved
+    ///      every `primary_epoch_reward_halving_interval` epochs, and the secondary epoch reward.
+    ///      The genesis epoch is skipped as the genesis block is not rewarded;
+    ///   4. The indexers have the same live cells, checked only if the nodes share the same tip;
+    ///   5. The tx-pools have no pending or proposed transactions. As the tx-pool is updated
+    ///      asynchronously after a block is accepted, and the transactions being relayed arrive
+    ///      late, the tx-pools are given `TX_POOL_DRAIN_TIMEOUT` to drain.
+    pub fn check_invariants(&self) -> Result<(), Divergence> {
+        crate::trace!("Nodes::check_invariants start");
+        let mut nodes = self.nodes().collect::<Vec<_>>();
+        if nodes.is_empty() {
+            return Ok(());
+        }
+        nodes.sort_by(|a, b| a.node_name().cmp(b.node_name()));
+        let fixed_number = self.try_get_fixed_header()?.number();
+        check_blocks(&nodes, fixed_number)?;
+        for node in nodes.iter() {
+            check_issuance(node, fixed_number)?;
+        }
+        check_live_cells(&nodes)?;
+        for node in nodes.iter() {
+            check_tx_pool_drained(node)?;
+        }
+        crate::trace!("Nodes::check_invariants end");
+        Ok(())
+    }
+}
+
+// Compare the blocks above the fixed header among the nodes having them
+fn check_blocks(nodes: &[&Node], fixed_number: BlockNumber) -> Result<(), Divergence> {
+    let tip_numbers = nodes
+        .iter()
+        .map(|node| node.get_tip_block_number())
+        .collect::<Vec<_>>();
+    let highest_number = tip_numbers.iter().copied().max().unwrap_or(fixed_number);
+    for number in fixed_number + 1..=highest_number {
+        let headers = nodes
+            .iter()
+            .zip(tip_numbers.iter())
+            .filter(|(_, tip_number)| **tip_number >= number)
+            .map(|(node, _)| {
+                let header = node.get_header_by_number(number);
+                (node.node_name().to_string(), header)
+            })
+            .collect::<Vec<_>>();
+        if headers.len() < 2 {
+            break;
+        }
+        // Report the diverged chain states first, the blocks may differ only in e.g. timestamps
+        let dao = headers[0].1.dao();
+        if headers.iter().any(|(_, header)| header.dao() != dao) {
+            return Err(Divergence::Dao {
+                number,
+                daos: headers
+                    .into_iter()
+                    .map(|(node_name, header)| (node_name, header.dao()))
+                    .collect(),
+            });
+        }
+        let hash = headers[0].1.hash();
+        if headers.iter().any(|(_, header)| header.hash() != hash) {
+            return Err(Divergence::BlockHash {
+                number,
+                hashes: headers
+                    .into_iter()
+                    .map(|(node_name, header)| (node_name, header.hash()))
+                    .collect(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_tx_pool_drained(node: &Node) -> Result<(), Divergence> {
+    Waiter::new(
+        format!("node {} tx-pool drained", node.node_name()),
+        TX_POOL_DRAIN_TIMEOUT,
+    )
+    .wait_for(|| {
+        let tx_pool_info = node.rpc_client().tx_pool_info();
+        let pending = tx_pool_info.pending.value();
+        let proposed = tx_pool_info.proposed.value();
+        if pending == 0 && proposed == 0 {
+            Ok(())
+        } else {
+            Err((pending, proposed))
+        }
+    })
+    .map_err(|timeout| {
+        let (pending, proposed) = timeout.last_state.unwrap_or_default();
+        Divergence::TxPoolNotEmpty {
+            node_name: node.node_name().to_string(),
+            number: node.get_tip_block_number(),
+            pending,
+            proposed,
+        }
+    })
+}
+
+fn check_issuance(node: &Node, fixed_number: BlockNumber) -> Result<(), Divergence> {
+    let consensus = node.consensus();
+    let initial_primary_epoch_reward = consensus.initial_primary_epoch_reward.value();
+    let secondary_epoch_reward = consensus.secondary_epoch_reward.value();
+    let halving_interval = consensus.primary_epoch_reward_halving_interval.value();
+
+    // #{ epoch => (primary, secondary, finalized blocks) }
+    let mut epochs: BTreeMap<EpochNumber, (u64, u64, u64)> = BTreeMap::new();
+    let mut parent_c = {
+        let genesis = node.get_header_by_number(0);
+        extract_dao_data(&genesis.dao()).1
+    };
+    for number in 1..=fixed_number {
+        let header = node.get_header_by_number(number);
+        let (_, c, _, _) = extract_dao_data(&header.dao());
+        let economic_state = match node.rpc_client().get_block_economic_state(header.hash()) {
+            Some(economic_state) => economic_state,
+            // The rewards of the blocks within the finalization delay are not finalized yet
+            None => break,
+        };
+        let primary = economic_state.issuance.primary.value();
+        let secondary = economic_state.issuance.secondary.value();
+        if c != parent_c + primary + secondary {
+            return Err(Divergence::DaoIssuance {
+                node_name: node.node_name().to_string(),
+                number,
+                expected: Capacity::shannons(parent_c + primary + secondary),
+                actual: Capacity::shannons(c),
+            });
+        }
+        parent_c = c;
+
+        let issued = epochs.entry(header.epoch().number()).or_default();
+        issued.0 += primary;
+        issued.1 += secondary;
+        issued.2 += 1;
+    }
+
+    for (epoch, (primary, secondary, finalized)) in epochs {
+        if epoch == 0 {
+            continue;
+        }
+        let length = node
+            .rpc_client()
+            .get_epoch_by_number(epoch)
+            .expect("epoch exists")
+            .length
+            .value();
+        if finalized < length {
+            break;
+        }
+        let expected_primary = initial_primary_epoch_reward >> (epoch / halving_interval);
+        if primary != expected_primary || secondary != secondary_epoch_reward {
+            return Err(Divergence::EpochIssuance {
+                node_name: node.node_name().to_string(),
+                epoch,
+                expected: (
+                    Capacity::shannons(expected_primary),
+                    Capacity::shannons(secondary_epoch_reward),
+                ),
+                actual: (Capacity::shannons(primary), Capacity::shannons(secondary)),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_live_cells(nodes: &[&Node]) -> Result<(), Divergence> {
+    let tip_hashes = nodes
+        .iter()
+        .map(|node| node.get_tip_block().hash())
+        .collect::<HashSet<_>>();
+    if tip_hashes.len() != 1 {
+        return Ok(());
+    }
+    let number = nodes[0].get_tip_block_number();
+    let live_cells = nodes
+        .iter()
+        .map(|node| {
+            let out_points = node
+                .get_live_out_points()
+                .into_iter()
+                .collect::<HashSet<_>>();
+            (node.node_name().to_string(), out_points)
+        })
+        .collect::<Vec<_>>();
+    let mut all_out_points = live_cells
+        .iter()
+        .flat_map(|(_, out_points)| out_points.iter().cloned())
+        .collect::<Vec<_>>();
+    // Report the same divergence on every run
+    all_out_points.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    all_out_points.dedup();
+    for out_point in all_out_points {
+        let (present, absent): (Vec<_>, Vec<_>) = live_cells
+            .iter()
+            .partition(|(_, out_points)| out_points.contains(&out_point));
+        if !absent.is_empty() {
+            return Err(Divergence::LiveCells {
+                number,
+                out_point,
+                present: present.into_iter().map(|(name, _)| name.clone()).collect(),
+                absent: absent.into_iter().map(|(name, _)| name.clone()).collect(),
+            });
+        }
+    }
+    Ok(())
+}
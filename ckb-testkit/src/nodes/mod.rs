@@ -1,5 +1,10 @@
 mod chain;
+mod fork;
+mod invariant;
 mod nodes;
 mod p2p;
+mod topology;
 
+pub use fork::{Fork, ForkBranch};
+pub use invariant::Divergence;
 pub use nodes::Nodes;
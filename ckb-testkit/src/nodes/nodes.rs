@@ -1,14 +1,19 @@
+use super::topology::PartitionGuard;
 use crate::Node;
 use std::collections::hash_map::{Keys, Values};
 use std::collections::HashMap;
 
 pub struct Nodes {
     _inner: HashMap<String, Node>,
+    pub(super) partition_guard: Option<PartitionGuard>,
 }
 
 impl From<HashMap<String, Node>> for Nodes {
     fn from(nodes: HashMap<String, Node>) -> Self {
-        Nodes { _inner: nodes }
+        Nodes {
+            _inner: nodes,
+            partition_guard: None,
+        }
     }
 }
 
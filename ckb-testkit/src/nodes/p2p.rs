@@ -1,4 +1,4 @@
-use crate::Nodes;
+use crate::{Node, Nodes};
 
 impl Nodes {
     pub fn p2p_connect(&self) {
@@ -6,13 +6,7 @@ impl Nodes {
             for node_b in self.nodes() {
                 if node_a.p2p_address() != node_b.p2p_address() && !node_a.is_p2p_connected(node_b)
                 {
-                    if node_a.get_tip_block_number() < node_b.get_tip_block_number() {
-                        // An ibd node will not request GetHeaders from inbound peers.
-                        // https://github.com/nervosnetwork/ckb/blob/78fb281317aeaaa8b2621908cda79928ac697df4/sync/src/synchronizer/mod.rs#L543
-                        node_a.p2p_connect(node_b);
-                    } else {
-                        node_b.p2p_connect(node_a);
-                    }
+                    p2p_connect_pair(node_a, node_b);
                 }
             }
         }
@@ -28,3 +22,14 @@ impl Nodes {
         }
     }
 }
+
+/// Connect `node_a` and `node_b`, the one with lower tip dials the other.
+pub(super) fn p2p_connect_pair(node_a: &Node, node_b: &Node) {
+    if node_a.get_tip_block_number() < node_b.get_tip_block_number() {
+        // An ibd node will not request GetHeaders from inbound peers.
+        // https://github.com/nervosnetwork/ckb/blob/78fb281317aeaaa8b2621908cda79928ac697df4/sync/src/synchronizer/mod.rs#L543
+        node_a.p2p_connect(node_b);
+    } else {
+        node_b.p2p_connect(node_a);
+    }
+}
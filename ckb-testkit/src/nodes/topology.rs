@@ -0,0 +1,291 @@
+use super::p2p::p2p_connect_pair;
+use crate::util::{rng::XorShift, wait_until};
+use crate::Nodes;
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// An undirected edge between two nodes, identified by node names. The smaller name comes first.
+pub type Edge = (String, String);
+
+fn edge(node_a: &str, node_b: &str) -> Edge {
+    if node_a <= node_b {
+        (node_a.to_string(), node_b.to_string())
+    } else {
+        (node_b.to_string(), node_a.to_string())
+    }
+}
+
+// Long enough to outlive any case, the bans are removed by `Nodes::heal`
+const PARTITION_BAN_TIME_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// PartitionGuard records how the partitioned groups are kept apart.
+///
+/// Every node listens on its own loopback IP, so each node bans the IPs of the nodes in the
+/// other groups. A node neither dials a banned IP nor keeps the sessions it dialed to one,
+/// and as both sides ban each other, no cross-group connection survives, even via discovery.
+pub(super) struct PartitionGuard {
+    // the cross-group edges which were connected before partition
+    cut_edges: Vec<Edge>,
+    // (node name, the banned address)
+    bans: Vec<(String, String)>,
+}
+
+impl Nodes {
+    /// Connect the nodes as a line, `node_names[0] - node_names[1] - ... - node_names[n-1]`.
+    pub fn p2p_connect_line(&self, node_names: &[&str]) {
+        let edges = node_names
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        self.p2p_connect_edges(&edges);
+    }
+
+    /// Connect the nodes as a ring, the line plus `node_names[n-1] - node_names[0]`.
+    pub fn p2p_connect_ring(&self, node_names: &[&str]) {
+        let mut edges = node_names
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        if node_names.len() > 2 {
+            edges.push((node_names[node_names.len() - 1], node_names[0]));
+        }
+        self.p2p_connect_edges(&edges);
+    }
+
+    /// Connect every node of `leaves` to `center`.
+    pub fn p2p_connect_star(&self, center: &str, leaves: &[&str]) {
+        let edges = leaves
+            .iter()
+            .map(|leaf| (center, *leaf))
+            .collect::<Vec<_>>();
+        self.p2p_connect_edges(&edges);
+    }
+
+    /// Connect the nodes as a random connected graph, in which each node has at least `degree`
+    /// peers (as long as there are enough nodes). The same `seed` produces the same graph.
+    /// Return the edges of the graph.
+    pub fn p2p_connect_random(&self, node_names: &[&str], degree: usize, seed: u64) -> Vec<Edge> {
+        let mut rng = XorShift::new(seed);
+        let mut shuffled = node_names.to_vec();
+        rng.shuffle(&mut shuffled);
+
+        // A random spanning tree makes sure the graph is connected
+        let mut edges = BTreeSet::new();
+        for i in 1..shuffled.len() {
+            let parent = shuffled[rng.gen_range(i as u64) as usize];
+            edges.insert(edge(parent, shuffled[i]));
+        }
+
+        // Then add random edges until every node reaches `degree`
+        let degree = degree.min(node_names.len().saturating_sub(1));
+        for node_name in shuffled.iter() {
+            let count_degree = |edges: &BTreeSet<Edge>| {
+                edges
+                    .iter()
+                    .filter(|(a, b)| a == node_name || b == node_name)
+                    .count()
+            };
+            while count_degree(&edges) < degree {
+                let other = shuffled[rng.gen_range(shuffled.len() as u64) as usize];
+                if other != *node_name {
+                    edges.insert(edge(node_name, other));
+                }
+            }
+        }
+
+        let edges = edges.into_iter().collect::<Vec<_>>();
+        let edges_ref = edges
+            .iter()
+            .map(|(a, b)| (a.as_str(), b.as_str()))
+            .collect::<Vec<_>>();
+        self.p2p_connect_edges(&edges_ref);
+        edges
+    }
+
+    /// Connect the nodes according to `edges`.
+    pub fn p2p_connect_edges(&self, edges: &[(&str, &str)]) {
+        for (node_a, node_b) in edges {
+            let node_a = self.get_node(node_a);
+            let node_b = self.get_node(node_b);
+            if !node_a.is_p2p_connected(node_b) {
+                p2p_connect_pair(node_a, node_b);
+            }
+        }
+    }
+
+    /// Return the current topology, built from `get_peers` of every node.
+    pub fn get_topology(&self) -> BTreeSet<Edge> {
+        let names_by_id = self
+            .nodes()
+            .map(|node| (node.node_id().to_string(), node.node_name()))
+            .collect::<HashMap<_, _>>();
+        let mut edges = BTreeSet::new();
+        for node in self.nodes() {
+            for peer in node.rpc_client().get_peers() {
+                // peers out of `Nodes`, e.g. a connector, are ignored
+                if let Some(peer_name) = names_by_id.get(&peer.node_id) {
+                    edges.insert(edge(node.node_name(), peer_name));
+                }
+            }
+        }
+        edges
+    }
+
+    /// Assert the current topology contains all of `edges`. As discovery may connect more nodes,
+    /// extra edges are allowed.
+    pub fn assert_topology_contains(&self, edges: &[(&str, &str)]) {
+        let topology = self.get_topology();
+        let missing = edges
+            .iter()
+            .map(|(a, b)| edge(a, b))
+            .filter(|edge| !topology.contains(edge))
+            .collect::<Vec<_>>();
+        assert!(
+            missing.is_empty(),
+            "topology is missing edges {:?}, actual topology: {:?}",
+            missing,
+            topology
+        );
+    }
+
+    /// Assert there is no connection between `group_a` and `group_b`.
+    pub fn assert_partitioned(&self, group_a: &[&str], group_b: &[&str]) {
+        let topology = self.get_topology();
+        let crossed = cross_edges(group_a, group_b)
+            .into_iter()
+            .filter(|edge| topology.contains(edge))
+            .collect::<Vec<_>>();
+        assert!(
+            crossed.is_empty(),
+            "groups {:?} and {:?} are connected via {:?}",
+            group_a,
+            group_b,
+            crossed
+        );
+    }
+
+    /// Cut off all the connections between `group_a` and `group_b`, and keep them apart until
+    /// `heal`. Connections inside each group are untouched.
+    pub fn partition(&mut self, group_a: &[&str], group_b: &[&str]) {
+        self.partition_groups(&[group_a, group_b]);
+    }
+
+    /// Like `partition`, but cut off the connections among any number of groups.
+    pub fn partition_groups(&mut self, groups: &[&[&str]]) {
+        assert!(
+            self.partition_guard.is_none(),
+            "nodes are partitioned already, heal them before partitioning again"
+        );
+        let groups = groups
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|name| self.get_node(name))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let n_grouped_nodes: usize = groups.iter().map(Vec::len).sum();
+        let grouped_names = groups
+            .iter()
+            .flatten()
+            .map(|node| node.node_name().to_string())
+            .collect::<HashSet<_>>();
+        assert_eq!(
+            n_grouped_nodes,
+            grouped_names.len(),
+            "partitioned groups should be disjoint"
+        );
+        crate::debug!(
+            "Nodes::partition_groups({:?})",
+            groups
+                .iter()
+                .map(|group| group
+                    .iter()
+                    .map(|node| node.node_name())
+                    .collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+        );
+
+        let mut cut_edges = Vec::new();
+        for (i, group_a) in groups.iter().enumerate() {
+            for group_b in groups.iter().skip(i + 1) {
+                for node_a in group_a.iter() {
+                    for node_b in group_b.iter() {
+                        if node_a.is_p2p_connected(node_b) {
+                            cut_edges.push(edge(node_a.node_name(), node_b.node_name()));
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut bans = Vec::new();
+        for (i, group) in groups.iter().enumerate() {
+            for node in group.iter() {
+                for (j, other_group) in groups.iter().enumerate() {
+                    if i == j {
+                        continue;
+                    }
+                    for other in other_group.iter() {
+                        let address = format!("{}/32", other.p2p_socket_address().ip());
+                        node.rpc_client().set_ban(
+                            address.clone(),
+                            "insert".to_string(),
+                            Some(PARTITION_BAN_TIME_MS.into()),
+                            Some(false),
+                            Some("partition".to_string()),
+                        );
+                        bans.push((node.node_name().to_string(), address));
+                    }
+                }
+            }
+        }
+        for (node_a, node_b) in cut_edges.iter() {
+            let node_a = self.get_node(node_a);
+            let node_b = self.get_node(node_b);
+            let disconnected = wait_until(10, || !node_a.is_p2p_connected(node_b));
+            assert!(
+                disconnected,
+                "Nodes::partition_groups, {} and {} are still connected after banned",
+                node_a.node_name(),
+                node_b.node_name()
+            );
+        }
+        self.partition_guard = Some(PartitionGuard { cut_edges, bans });
+    }
+
+    /// Remove the bans set by `partition` and restore the connections cut by it.
+    pub fn heal(&mut self) {
+        if let Some(guard) = self.partition_guard.take() {
+            for (node_name, address) in guard.bans.iter() {
+                self.get_node(node_name).rpc_client().set_ban(
+                    address.clone(),
+                    "delete".to_string(),
+                    None,
+                    None,
+                    None,
+                );
+            }
+            crate::debug!("Nodes::heal, restoring edges {:?}", guard.cut_edges);
+            for (node_a, node_b) in guard.cut_edges.iter() {
+                let node_a = self.get_node(node_a);
+                let node_b = self.get_node(node_b);
+                // The disconnected peers may be reconnected automatically
+                if !wait_until(1, || node_a.is_p2p_connected(node_b)) {
+                    p2p_connect_pair(node_a, node_b);
+                }
+            }
+        }
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        self.partition_guard.is_some()
+    }
+}
+
+fn cross_edges(group_a: &[&str], group_b: &[&str]) -> Vec<Edge> {
+    group_a
+        .iter()
+        .flat_map(|a| group_b.iter().map(move |b| edge(a, b)))
+        .collect()
+}
@@ -0,0 +1,314 @@
+//! A fault-injecting TCP proxy which sits between two nodes.
+//!
+//! ```ignore
+//! let proxy = Proxy::start(node_b);
+//! node_a.p2p_connect_via_proxy(node_b, &proxy);
+//!
+//! proxy.set_latency(Duration::from_millis(500));
+//! proxy.partition();
+//! // ...
+//! proxy.heal();
+//! ```
+use crate::util::{find_available_port, rng::XorShift};
+use crate::Node;
+use ckb_stop_handler::{SignalSender, StopHandler};
+use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+const BUFFER_SIZE: usize = 16 * 1024;
+
+/// Faults applied on every chunk forwarded through the proxy, in both directions.
+///
+/// Faults only delay chunks and never alter or discard bytes: the p2p stream is encrypted, a
+/// corrupted stream is torn down rather than observed as a lossy link.
+#[derive(Debug, Clone, Default)]
+pub struct ProxyFaults {
+    /// Delay before forwarding each chunk
+    pub latency: Option<Duration>,
+    /// Bandwidth cap, in bytes per second
+    pub bandwidth: Option<u64>,
+    /// The ratio of chunks considered lost, within `[0.0, 1.0]`
+    pub loss_ratio: f64,
+    /// The extra delay of a lost chunk, as if it was retransmitted
+    pub loss_delay: Duration,
+    /// Keep the connections open but stop reading any data, so both sides consider the
+    /// connection alive while the other side is gone. The unread data backs up in the socket
+    /// buffers and is forwarded once resumed.
+    pub half_open: bool,
+    /// Seed of the pseudo-random generator deciding which chunks are lost
+    pub seed: u64,
+}
+
+/// Proxy forwards the connections it accepts to the target node's p2p port.
+pub struct Proxy {
+    target_node_name: String,
+    listen_address: SocketAddr,
+    target_address: SocketAddr,
+    faults_sender: watch::Sender<ProxyFaults>,
+    // Keep a receiver alive, otherwise `faults_sender.send` fails
+    faults_receiver: watch::Receiver<ProxyFaults>,
+    partition_sender: watch::Sender<bool>,
+    _stop_handler: StopHandler<tokio::sync::oneshot::Sender<()>>,
+}
+
+impl Proxy {
+    /// Start a proxy in front of `target`.
+    pub fn start(target: &Node) -> Self {
+        Self::start_to(target.node_name(), target.p2p_socket_address())
+    }
+
+    // Start a proxy forwarding to any TCP address
+    pub(crate) fn start_to(target_node_name: &str, target_address: SocketAddr) -> Self {
+        let listen_address = SocketAddr::V4(SocketAddrV4::new(
+            Ipv4Addr::LOCALHOST,
+            find_available_port(),
+        ));
+        let (faults_sender, faults_receiver) = watch::channel(ProxyFaults::default());
+        let (partition_sender, partition_receiver) = watch::channel(false);
+        let (stopped_signal_sender, mut stopped_signal_receiver) = tokio::sync::oneshot::channel();
+
+        let faults_receiver_ = faults_receiver.clone();
+        let (listened_sender, listened_receiver) = crossbeam::channel::bounded(1);
+        crate::logger::spawn(move || {
+            let rt = crate::logger::tokio_runtime();
+            rt.block_on(async move {
+                let listener = TcpListener::bind(listen_address)
+                    .await
+                    .unwrap_or_else(|err| {
+                        panic!("proxy failed to listen on {}, error: {}", listen_address, err)
+                    });
+                let _ = listened_sender.send(());
+                loop {
+                    tokio::select! {
+                        Ok((inbound, _)) = listener.accept() => {
+                            if *partition_receiver.borrow() {
+                                // Refuse new connections during partition
+                                drop(inbound);
+                                continue;
+                            }
+                            let faults_receiver = faults_receiver_.clone();
+                            let partition_receiver = partition_receiver.clone();
+                            tokio::spawn(async move {
+                                if let Err(err) = forward(inbound, target_address, faults_receiver, partition_receiver).await {
+                                    crate::debug!("Proxy failed to forward to {}, error: {}", target_address, err);
+                                }
+                            });
+                        }
+                        _ = &mut stopped_signal_receiver => {
+                            break;
+                        }
+                    }
+                }
+            });
+        });
+        listened_receiver
+            .recv()
+            .expect("proxy should be listening before returning");
+
+        crate::info!(
+            "[Proxy {}] START listen_address: {}, target_address: {}",
+            target_node_name,
+            listen_address,
+            target_address
+        );
+        Self {
+            target_node_name: target_node_name.to_string(),
+            listen_address,
+            target_address,
+            faults_sender,
+            faults_receiver,
+            partition_sender,
+            _stop_handler: StopHandler::new(
+                SignalSender::Tokio(stopped_signal_sender),
+                None,
+                "proxy".to_string(),
+            ),
+        }
+    }
+
+    /// P2p address of the proxy, without node_id. E.g. "/ip4/127.0.0.1/tcp/9003"
+    pub fn p2p_address(&self) -> String {
+        format!("/ip4/127.0.0.1/tcp/{}", self.listen_address.port())
+    }
+
+    pub fn listen_address(&self) -> SocketAddr {
+        self.listen_address
+    }
+
+    pub fn target_address(&self) -> SocketAddr {
+        self.target_address
+    }
+
+    /// Return a snapshot of current faults
+    pub fn faults(&self) -> ProxyFaults {
+        self.faults_receiver.borrow().clone()
+    }
+
+    pub fn set_faults(&self, faults: ProxyFaults) {
+        crate::debug!("[Proxy {}] set faults {:?}", self.target_node_name, faults);
+        let _ = self.faults_sender.send(faults);
+    }
+
+    pub fn set_latency(&self, latency: Duration) {
+        self.update_faults(|faults| faults.latency = Some(latency));
+    }
+
+    /// Set bandwidth cap, in bytes per second. `None` means unlimited.
+    pub fn set_bandwidth(&self, bytes_per_second: Option<u64>) {
+        assert_ne!(
+            bytes_per_second,
+            Some(0),
+            "use `partition` to cut off traffic"
+        );
+        self.update_faults(|faults| faults.bandwidth = bytes_per_second);
+    }
+
+    /// Consider `loss_ratio` of the chunks lost, each lost chunk is forwarded after an extra
+    /// `loss_delay`.
+    pub fn set_loss(&self, loss_ratio: f64, loss_delay: Duration) {
+        assert!(
+            (0.0..=1.0).contains(&loss_ratio),
+            "loss_ratio: {}",
+            loss_ratio
+        );
+        self.update_faults(|faults| {
+            faults.loss_ratio = loss_ratio;
+            faults.loss_delay = loss_delay;
+        });
+    }
+
+    pub fn set_half_open(&self, half_open: bool) {
+        self.update_faults(|faults| faults.half_open = half_open);
+    }
+
+    /// Reset all faults, but not partition.
+    pub fn reset_faults(&self) {
+        self.set_faults(ProxyFaults::default());
+    }
+
+    /// Close all forwarded connections and refuse the new ones until `heal`.
+    pub fn partition(&self) {
+        crate::debug!("[Proxy {}] partition", self.target_node_name);
+        let _ = self.partition_sender.send(true);
+    }
+
+    pub fn heal(&self) {
+        crate::debug!("[Proxy {}] heal", self.target_node_name);
+        let _ = self.partition_sender.send(false);
+    }
+
+    pub fn is_partitioned(&self) -> bool {
+        *self.partition_sender.borrow()
+    }
+
+    fn update_faults<F>(&self, f: F)
+    where
+        F: FnOnce(&mut ProxyFaults),
+    {
+        let mut faults = self.faults();
+        f(&mut faults);
+        self.set_faults(faults);
+    }
+}
+
+async fn forward(
+    inbound: TcpStream,
+    target_address: SocketAddr,
+    faults_receiver: watch::Receiver<ProxyFaults>,
+    partition_receiver: watch::Receiver<bool>,
+) -> Result<(), String> {
+    let outbound = TcpStream::connect(target_address)
+        .await
+        .map_err(|err| err.to_string())?;
+    let (inbound_reader, inbound_writer) = inbound.into_split();
+    let (outbound_reader, outbound_writer) = outbound.into_split();
+    let upstream = pump(
+        inbound_reader,
+        outbound_writer,
+        faults_receiver.clone(),
+        partition_receiver.clone(),
+    );
+    let downstream = pump(
+        outbound_reader,
+        inbound_writer,
+        faults_receiver,
+        partition_receiver,
+    );
+    futures::join!(upstream, downstream);
+    Ok(())
+}
+
+async fn pump(
+    mut reader: OwnedReadHalf,
+    mut writer: OwnedWriteHalf,
+    mut faults_receiver: watch::Receiver<ProxyFaults>,
+    mut partition_receiver: watch::Receiver<bool>,
+) {
+    let mut buffer = vec![0u8; BUFFER_SIZE];
+    let mut rng = XorShift::new(faults_receiver.borrow().seed);
+    loop {
+        if !wait_until_resumed(&mut faults_receiver, &mut partition_receiver).await {
+            break;
+        }
+        let n = tokio::select! {
+            result = reader.read(&mut buffer) => match result {
+                Ok(0) | Err(_) => break,
+                Ok(n) => n,
+            },
+            changed = partition_receiver.changed() => {
+                let partitioned = *partition_receiver.borrow();
+                if changed.is_err() || partitioned {
+                    break;
+                }
+                continue;
+            }
+        };
+
+        // Hold the chunk read right before turning half-open
+        if !wait_until_resumed(&mut faults_receiver, &mut partition_receiver).await {
+            break;
+        }
+        let faults = faults_receiver.borrow().clone();
+        if faults.loss_ratio > 0.0 && rng.next_f64() < faults.loss_ratio {
+            tokio::time::sleep(faults.loss_delay).await;
+        }
+        if let Some(latency) = faults.latency {
+            tokio::time::sleep(latency).await;
+        }
+        if let Some(bandwidth) = faults.bandwidth {
+            let transmission = Duration::from_secs_f64(n as f64 / bandwidth as f64);
+            tokio::time::sleep(transmission).await;
+        }
+        if writer.write_all(&buffer[..n]).await.is_err() {
+            break;
+        }
+    }
+    let _ = writer.shutdown().await;
+}
+
+// Wait while the connection is half-open. Return false if the connection should be closed,
+// i.e. partitioned or the proxy stopped.
+async fn wait_until_resumed(
+    faults_receiver: &mut watch::Receiver<ProxyFaults>,
+    partition_receiver: &mut watch::Receiver<bool>,
+) -> bool {
+    while faults_receiver.borrow().half_open {
+        tokio::select! {
+            changed = faults_receiver.changed() => {
+                if changed.is_err() {
+                    return false;
+                }
+            }
+            changed = partition_receiver.changed() => {
+                if changed.is_err() || *partition_receiver.borrow() {
+                    return false;
+                }
+            }
+        }
+    }
+    true
+}
@@ -0,0 +1,40 @@
+//! The types of the indexer RPC `get_cells`, served by the ckb built-in indexer and the
+//! standalone ckb-indexer.
+
+use ckb_jsonrpc_types::{BlockNumber, CellOutput, JsonBytes, OutPoint, Script, Uint32};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchKey {
+    pub script: Script,
+    pub script_type: ScriptType,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptType {
+    Lock,
+    Type,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    Desc,
+    Asc,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerCell {
+    pub output: CellOutput,
+    pub output_data: JsonBytes,
+    pub out_point: OutPoint,
+    pub block_number: BlockNumber,
+    pub tx_index: Uint32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pagination<T> {
+    pub objects: Vec<T>,
+    pub last_cursor: JsonBytes,
+}
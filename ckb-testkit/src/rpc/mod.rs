@@ -2,15 +2,16 @@ mod id_generator;
 #[macro_use]
 mod macros;
 mod error;
+mod indexer;
 mod v2019;
 mod v2021;
 
 use ckb_error::AnyError;
 // TODO replace json types with core types
 use ckb_jsonrpc_types::{
-    Alert, BannedAddr, Block, BlockTemplate, BlockView, CellWithStatus, ChainInfo, Consensus,
-    DryRunResult, EpochView, HeaderView, LocalNode, OutPoint, RawTxPool, RemoteNode, Timestamp,
-    Transaction, TransactionWithStatus, TxPoolInfo,
+    Alert, BannedAddr, Block, BlockEconomicState, BlockTemplate, BlockView, CellWithStatus,
+    ChainInfo, Consensus, DryRunResult, EpochView, HeaderView, JsonBytes, LocalNode, OutPoint,
+    RawTxPool, RemoteNode, Timestamp, Transaction, TransactionWithStatus, TxPoolInfo,
 };
 use ckb_types::core::{
     BlockNumber as CoreBlockNumber, Capacity as CoreCapacity, EpochNumber as CoreEpochNumber,
@@ -22,6 +23,8 @@ use std::time::{Duration, Instant};
 use v2019::Inner2019;
 use v2021::Inner2021;
 
+pub use indexer::{IndexerCell, Order, Pagination, ScriptType, SearchKey};
+
 lazy_static! {
     pub static ref HTTP_CLIENT: reqwest::blocking::Client = reqwest::blocking::Client::builder()
         .timeout(::std::time::Duration::from_secs(30))
@@ -394,6 +397,27 @@ impl RpcClient {
             .into()
     }
 
+    /// Return `None` if the block is not found or its rewards are not finalized yet.
+    pub fn get_block_economic_state(&self, hash: Byte32) -> Option<BlockEconomicState> {
+        self.inner()
+            .get_block_economic_state(hash.unpack())
+            .expect("rpc call get_block_economic_state")
+    }
+
+    /// Call the indexer RPC `get_cells`, which requires the node to enable its built-in
+    /// indexer, or `self` to point to a ckb-indexer server.
+    pub fn get_cells(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> Pagination<IndexerCell> {
+        self.inner()
+            .get_cells(search_key, order, limit.into(), after)
+            .expect("rpc call get_cells")
+    }
+
     pub fn process_block_without_verify(
         &self,
         block: Block,
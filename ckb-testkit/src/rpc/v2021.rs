@@ -1,8 +1,9 @@
+use super::indexer::{IndexerCell, Order, Pagination, SearchKey};
 use ckb_jsonrpc_types::{
-    Alert, BannedAddr, Block, BlockNumber, BlockTemplate, BlockView, Byte32, Capacity,
-    CellWithStatus, ChainInfo, Consensus, DryRunResult, EpochNumber, EpochView, HeaderView,
-    JsonBytes, LocalNode, OutPoint, RawTxPool, RemoteNode, Script, Timestamp, Transaction,
-    TransactionWithStatus, TxPoolInfo, Uint64, Version,
+    Alert, BannedAddr, Block, BlockEconomicState, BlockNumber, BlockTemplate, BlockView, Byte32,
+    Capacity, CellWithStatus, ChainInfo, Consensus, DryRunResult, EpochNumber, EpochView,
+    HeaderView, JsonBytes, LocalNode, OutPoint, RawTxPool, RemoteNode, Script, Timestamp,
+    Transaction, TransactionWithStatus, TxPoolInfo, Uint32, Uint64, Version,
 };
 use ckb_types::H256;
 
@@ -58,4 +59,13 @@ jsonrpc!(pub struct Inner2021 {
     pub fn get_raw_tx_pool(&self, verbose: Option<bool>) -> RawTxPool;
 
     pub fn calculate_dao_maximum_withdraw(&self, _out_point: OutPoint, _hash: H256) -> Capacity;
+    pub fn get_block_economic_state(&self, _hash: H256) -> Option<BlockEconomicState>;
+
+    pub fn get_cells(
+        &self,
+        search_key: SearchKey,
+        order: Order,
+        limit: Uint32,
+        after: Option<JsonBytes>
+    ) -> Pagination<IndexerCell>;
 });
@@ -0,0 +1,198 @@
+use crate::node::extract_dao_data;
+use crate::tests::{consensus, DAO_TYPE_HASH};
+use crate::BlockAssembler;
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        BlockView, Capacity, EpochNumberWithFraction, HeaderBuilder, HeaderView, TransactionBuilder,
+    },
+    h256,
+    packed::{Byte32, CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+    H256,
+};
+use std::str::FromStr;
+
+const PRIMARY_EPOCH_REWARD: u64 = 1_000_000_000;
+// The remainder 500 goes to the first 500 blocks of an epoch
+const SECONDARY_EPOCH_REWARD: u64 = 1_000_000_500;
+const EPOCH_LENGTH: u64 = 1000;
+
+const PARENT_AR: u64 = 10_000_000_000_000_000;
+const PARENT_C: u64 = 10_000_000_000;
+const PARENT_S: u64 = 0;
+const PARENT_U: u64 = 4_000_000_000;
+
+// The occupied capacity of a cell locked by the default script without data, 8 + 33 bytes
+const EMPTY_CELL_OCCUPIED: u64 = 4_100_000_000;
+
+fn dao(ar: u64, c: u64, s: u64, u: u64) -> Byte32 {
+    let mut buf = [0u8; 32];
+    buf[0..8].copy_from_slice(&c.to_le_bytes());
+    buf[8..16].copy_from_slice(&ar.to_le_bytes());
+    buf[16..24].copy_from_slice(&s.to_le_bytes());
+    buf[24..32].copy_from_slice(&u.to_le_bytes());
+    buf.pack()
+}
+
+fn parent(number: u64, epoch: EpochNumberWithFraction) -> HeaderView {
+    HeaderBuilder::default()
+        .number(number.pack())
+        .epoch(epoch.pack())
+        .timestamp(1_000_000u64.pack())
+        .compact_target(0x2000_0100u32.pack())
+        .dao(dao(PARENT_AR, PARENT_C, PARENT_S, PARENT_U))
+        .build()
+}
+
+fn assemble(assembler: BlockAssembler, inputs: &[(OutPoint, CellOutput, Bytes)]) -> BlockView {
+    assembler
+        .assemble(|out_point| {
+            inputs
+                .iter()
+                .find(|(op, _, _)| op == out_point)
+                .map(|(_, output, data)| (output.clone(), data.clone()))
+        })
+        .expect("assemble")
+}
+
+#[test]
+fn test_block_assembler_header_fields() {
+    let consensus = consensus(PRIMARY_EPOCH_REWARD, SECONDARY_EPOCH_REWARD, &[]);
+    let parent = parent(1999, EpochNumberWithFraction::new(1, 999, EPOCH_LENGTH));
+    let block = assemble(BlockAssembler::new(&consensus, &parent), &[]);
+    assert_eq!(block.number(), 2000);
+    assert_eq!(block.parent_hash(), parent.hash());
+    assert_eq!(
+        block.epoch(),
+        EpochNumberWithFraction::new(2, 0, EPOCH_LENGTH)
+    );
+    assert_eq!(block.timestamp(), parent.timestamp() + 1);
+    assert_eq!(block.compact_target(), parent.compact_target());
+    assert_eq!(block.transactions().len(), 1);
+    assert!(block.transaction(0).unwrap().is_cellbase());
+    assert!(block.transaction(0).unwrap().outputs().is_empty());
+}
+
+#[test]
+fn test_block_assembler_dao_issuance() {
+    let consensus = consensus(PRIMARY_EPOCH_REWARD, SECONDARY_EPOCH_REWARD, &[]);
+    // The block 1001 is the block #1 of epoch 1, which gets the remainder of secondary reward
+    let parent = parent(1000, EpochNumberWithFraction::new(1, 0, EPOCH_LENGTH));
+    let block = assemble(BlockAssembler::new(&consensus, &parent), &[]);
+
+    let g2 = 1_000_001;
+    let g = 1_000_000 + g2;
+    // miner gets g2 * U / C, the rest goes to NervosDAO
+    let miner_issuance = 400_000;
+    assert_eq!(
+        extract_dao_data(&block.dao()),
+        (
+            PARENT_AR + PARENT_AR / PARENT_C * g2,
+            PARENT_C + g,
+            PARENT_S + g2 - miner_issuance,
+            PARENT_U
+        )
+    );
+
+    // Block #500 of the epoch no longer gets the remainder
+    let parent = parent_at(1499, 499);
+    let block = assemble(BlockAssembler::new(&consensus, &parent), &[]);
+    let (_, c, _, _) = extract_dao_data(&block.dao());
+    assert_eq!(c, PARENT_C + 1_000_000 + 1_000_000);
+}
+
+fn parent_at(number: u64, index: u64) -> HeaderView {
+    parent(number, EpochNumberWithFraction::new(1, index, EPOCH_LENGTH))
+}
+
+#[test]
+fn test_block_assembler_dao_occupied_capacities() {
+    let consensus = consensus(PRIMARY_EPOCH_REWARD, SECONDARY_EPOCH_REWARD, &[]);
+    let parent = parent_at(1000, 0);
+
+    // The cellbase output occupies capacity
+    let block = assemble(
+        BlockAssembler::new(&consensus, &parent)
+            .cellbase_reward(Capacity::shannons(50_000_000_000), Script::default()),
+        &[],
+    );
+    let (_, _, _, u) = extract_dao_data(&block.dao());
+    assert_eq!(u, PARENT_U + EMPTY_CELL_OCCUPIED);
+
+    // Consume a cell with 10 bytes data, create an empty one and a child spending it
+    let input_out_point = OutPoint::new(h256!("0x1").pack(), 0);
+    let input = CellOutput::new_builder()
+        .capacity(Capacity::shannons(10_000_000_000).pack())
+        .build();
+    let input_data = Bytes::from(vec![1u8; 10]);
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(input_out_point.clone(), 0))
+        .output(
+            CellOutput::new_builder()
+                .capacity(Capacity::shannons(9_000_000_000).pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let child = TransactionBuilder::default()
+        .input(CellInput::new(OutPoint::new(tx.hash(), 0), 0))
+        .output(
+            CellOutput::new_builder()
+                .capacity(Capacity::shannons(8_000_000_000).pack())
+                .build(),
+        )
+        .output_data(Bytes::new().pack())
+        .build();
+    let block = assemble(
+        BlockAssembler::new(&consensus, &parent)
+            .transaction(&tx)
+            .transaction(&child),
+        &[(input_out_point, input, input_data)],
+    );
+    let (_, _, _, u) = extract_dao_data(&block.dao());
+    assert_eq!(u, PARENT_U - 1_000_000_000);
+}
+
+#[test]
+fn test_block_assembler_unsupported() {
+    let consensus = consensus(PRIMARY_EPOCH_REWARD, SECONDARY_EPOCH_REWARD, &[]);
+    let parent = parent_at(1000, 0);
+
+    // NervosDAO withdrawing, i.e. a DAO cell with non-zero data
+    let input_out_point = OutPoint::new(h256!("0x1").pack(), 0);
+    let dao_type = Script::new_builder()
+        .code_hash(H256::from_str(&DAO_TYPE_HASH[2..]).unwrap().pack())
+        .build();
+    let input = CellOutput::new_builder()
+        .capacity(Capacity::shannons(20_000_000_000).pack())
+        .type_(Some(dao_type).pack())
+        .build();
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(input_out_point.clone(), 0))
+        .build();
+    let result = BlockAssembler::new(&consensus, &parent)
+        .transaction(&tx)
+        .assemble(|_| Some((input.clone(), Bytes::from(1u64.to_le_bytes().to_vec()))));
+    assert!(
+        matches!(&result, Err(err) if err.contains("withdraws from NervosDAO")),
+        "{:?}",
+        result
+    );
+
+    // Unresolvable input
+    let result = BlockAssembler::new(&consensus, &parent)
+        .transaction(&tx)
+        .assemble(|_| None);
+    assert!(
+        matches!(&result, Err(err) if err.contains("failed to resolve input")),
+        "{:?}",
+        result
+    );
+
+    // Adjusted difficulty
+    let mut consensus = consensus;
+    consensus.permanent_difficulty_in_dummy = false;
+    let result = BlockAssembler::new(&consensus, &parent).assemble(|_| None);
+    assert!(result.is_err());
+}
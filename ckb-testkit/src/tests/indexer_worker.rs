@@ -0,0 +1,239 @@
+use crate::node::{IndexerMetrics, IndexerWorker};
+use crate::rpc::RpcClient;
+use crate::util::Waiter;
+use ckb_indexer::{indexer::Indexer, store::RocksdbStore, store::Store};
+use ckb_types::{
+    bytes::Bytes,
+    core::{BlockBuilder, BlockNumber, BlockView, TransactionBuilder},
+    packed::{Byte32, CellInput, CellOutput},
+    prelude::*,
+    H256,
+};
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep};
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// A fake node serving the RPCs polled by the indexer worker, over a replaceable chain
+#[derive(Clone)]
+struct FakeNode {
+    chain: Arc<Mutex<Vec<BlockView>>>,
+    url: String,
+}
+
+impl FakeNode {
+    fn start(chain: Vec<BlockView>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind fake node");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let chain = Arc::new(Mutex::new(chain));
+        let fake_node = Self { chain, url };
+        let server = fake_node.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = server.clone();
+                thread::spawn(move || server.serve(stream));
+            }
+        });
+        fake_node
+    }
+
+    fn rpc_client(&self) -> RpcClient {
+        RpcClient::new(&self.url, true)
+    }
+
+    fn set_chain(&self, chain: Vec<BlockView>) {
+        *self.chain.lock().unwrap() = chain;
+    }
+
+    fn serve(&self, stream: TcpStream) {
+        let mut writer = stream.try_clone().expect("clone stream");
+        let mut reader = BufReader::new(stream);
+        loop {
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                    return;
+                }
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let lowercase = line.to_lowercase();
+                if let Some(length) = lowercase.strip_prefix("content-length:") {
+                    content_length = length.trim().parse().expect("content length");
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).expect("read request body");
+            let request: Value = serde_json::from_slice(&body).expect("json request");
+            let response = json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "result": self.handle(&request["method"], &request["params"]),
+            })
+            .to_string();
+            write!(
+                writer,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                response.len(),
+                response
+            )
+            .expect("write response");
+        }
+    }
+
+    fn handle(&self, method: &Value, params: &Value) -> Value {
+        let chain = self.chain.lock().unwrap();
+        let block = |params: &Value| {
+            let number = params[0].as_str().expect("block number param");
+            let number = u64::from_str_radix(number.trim_start_matches("0x"), 16).unwrap();
+            chain.get(number as usize)
+        };
+        match method.as_str().expect("method") {
+            "get_tip_block_number" => json!(format!("{:#x}", chain.len() - 1)),
+            "get_block_by_number" => match block(params) {
+                Some(block) => json!(ckb_jsonrpc_types::BlockView::from(block.clone())),
+                None => Value::Null,
+            },
+            "get_block_hash" => match block(params) {
+                Some(block) => json!(Unpack::<H256>::unpack(&block.hash())),
+                None => Value::Null,
+            },
+            method => panic!("unexpected rpc method {}", method),
+        }
+    }
+}
+
+// Extend `chain` to `tip_number`, the `salt` distinguishes the blocks of different forks
+fn extend_chain(chain: &mut Vec<BlockView>, tip_number: BlockNumber, salt: u64) {
+    while chain.len() as BlockNumber <= tip_number {
+        let number = chain.len() as BlockNumber;
+        let parent_hash = chain.last().map(|parent| parent.hash()).unwrap_or_default();
+        let cellbase = TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(number))
+            .output(CellOutput::default())
+            .output_data(Bytes::new().pack())
+            .build();
+        let block = BlockBuilder::default()
+            .number(number.pack())
+            .parent_hash(parent_hash)
+            .timestamp((number * 1000 + salt).pack())
+            .transaction(cellbase)
+            .build();
+        chain.push(block);
+    }
+}
+
+fn new_indexer(dir: &tempfile::TempDir) -> Indexer<RocksdbStore> {
+    Indexer::new(
+        RocksdbStore::new(dir.path().to_str().unwrap()),
+        1000000,
+        60 * 60,
+        None,
+    )
+}
+
+fn wait_for_metrics<F>(worker: &IndexerWorker, predicate: F) -> IndexerMetrics
+where
+    F: Fn(&IndexerMetrics) -> bool,
+{
+    Waiter::new("indexer worker metrics", Duration::from_secs(10))
+        .initial_interval(POLL_INTERVAL)
+        .wait_for(|| {
+            let metrics = worker.metrics();
+            if predicate(&metrics) {
+                Ok(metrics)
+            } else {
+                Err(metrics)
+            }
+        })
+        .unwrap_or_else(|err| panic!("{}", err))
+}
+
+fn indexer_tip(indexer: &Indexer<RocksdbStore>) -> (BlockNumber, Byte32) {
+    indexer.tip().expect("indexer tip").expect("indexed")
+}
+
+#[test]
+fn test_indexer_worker_metrics() {
+    let mut chain = Vec::new();
+    extend_chain(&mut chain, 4, 0);
+    let fake_node = FakeNode::start(chain.clone());
+    let dir = tempfile::tempdir().unwrap();
+    let indexer = new_indexer(&dir);
+    let worker = IndexerWorker::spawn(
+        "metrics".to_string(),
+        fake_node.rpc_client(),
+        indexer.clone(),
+        POLL_INTERVAL,
+    );
+
+    // The genesis block counts as appended
+    let metrics = wait_for_metrics(&worker, |metrics| metrics.indexed_number == 4);
+    assert_eq!(
+        metrics,
+        IndexerMetrics {
+            indexed_number: 4,
+            chain_tip_number: 4,
+            appended_blocks: 5,
+            rollbacks: 0,
+        }
+    );
+    assert_eq!(metrics.lag(), 0);
+    assert_eq!(indexer_tip(&indexer), (4, chain[4].hash()));
+
+    extend_chain(&mut chain, 7, 0);
+    fake_node.set_chain(chain.clone());
+    let metrics = wait_for_metrics(&worker, |metrics| metrics.indexed_number == 7);
+    assert_eq!(metrics.appended_blocks, 8);
+    assert_eq!(metrics.rollbacks, 0);
+    assert_eq!(indexer_tip(&indexer), (7, chain[7].hash()));
+
+    // The metrics are frozen once stopped
+    worker.stop();
+    assert!(worker.is_stopped());
+    extend_chain(&mut chain, 9, 0);
+    fake_node.set_chain(chain);
+    sleep(POLL_INTERVAL * 5);
+    assert_eq!(worker.metrics(), metrics);
+    assert_eq!(indexer_tip(&indexer).0, 7);
+}
+
+#[test]
+fn test_indexer_worker_rollback() {
+    let mut chain = Vec::new();
+    extend_chain(&mut chain, 4, 0);
+    let fake_node = FakeNode::start(chain.clone());
+    let dir = tempfile::tempdir().unwrap();
+    let indexer = new_indexer(&dir);
+    let worker = IndexerWorker::spawn(
+        "rollback".to_string(),
+        fake_node.rpc_client(),
+        indexer.clone(),
+        POLL_INTERVAL,
+    );
+    wait_for_metrics(&worker, |metrics| metrics.indexed_number == 4);
+
+    // Switch to a longer fork from block 2, the worker should roll back blocks 4 and 3
+    let mut fork = chain[..=2].to_vec();
+    extend_chain(&mut fork, 5, 1);
+    fake_node.set_chain(fork.clone());
+    let metrics = wait_for_metrics(&worker, |metrics| metrics.indexed_number == 5);
+    assert_eq!(metrics.rollbacks, 2);
+    assert_eq!(metrics.appended_blocks, 5 + 3);
+    assert_eq!(indexer_tip(&indexer), (5, fork[5].hash()));
+
+    // Switch back to the original chain, now shorter than the indexed fork
+    fake_node.set_chain(chain.clone());
+    let metrics = wait_for_metrics(&worker, |metrics| {
+        metrics.indexed_number == 4 && metrics.rollbacks == 2 + 3
+    });
+    assert_eq!(metrics.appended_blocks, 8 + 2);
+    assert_eq!(indexer_tip(&indexer), (4, chain[4].hash()));
+    worker.stop();
+}
@@ -0,0 +1,161 @@
+use crate::LiveCellTracker;
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{CellMeta, CellMetaBuilder},
+        BlockBuilder, BlockNumber, BlockView, Capacity, TransactionBuilder, TransactionView,
+    },
+    packed::{Byte32, CellInput, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+
+fn lock_script(id: u8) -> Script {
+    Script::new_builder().args([id][..].pack()).build()
+}
+
+fn output(id: u8) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(Capacity::shannons(1000).pack())
+        .lock(lock_script(id))
+        .build()
+}
+
+// A live cell known before tracking, e.g. a genesis cell
+fn known_cell(index: u32) -> CellMeta {
+    let out_point = OutPoint::new(Byte32::new([0xff; 32]), index);
+    CellMetaBuilder::from_cell_output(output(0), Bytes::new())
+        .out_point(out_point)
+        .build()
+}
+
+fn transaction(inputs: Vec<OutPoint>, output_ids: &[u8]) -> TransactionView {
+    TransactionBuilder::default()
+        .inputs(
+            inputs
+                .into_iter()
+                .map(|out_point| CellInput::new(out_point, 0)),
+        )
+        .outputs(output_ids.iter().map(|id| output(*id)))
+        .outputs_data(output_ids.iter().map(|_| Bytes::new().pack()))
+        .build()
+}
+
+fn block(number: BlockNumber, transactions: Vec<TransactionView>) -> BlockView {
+    let cellbase = TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(number))
+        .output(output(9))
+        .output_data(Bytes::new().pack())
+        .build();
+    BlockBuilder::default()
+        .number(number.pack())
+        .transaction(cellbase)
+        .transactions(transactions)
+        .build()
+}
+
+fn out_point(transaction: &TransactionView, index: u32) -> OutPoint {
+    OutPoint::new(transaction.hash(), index)
+}
+
+#[test]
+fn test_live_cell_tracker_spends_inputs_and_adds_outputs() {
+    let tracker = LiveCellTracker::new();
+    let cell = known_cell(0);
+    tracker.track_cell(cell.clone());
+    assert_eq!(
+        tracker.live_cells_by_lock_script(&lock_script(0)),
+        vec![cell.clone()]
+    );
+
+    let tx = transaction(vec![cell.out_point.clone()], &[1, 1, 2]);
+    tracker.track_transaction(&tx);
+    assert!(tracker.live_cell(&cell.out_point).is_none());
+    assert!(tracker
+        .live_cells_by_lock_script(&lock_script(0))
+        .is_empty());
+    let mut expected = vec![out_point(&tx, 0), out_point(&tx, 1)];
+    expected.sort_by(|a, b| a.as_slice().cmp(b.as_slice()));
+    let actual = tracker
+        .live_cells_by_lock_script(&lock_script(1))
+        .into_iter()
+        .map(|cell| cell.out_point)
+        .collect::<Vec<_>>();
+    assert_eq!(actual, expected);
+    let submitted = tracker.live_cell(&out_point(&tx, 2)).expect("live output");
+    assert_eq!(submitted.cell_output, output(2));
+    assert!(submitted.transaction_info.is_none());
+
+    // Tracking the block committing the transaction fills the transaction info, and adds the
+    // cellbase outputs
+    let block = block(1, vec![tx.clone()]);
+    tracker.track_block(&block);
+    let committed = tracker.live_cell(&out_point(&tx, 2)).expect("live output");
+    let txinfo = committed.transaction_info.expect("committed");
+    assert_eq!(txinfo.block_number, 1);
+    assert_eq!(txinfo.block_hash, block.hash());
+    assert_eq!(txinfo.index, 1);
+    let cellbase = block.transaction(0).unwrap();
+    let cellbase_cell = tracker
+        .live_cell(&out_point(&cellbase, 0))
+        .expect("cellbase");
+    assert!(cellbase_cell.transaction_info.unwrap().is_cellbase());
+}
+
+#[test]
+fn test_live_cell_tracker_duplicate_feeds() {
+    let tracker = LiveCellTracker::new();
+    let cell = known_cell(0);
+    tracker.track_cell(cell.clone());
+    let parent = transaction(vec![cell.out_point.clone()], &[1, 1]);
+    let child = transaction(vec![out_point(&parent, 0)], &[2]);
+    tracker.track_transaction(&parent);
+    tracker.track_transaction(&parent);
+    tracker.track_transaction(&child);
+
+    // The parent committed after the child is submitted, its spent output stays dead
+    tracker.track_block(&block(1, vec![parent.clone()]));
+    tracker.track_transaction(&parent);
+    assert!(tracker.live_cell(&cell.out_point).is_none());
+    assert!(tracker.live_cell(&out_point(&parent, 0)).is_none());
+    let parent_output = tracker.live_cell(&out_point(&parent, 1)).expect("live");
+    assert_eq!(parent_output.transaction_info.unwrap().block_number, 1);
+    assert!(tracker.live_cell(&out_point(&child, 0)).is_some());
+
+    tracker.track_block(&block(2, vec![child.clone()]));
+    tracker.track_block(&block(2, vec![child.clone()]));
+    assert!(tracker.live_cell(&out_point(&parent, 0)).is_none());
+    let child_output = tracker.live_cell(&out_point(&child, 0)).expect("live");
+    assert_eq!(child_output.transaction_info.unwrap().block_number, 2);
+}
+
+#[test]
+fn test_live_cell_tracker_conflicting_feeds() {
+    let tracker = LiveCellTracker::new();
+    let (cell0, cell1) = (known_cell(0), known_cell(1));
+    tracker.track_cell(cell0.clone());
+    tracker.track_cell(cell1.clone());
+    let submitted = transaction(vec![cell0.out_point.clone(), cell1.out_point.clone()], &[1]);
+    let child = transaction(vec![out_point(&submitted, 0)], &[2]);
+    tracker.track_transaction(&submitted);
+    tracker.track_transaction(&child);
+    assert!(tracker.live_cell(&cell1.out_point).is_none());
+
+    // A committed transaction conflicting with the submitted one replaces it and its child
+    let committed = transaction(vec![cell0.out_point.clone()], &[3]);
+    tracker.track_block(&block(1, vec![committed.clone()]));
+    assert!(tracker.live_cell(&cell0.out_point).is_none());
+    assert_eq!(tracker.live_cell(&cell1.out_point), Some(cell1.clone()));
+    assert!(tracker.live_cell(&out_point(&submitted, 0)).is_none());
+    assert!(tracker.live_cell(&out_point(&child, 0)).is_none());
+    assert!(tracker
+        .live_cells_by_lock_script(&lock_script(2))
+        .is_empty());
+    let committed_output = tracker.live_cell(&out_point(&committed, 0)).expect("live");
+    assert_eq!(committed_output.transaction_info.unwrap().block_number, 1);
+
+    // The replaced transaction fed again is the latest and wins back
+    tracker.track_transaction(&submitted);
+    assert!(tracker.live_cell(&out_point(&committed, 0)).is_none());
+    assert!(tracker.live_cell(&out_point(&submitted, 0)).is_some());
+    assert!(tracker.live_cell(&cell1.out_point).is_none());
+}
@@ -0,0 +1,69 @@
+use crate::LogWatcher;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+fn append(path: &Path, content: &str) {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .expect("open log file");
+    file.write_all(content.as_bytes()).expect("write log file");
+}
+
+#[test]
+fn test_log_watcher_reads_complete_lines() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let log_path = dir.path().join("run.log");
+    let mut watcher = LogWatcher::new(&log_path);
+    // The log file does not exist yet
+    assert!(watcher.read_lines().is_empty());
+
+    append(&log_path, "line 1\nline");
+    assert_eq!(watcher.read_lines(), vec!["line 1"]);
+    // The partial line is held until it completes
+    assert!(watcher.read_lines().is_empty());
+    append(&log_path, " 2\nline 3\n");
+    assert_eq!(watcher.read_lines(), vec!["line 2", "line 3"]);
+    assert!(watcher.read_lines().is_empty());
+}
+
+#[test]
+fn test_log_watcher_from_end_skips_old_lines() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let log_path = dir.path().join("run.log");
+    append(&log_path, "old ERROR line\n");
+    let mut watcher = LogWatcher::from_end(&log_path);
+    assert!(watcher.read_lines().is_empty());
+    watcher.assert_no_log("ERROR");
+
+    append(&log_path, "new line\n");
+    assert_eq!(watcher.read_lines(), vec!["new line"]);
+}
+
+#[test]
+fn test_log_watcher_expect_log_keeps_pending_lines() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let log_path = dir.path().join("run.log");
+    let mut watcher = LogWatcher::new(&log_path);
+    append(&log_path, "first\nmatched 1\nsecond\nmatched 2\nthird\n");
+
+    let timeout = Duration::from_millis(100);
+    assert_eq!(watcher.expect_log("matched", timeout).unwrap(), "matched 1");
+    // The lines after the matched one are carried over
+    assert_eq!(watcher.expect_log("matched", timeout).unwrap(), "matched 2");
+    assert_eq!(watcher.read_lines(), vec!["third"]);
+    assert!(watcher.expect_log("matched", timeout).is_err());
+}
+
+#[test]
+#[should_panic(expected = "unexpected log")]
+fn test_log_watcher_assert_no_log() {
+    let dir = tempfile::tempdir().expect("create tempdir");
+    let log_path = dir.path().join("run.log");
+    let mut watcher = LogWatcher::new(&log_path);
+    append(&log_path, "thread 'main' panicked at 'boom'\n");
+    watcher.assert_no_log("panicked at");
+}
@@ -0,0 +1,56 @@
+mod block_assembler;
+mod indexer_worker;
+mod live_cell_tracker;
+mod log_watcher;
+mod proxy;
+mod rng;
+mod scenario;
+mod since;
+mod tx_builder;
+mod util;
+
+use ckb_jsonrpc_types::Consensus;
+use ckb_types::core::EpochNumber;
+use serde_json::json;
+
+pub(crate) const DAO_TYPE_HASH: &str =
+    "0x82d76d1b75fe2fd9a27dfbaa65a039221a380d76c926f378d3f81cf3e7e13f2e";
+
+// A dummy-difficulty consensus, with the hardfork features activated at the given epochs
+pub(crate) fn consensus(
+    primary_epoch_reward: u64,
+    secondary_epoch_reward: u64,
+    hardfork_features: &[(&str, EpochNumber)],
+) -> Consensus {
+    let zero_hash = format!("0x{}", "00".repeat(32));
+    let hardfork_features = hardfork_features
+        .iter()
+        .map(|(rfc, epoch_number)| json!({ "rfc": rfc, "epoch_number": format!("{:#x}", epoch_number) }))
+        .collect::<Vec<_>>();
+    serde_json::from_value(json!({
+        "id": "ckb_testkit",
+        "genesis_hash": zero_hash,
+        "dao_type_hash": DAO_TYPE_HASH,
+        "secp256k1_blake160_sighash_all_type_hash": null,
+        "secp256k1_blake160_multisig_all_type_hash": null,
+        "initial_primary_epoch_reward": format!("{:#x}", primary_epoch_reward),
+        "secondary_epoch_reward": format!("{:#x}", secondary_epoch_reward),
+        "max_uncles_num": "0x2",
+        "orphan_rate_target": { "numer": "0x1", "denom": "0x28" },
+        "epoch_duration_target": "0x3840",
+        "tx_proposal_window": { "closest": "0x2", "farthest": "0xa" },
+        "proposer_reward_ratio": { "numer": "0x4", "denom": "0xa" },
+        "cellbase_maturity": "0x0",
+        "median_time_block_count": "0x25",
+        "max_block_cycles": "0x2540be400",
+        "max_block_bytes": "0x91c08",
+        "block_version": "0x0",
+        "tx_version": "0x0",
+        "type_id_code_hash": "0x00000000000000000000000000000000000000000000000000545950455f4944",
+        "max_block_proposals_limit": "0x5dc",
+        "primary_epoch_reward_halving_interval": "0x2238",
+        "permanent_difficulty_in_dummy": true,
+        "hardfork_features": hardfork_features,
+    }))
+    .expect("valid consensus")
+}
@@ -0,0 +1,140 @@
+use crate::Proxy;
+use std::io::{ErrorKind, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const MESSAGE: &[u8] = b"ping";
+
+// Start a TCP server which echoes back everything it receives
+fn echo_server() -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind echo server");
+    let address = listener.local_addr().expect("echo server address");
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => break,
+            };
+            thread::spawn(move || {
+                let mut buffer = [0u8; 1024];
+                while let Ok(n) = stream.read(&mut buffer) {
+                    if n == 0 || stream.write_all(&buffer[..n]).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    address
+}
+
+fn connect(proxy: &Proxy) -> TcpStream {
+    let stream = TcpStream::connect(proxy.listen_address()).expect("connect proxy");
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .expect("set read timeout");
+    stream
+}
+
+// Send `MESSAGE` and return the echoed bytes and the round-trip duration
+fn round_trip(stream: &mut TcpStream) -> (Vec<u8>, Duration) {
+    let start = Instant::now();
+    stream.write_all(MESSAGE).expect("write proxy");
+    let mut echoed = vec![0u8; MESSAGE.len()];
+    stream.read_exact(&mut echoed).expect("read proxy");
+    (echoed, start.elapsed())
+}
+
+#[test]
+fn test_proxy_forwards() {
+    let proxy = Proxy::start_to("echo", echo_server());
+    let mut stream = connect(&proxy);
+    let (echoed, _) = round_trip(&mut stream);
+    assert_eq!(echoed, MESSAGE);
+}
+
+#[test]
+fn test_proxy_latency() {
+    let proxy = Proxy::start_to("echo", echo_server());
+    let latency = Duration::from_millis(200);
+    proxy.set_latency(latency);
+    let mut stream = connect(&proxy);
+    let (echoed, elapsed) = round_trip(&mut stream);
+    assert_eq!(echoed, MESSAGE);
+    // The latency applies to both directions
+    assert!(elapsed >= latency * 2, "elapsed: {:?}", elapsed);
+}
+
+#[test]
+fn test_proxy_loss_delays_without_dropping_bytes() {
+    let proxy = Proxy::start_to("echo", echo_server());
+    let loss_delay = Duration::from_millis(200);
+    proxy.set_loss(1.0, loss_delay);
+    let mut stream = connect(&proxy);
+    for _ in 0..3 {
+        let (echoed, elapsed) = round_trip(&mut stream);
+        assert_eq!(echoed, MESSAGE);
+        assert!(elapsed >= loss_delay * 2, "elapsed: {:?}", elapsed);
+    }
+
+    proxy.set_loss(0.0, loss_delay);
+    let (echoed, elapsed) = round_trip(&mut stream);
+    assert_eq!(echoed, MESSAGE);
+    assert!(elapsed < loss_delay, "elapsed: {:?}", elapsed);
+}
+
+#[test]
+fn test_proxy_half_open_pauses_reads() {
+    let proxy = Proxy::start_to("echo", echo_server());
+    let mut stream = connect(&proxy);
+    let (echoed, _) = round_trip(&mut stream);
+    assert_eq!(echoed, MESSAGE);
+
+    proxy.set_half_open(true);
+    stream
+        .set_read_timeout(Some(Duration::from_millis(300)))
+        .expect("set read timeout");
+    stream.write_all(MESSAGE).expect("write proxy");
+    let mut echoed = vec![0u8; MESSAGE.len()];
+    let err = stream
+        .read(&mut echoed)
+        .expect_err("no data is forwarded while half-open");
+    assert!(
+        matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut),
+        "error: {}",
+        err
+    );
+
+    // The held data is forwarded once resumed, over the same connection
+    proxy.set_half_open(false);
+    stream
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .expect("set read timeout");
+    stream.read_exact(&mut echoed).expect("read proxy");
+    assert_eq!(echoed, MESSAGE);
+}
+
+#[test]
+fn test_proxy_partition_closes_and_refuses() {
+    let proxy = Proxy::start_to("echo", echo_server());
+    let mut stream = connect(&proxy);
+    let (echoed, _) = round_trip(&mut stream);
+    assert_eq!(echoed, MESSAGE);
+
+    proxy.partition();
+    assert!(proxy.is_partitioned());
+    // The forwarded connection is closed
+    let mut buffer = [0u8; 16];
+    assert_eq!(stream.read(&mut buffer).expect("read proxy"), 0);
+    // New connections are accepted then closed right away
+    let mut refused = connect(&proxy);
+    let _ = refused.write_all(MESSAGE);
+    assert!(matches!(refused.read(&mut buffer), Ok(0) | Err(_)));
+
+    proxy.heal();
+    assert!(!proxy.is_partitioned());
+    let mut stream = connect(&proxy);
+    let (echoed, _) = round_trip(&mut stream);
+    assert_eq!(echoed, MESSAGE);
+}
@@ -0,0 +1,46 @@
+use crate::util::rng::XorShift;
+
+#[test]
+fn test_xorshift_is_deterministic() {
+    let mut rng_a = XorShift::new(42);
+    let mut rng_b = XorShift::new(42);
+    for _ in 0..100 {
+        assert_eq!(rng_a.next_u64(), rng_b.next_u64());
+    }
+}
+
+#[test]
+fn test_xorshift_close_seeds_diverge() {
+    let first = |seed| XorShift::new(seed).next_u64();
+    assert_ne!(first(0), first(1));
+    assert_ne!(first(1), first(2));
+}
+
+#[test]
+fn test_xorshift_never_stuck_at_zero() {
+    // The seed mapped to the zero state by xoring with the golden ratio
+    for seed in [0, 0x9E37_79B9_7F4A_7C15, u64::MAX] {
+        let mut rng = XorShift::new(seed);
+        let values = (0..10).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        assert!(values.iter().all(|value| *value != 0), "seed {:#x}", seed);
+    }
+}
+
+#[test]
+fn test_xorshift_ranges() {
+    let mut rng = XorShift::new(7);
+    for _ in 0..1000 {
+        let f = rng.next_f64();
+        assert!((0.0..1.0).contains(&f), "{}", f);
+        assert!(rng.gen_range(3) < 3);
+    }
+}
+
+#[test]
+fn test_xorshift_shuffle_is_permutation() {
+    let mut items = (0..50).collect::<Vec<_>>();
+    XorShift::new(3).shuffle(&mut items);
+    assert_ne!(items, (0..50).collect::<Vec<_>>());
+    items.sort_unstable();
+    assert_eq!(items, (0..50).collect::<Vec<_>>());
+}
@@ -0,0 +1,122 @@
+use crate::{BuildInstruction, Scenario};
+use ckb_types::core::{BlockNumber, TransactionView};
+use ckb_types::prelude::*;
+
+const BASE: BlockNumber = 100;
+const CLOSEST: BlockNumber = 2;
+const FARTHEST: BlockNumber = 10;
+
+fn transaction(version: u32) -> TransactionView {
+    TransactionView::new_advanced_builder()
+        .version(version.pack())
+        .build()
+}
+
+// (template number, instruction kind) of every planned block
+fn plan(scenario: &Scenario) -> Vec<Vec<(BlockNumber, &'static str)>> {
+    scenario
+        .plan(BASE, CLOSEST, FARTHEST)
+        .expect("valid scenario")
+        .iter()
+        .map(|block| {
+            block
+                .instructions
+                .iter()
+                .map(|instruction| {
+                    let kind = match instruction {
+                        BuildInstruction::Propose { .. } => "propose",
+                        BuildInstruction::Commit { .. } => "commit",
+                        BuildInstruction::HeaderTimestamp { .. } => "timestamp",
+                        BuildInstruction::ProcessWithoutVerify { .. } => "process_without_verify",
+                        _ => "other",
+                    };
+                    (instruction.template_number(), kind)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_scenario_mine_builds_n_blocks() {
+    assert_eq!(plan(&Scenario::new().mine(3)).len(), 3);
+    assert_eq!(plan(&Scenario::new().next_block()).len(), 1);
+    assert_eq!(plan(&Scenario::new()).len(), 0);
+}
+
+#[test]
+fn test_scenario_steps_after_mine_apply_to_the_next_block() {
+    let scenario = Scenario::new()
+        .mine(2)
+        .timestamp(42)
+        .process_without_verify();
+    assert_eq!(
+        plan(&scenario),
+        vec![
+            vec![],
+            vec![],
+            vec![
+                (BASE + 3, "timestamp"),
+                (BASE + 3, "process_without_verify")
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_scenario_commit_moves_into_proposal_window() {
+    let tx = transaction(1);
+    let scenario = Scenario::new().propose(&tx).commit(&tx).mine(2);
+    assert_eq!(
+        plan(&scenario),
+        vec![
+            vec![(BASE + 1, "propose")],
+            vec![],
+            vec![(BASE + 1 + CLOSEST, "commit")],
+            vec![],
+        ]
+    );
+
+    // Already in the window, the commit stays in the current block
+    let scenario = Scenario::new().propose(&tx).mine(5).commit(&tx);
+    assert_eq!(plan(&scenario)[5], vec![(BASE + 6, "commit")]);
+}
+
+#[test]
+fn test_scenario_send_transaction() {
+    let tx_a = transaction(1);
+    let tx_b = transaction(2);
+    let planned = Scenario::new()
+        .send_transaction(&tx_a)
+        .next_block()
+        .send_transaction(&tx_b)
+        .plan(BASE, CLOSEST, FARTHEST)
+        .expect("valid scenario");
+    assert_eq!(planned.len(), 2);
+    assert_eq!(planned[0].transactions.len(), 1);
+    assert_eq!(planned[0].transactions[0].1.hash(), tx_a.hash());
+    assert_eq!(planned[1].transactions[0].0, 2);
+    assert_eq!(planned[1].transactions[0].1.hash(), tx_b.hash());
+}
+
+#[test]
+fn test_scenario_invalid_commit() {
+    let tx = transaction(1);
+    let err = Scenario::new()
+        .commit(&tx)
+        .plan(BASE, CLOSEST, FARTHEST)
+        .err()
+        .expect("commit without proposal");
+    assert!(err.contains("step #0"), "{}", err);
+    assert!(err.contains("is not proposed"), "{}", err);
+
+    let err = Scenario::new()
+        .propose(&tx)
+        .mine(FARTHEST + 1)
+        .commit(&tx)
+        .plan(BASE, CLOSEST, FARTHEST)
+        .err()
+        .expect("commit out of the proposal window");
+    assert!(err.contains("step #2"), "{}", err);
+    assert!(err.contains("out of the proposal window"), "{}", err);
+}
@@ -0,0 +1,133 @@
+use super::consensus;
+use crate::util::{
+    since_from_absolute_block_number, since_from_absolute_epoch_number_with_fraction,
+    since_from_relative_timestamp, Since, SinceBlock, SinceError,
+};
+use ckb_types::core::EpochNumberWithFraction;
+
+fn block(number: u64, epoch: EpochNumberWithFraction, timestamp: u64) -> SinceBlock {
+    SinceBlock {
+        number,
+        epoch,
+        timestamp,
+        parent_median_time: timestamp,
+    }
+}
+
+#[test]
+fn test_since_round_trips_u64() {
+    let epoch = EpochNumberWithFraction::new(10, 3, 100);
+    for since in [
+        Since::absolute_block_number(100),
+        Since::relative_block_number(100),
+        Since::absolute_epoch(epoch),
+        Since::relative_epoch(epoch),
+        Since::absolute_timestamp(1_600_000_000),
+        Since::relative_timestamp(60),
+    ] {
+        assert_eq!(Since::from_u64(since.to_u64()), Ok(since), "{}", since);
+    }
+    assert_eq!(
+        Since::absolute_block_number(100).to_u64(),
+        since_from_absolute_block_number(100)
+    );
+    assert_eq!(
+        Since::absolute_epoch(epoch).to_u64(),
+        since_from_absolute_epoch_number_with_fraction(epoch)
+    );
+    assert_eq!(
+        Since::relative_timestamp(60).to_u64(),
+        since_from_relative_timestamp(60)
+    );
+}
+
+#[test]
+fn test_since_rejects_reserved_bits_and_unknown_metric() {
+    let reserved = Since::absolute_block_number(100).to_u64() | 0x0100_0000_0000_0000;
+    assert!(matches!(
+        Since::from_u64(reserved),
+        Err(SinceError::InvalidSince(_))
+    ));
+    let unknown_metric = 0x6000_0000_0000_0064;
+    assert!(matches!(
+        Since::from_u64(unknown_metric),
+        Err(SinceError::InvalidSince(_))
+    ));
+}
+
+#[test]
+fn test_since_epoch_fraction_validation() {
+    assert!(Since::absolute_epoch(EpochNumberWithFraction::new(1, 1, 2)).is_well_formed());
+    assert!(
+        Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(1, 0, 0)).is_well_formed()
+    );
+    let malformed = Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(1, 5, 3));
+    assert!(!malformed.is_well_formed());
+
+    let consensus = consensus(0, 0, &[("0030", 10)]);
+    let input_block = block(0, EpochNumberWithFraction::new(0, 0, 100), 0);
+    let before = block(900, EpochNumberWithFraction::new(9, 0, 100), 0);
+    let after = block(1000, EpochNumberWithFraction::new(10, 0, 100), 0);
+    assert!(matches!(
+        malformed.verify(&consensus, &input_block, &before),
+        Err(SinceError::Undetermined(_))
+    ));
+    assert!(matches!(
+        malformed.verify(&consensus, &input_block, &after),
+        Err(SinceError::InvalidSince(_))
+    ));
+}
+
+#[test]
+fn test_since_verify_block_number_and_epoch() {
+    let consensus = consensus(0, 0, &[]);
+    let input_block = block(100, EpochNumberWithFraction::new(1, 0, 100), 0);
+    let committing_block = block(150, EpochNumberWithFraction::new(1, 50, 100), 0);
+
+    let verify = |since: Since| since.verify(&consensus, &input_block, &committing_block);
+    assert_eq!(verify(Since::absolute_block_number(150)), Ok(()));
+    assert_eq!(
+        verify(Since::absolute_block_number(151)),
+        Err(SinceError::Immature)
+    );
+    assert_eq!(verify(Since::relative_block_number(50)), Ok(()));
+    assert_eq!(
+        verify(Since::relative_block_number(51)),
+        Err(SinceError::Immature)
+    );
+    assert_eq!(
+        verify(Since::absolute_epoch(EpochNumberWithFraction::new(1, 1, 2))),
+        Ok(())
+    );
+    assert_eq!(
+        verify(Since::relative_epoch(EpochNumberWithFraction::new(
+            0, 51, 100
+        ))),
+        Err(SinceError::Immature)
+    );
+}
+
+#[test]
+fn test_since_verify_relative_timestamp_before_and_after_rfc0028() {
+    let consensus = consensus(0, 0, &[("0028", 10)]);
+    // The input block timestamp is ahead of its parent median time
+    let input_block = SinceBlock {
+        number: 100,
+        epoch: EpochNumberWithFraction::new(1, 0, 100),
+        timestamp: 10_000,
+        parent_median_time: 5_000,
+    };
+    let since = Since::relative_timestamp(10);
+
+    // Before RFC0028, counted from the median time, 5_000 + 10_000 <= 16_000
+    let before = block(900, EpochNumberWithFraction::new(9, 0, 100), 16_000);
+    assert_eq!(since.verify(&consensus, &input_block, &before), Ok(()));
+    // After RFC0028, counted from the block timestamp, 10_000 + 10_000 > 16_000
+    let after = block(1000, EpochNumberWithFraction::new(10, 0, 100), 16_000);
+    assert_eq!(
+        since.verify(&consensus, &input_block, &after),
+        Err(SinceError::Immature)
+    );
+    let after = block(1000, EpochNumberWithFraction::new(10, 0, 100), 20_000);
+    assert_eq!(since.verify(&consensus, &input_block, &after), Ok(()));
+}
@@ -0,0 +1,310 @@
+use crate::{TxBuilder, User, DEFAULT_FEE_RATE, SYSTEM_CELL_ALWAYS_SUCCESS_INDEX};
+use ckb_crypto::secp::{Privkey, Pubkey, Signature};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::{CellMeta, CellMetaBuilder},
+        BlockBuilder, BlockView, Capacity, FeeRate, TransactionBuilder, TransactionView,
+    },
+    h256,
+    packed::{CellInput, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+    H256,
+};
+
+// A genesis block with the always-success cell typed at `SYSTEM_CELL_ALWAYS_SUCCESS_INDEX` and
+// a dep-group transaction
+fn genesis_block() -> BlockView {
+    let type_id = Script::new_builder()
+        .args(Bytes::from(vec![1u8]).pack())
+        .build();
+    let cellbase = TransactionBuilder::default()
+        .input(CellInput::new_cellbase_input(0))
+        .outputs((0..=SYSTEM_CELL_ALWAYS_SUCCESS_INDEX).map(|index| {
+            let type_ = if index == SYSTEM_CELL_ALWAYS_SUCCESS_INDEX {
+                Some(type_id.clone())
+            } else {
+                None
+            };
+            CellOutput::new_builder().type_(type_.pack()).build()
+        }))
+        .outputs_data((0..=SYSTEM_CELL_ALWAYS_SUCCESS_INDEX).map(|_| Bytes::new().pack()))
+        .build();
+    let dep_group = TransactionBuilder::default()
+        .outputs(vec![CellOutput::default(); 2])
+        .outputs_data(vec![Bytes::new().pack(); 2])
+        .build();
+    BlockBuilder::default()
+        .transaction(cellbase)
+        .transaction(dep_group)
+        .build()
+}
+
+fn cell(index: u32, lock: Script, capacity: u64) -> CellMeta {
+    let output = CellOutput::new_builder()
+        .lock(lock)
+        .capacity(Capacity::shannons(capacity).pack())
+        .build();
+    CellMetaBuilder::from_cell_output(output, Bytes::new())
+        .out_point(OutPoint::new(h256!("0x1").pack(), index))
+        .build()
+}
+
+fn ckb(n: u64) -> u64 {
+    Capacity::shannons(n * 100_000_000).as_u64()
+}
+
+fn output(capacity: u64) -> CellOutput {
+    CellOutput::new_builder()
+        .capacity(Capacity::shannons(capacity).pack())
+        .build()
+}
+
+fn capacity_of(outputs: impl Iterator<Item = CellOutput>) -> u64 {
+    outputs
+        .map(|output| Unpack::<Capacity>::unpack(&output.capacity()).as_u64())
+        .sum()
+}
+
+fn expected_fee(tx: &TransactionView) -> u64 {
+    FeeRate::from_u64(DEFAULT_FEE_RATE)
+        .fee(tx.data().serialized_size_in_block())
+        .as_u64()
+}
+
+#[test]
+fn test_tx_builder_balances_with_change() {
+    let user = User::new_always_success(genesis_block());
+    let cells = vec![cell(0, user.lock_script(), ckb(100))];
+    let tx = TxBuilder::new(&user)
+        .cells(cells)
+        .output(output(ckb(50)), Bytes::new())
+        .build()
+        .expect("build");
+    assert_eq!(tx.inputs().len(), 1);
+    assert_eq!(tx.outputs().len(), 2);
+    let change = tx.output(1).unwrap();
+    assert_eq!(change.lock(), user.lock_script());
+    let fee = ckb(100) - capacity_of(tx.outputs().into_iter());
+    assert_eq!(fee, expected_fee(&tx));
+}
+
+#[test]
+fn test_tx_builder_collects_candidates_in_order() {
+    let user = User::new_always_success(genesis_block());
+    let cells = (0..5)
+        .map(|index| cell(index, user.lock_script(), ckb(100)))
+        .collect::<Vec<_>>();
+    let tx = TxBuilder::new(&user)
+        .cells(cells.clone())
+        .output(output(ckb(250)), Bytes::new())
+        .build()
+        .expect("build");
+    let inputs = tx
+        .input_pts_iter()
+        .map(|out_point| out_point.index().unpack())
+        .collect::<Vec<u32>>();
+    assert_eq!(inputs, vec![0, 1, 2]);
+
+    // Fixed inputs come first, and are not collected again
+    let tx = TxBuilder::new(&user)
+        .input(cells[3].clone(), 0)
+        .cells(cells)
+        .output(output(ckb(150)), Bytes::new())
+        .build()
+        .expect("build");
+    let inputs = tx
+        .input_pts_iter()
+        .map(|out_point| out_point.index().unpack())
+        .collect::<Vec<u32>>();
+    assert_eq!(inputs, vec![3, 0]);
+}
+
+#[test]
+fn test_tx_builder_surplus_goes_to_fee() {
+    let user = User::new_always_success(genesis_block());
+    // The remainder 10 CKB can afford the fee but not a change output
+    let cells = vec![cell(0, user.lock_script(), ckb(110))];
+    let tx = TxBuilder::new(&user)
+        .cells(cells)
+        .output(output(ckb(100)), Bytes::new())
+        .build()
+        .expect("build");
+    assert_eq!(tx.outputs().len(), 1);
+    assert!(ckb(10) > expected_fee(&tx));
+
+    // No remainder to pay the fee, collect more
+    let cells = vec![
+        cell(0, user.lock_script(), ckb(100)),
+        cell(1, user.lock_script(), ckb(100)),
+    ];
+    let tx = TxBuilder::new(&user)
+        .cells(cells)
+        .output(output(ckb(100)), Bytes::new())
+        .build()
+        .expect("build");
+    assert_eq!(tx.inputs().len(), 2);
+    assert_eq!(tx.outputs().len(), 2);
+}
+
+#[test]
+fn test_tx_builder_errors() {
+    let user = User::new_always_success(genesis_block());
+    let cells = vec![cell(0, user.lock_script(), ckb(100))];
+    let result = TxBuilder::new(&user)
+        .cells(cells.clone())
+        .output(output(ckb(100)), Bytes::new())
+        .build();
+    assert!(
+        matches!(&result, Err(err) if err.contains("insufficient capacity")),
+        "{:?}",
+        result
+    );
+
+    let result = TxBuilder::new(&user)
+        .cells(cells)
+        .output(output(ckb(1)), Bytes::new())
+        .build();
+    assert!(
+        matches!(&result, Err(err) if err.contains("less than its occupied capacity")),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn test_tx_builder_co_signers() {
+    let genesis_block = genesis_block();
+    let payer = User::new(genesis_block.clone(), Some(Privkey::from_slice(&[1u8; 32])));
+    let other = User::new(genesis_block, Some(Privkey::from_slice(&[2u8; 32])));
+    let tx = TxBuilder::new(&payer)
+        .signer(&other)
+        .input(cell(0, other.lock_script(), ckb(100)), 0)
+        .input(cell(1, payer.lock_script(), ckb(100)), 0)
+        .output(output(ckb(199)), Bytes::new())
+        .build()
+        .expect("build");
+    assert_eq!(tx.outputs().len(), 1);
+    for index in 0..2 {
+        let witness = WitnessArgs::from_slice(&tx.witnesses().get(index).unwrap().raw_data())
+            .expect("WitnessArgs");
+        assert_eq!(
+            witness.lock().to_opt().map(|lock| lock.raw_data().len()),
+            Some(65),
+            "witness {} should be signed",
+            index
+        );
+    }
+}
+
+// The sighash-all message of the witness group `group`, computed from the unsigned witnesses of
+// `tx`
+fn sighash_all_message(tx: &TransactionView, group: &[usize]) -> H256 {
+    let witnesses = tx
+        .witnesses()
+        .into_iter()
+        .map(|witness| witness.raw_data())
+        .collect::<Vec<_>>();
+    let mut blake2b = ckb_hash::new_blake2b();
+    blake2b.update(&tx.hash().raw_data());
+    let mut update_witness = |witness: &[u8]| {
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(witness);
+    };
+    let first = WitnessArgs::from_slice(&witnesses[group[0]])
+        .unwrap_or_default()
+        .as_builder()
+        .lock(Some(Bytes::from(vec![0u8; 65])).pack())
+        .build();
+    update_witness(&first.as_bytes());
+    for index in group.iter().skip(1) {
+        update_witness(&witnesses[*index]);
+    }
+    for witness in witnesses.iter().skip(tx.inputs().len()) {
+        update_witness(witness);
+    }
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+    H256::from(message)
+}
+
+fn recover_pubkey(witness: &WitnessArgs, message: &H256) -> Pubkey {
+    let lock = witness.lock().to_opt().expect("signed lock").raw_data();
+    Signature::from_slice(&lock)
+        .expect("signature")
+        .recover(message)
+        .expect("recover pubkey")
+}
+
+#[test]
+fn test_user_sign_interleaved_witness_group() {
+    let genesis_block = genesis_block();
+    let user_a = User::new(genesis_block.clone(), Some(Privkey::from_slice(&[1u8; 32])));
+    let user_b = User::new(genesis_block, Some(Privkey::from_slice(&[2u8; 32])));
+    let inputs = [
+        cell(0, user_a.lock_script(), ckb(100)),
+        cell(1, user_b.lock_script(), ckb(100)),
+        cell(2, user_a.lock_script(), ckb(100)),
+    ];
+    let first_witness = WitnessArgs::new_builder()
+        .input_type(Some(Bytes::from(vec![1u8; 3])).pack())
+        .output_type(Some(Bytes::from(vec![2u8; 5])).pack())
+        .build();
+    let extra_witness = WitnessArgs::new_builder()
+        .input_type(Some(Bytes::from(vec![3u8; 7])).pack())
+        .build();
+    let tx = TransactionBuilder::default()
+        .inputs(
+            inputs
+                .iter()
+                .map(|input| CellInput::new(input.out_point.clone(), 0)),
+        )
+        .output(output(ckb(299)))
+        .output_data(Bytes::new().pack())
+        // inputs `[A, B, A]`, plus a witness beyond the inputs
+        .witness(first_witness.as_bytes().pack())
+        .witness(Bytes::new().pack())
+        .witness(Bytes::new().pack())
+        .witness(extra_witness.as_bytes().pack())
+        .build();
+    let input_outputs = inputs
+        .iter()
+        .map(|input| input.cell_output.clone())
+        .collect::<Vec<_>>();
+    let signed_tx = user_a.sign(&tx, &input_outputs).expect("sign");
+    let signed_tx = user_b.sign(&signed_tx, &input_outputs).expect("sign");
+    assert_eq!(signed_tx.hash(), tx.hash());
+    let signed_witnesses = signed_tx.witnesses();
+    let signed_witness = |index: usize| {
+        WitnessArgs::from_slice(&signed_witnesses.get(index).unwrap().raw_data())
+            .expect("WitnessArgs")
+    };
+
+    // Group A is `[0, 2]`, signed in witness 0 with its `input_type` and `output_type` kept
+    let witness_a = signed_witness(0);
+    assert_eq!(
+        witness_a.input_type().as_bytes(),
+        first_witness.input_type().as_bytes()
+    );
+    assert_eq!(
+        witness_a.output_type().as_bytes(),
+        first_witness.output_type().as_bytes()
+    );
+    let message_a = sighash_all_message(&tx, &[0, 2]);
+    assert_eq!(
+        recover_pubkey(&witness_a, &message_a),
+        user_a.single_secp256k1_pubkey()
+    );
+
+    // Group B is `[1]`, signed in witness 1
+    let message_b = sighash_all_message(&tx, &[1]);
+    assert_ne!(message_a, message_b);
+    assert_eq!(
+        recover_pubkey(&signed_witness(1), &message_b),
+        user_b.single_secp256k1_pubkey()
+    );
+
+    // The other witnesses of the group and the ones beyond inputs are untouched
+    assert!(signed_witnesses.get(2).unwrap().is_empty());
+    assert_eq!(signed_witness(3).as_bytes(), extra_witness.as_bytes());
+}
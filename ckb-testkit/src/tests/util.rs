@@ -0,0 +1,118 @@
+use crate::util::{dao_withdraw_since, since_from_absolute_epoch_number_with_fraction, Waiter};
+use ckb_types::{
+    core::{EpochNumberWithFraction, HeaderBuilder, HeaderView},
+    prelude::*,
+};
+use std::time::Duration;
+
+fn header(number: u64, index: u64, length: u64) -> HeaderView {
+    HeaderBuilder::default()
+        .epoch(EpochNumberWithFraction::new(number, index, length).pack())
+        .build()
+}
+
+fn since(number: u64, index: u64, length: u64) -> u64 {
+    since_from_absolute_epoch_number_with_fraction(EpochNumberWithFraction::new(
+        number, index, length,
+    ))
+}
+
+#[test]
+fn test_dao_withdraw_since_locks_at_least_one_period() {
+    let deposit = header(5, 100, 1000);
+    assert_eq!(
+        dao_withdraw_since(&deposit, &header(5, 100, 1000)),
+        since(185, 100, 1000)
+    );
+    assert_eq!(
+        dao_withdraw_since(&deposit, &header(5, 200, 1000)),
+        since(185, 100, 1000)
+    );
+}
+
+#[test]
+fn test_dao_withdraw_since_rounds_up_to_periods() {
+    let deposit = header(5, 100, 1000);
+    // Exactly one period
+    assert_eq!(
+        dao_withdraw_since(&deposit, &header(185, 100, 1000)),
+        since(185, 100, 1000)
+    );
+    // A bit more than one period
+    assert_eq!(
+        dao_withdraw_since(&deposit, &header(185, 101, 1000)),
+        since(365, 100, 1000)
+    );
+    assert_eq!(
+        dao_withdraw_since(&deposit, &header(365, 100, 1000)),
+        since(365, 100, 1000)
+    );
+}
+
+#[test]
+fn test_dao_withdraw_since_compares_fractions_of_different_lengths() {
+    let deposit = header(5, 1, 2);
+    // 1/4 < 1/2
+    assert_eq!(
+        dao_withdraw_since(&deposit, &header(185, 1, 4)),
+        since(185, 1, 2)
+    );
+    // 3/4 > 1/2
+    assert_eq!(
+        dao_withdraw_since(&deposit, &header(185, 3, 4)),
+        since(365, 1, 2)
+    );
+}
+
+fn fixed_interval_waiter(timeout_millis: u64) -> Waiter {
+    Waiter::new("test", Duration::from_millis(timeout_millis))
+        .initial_interval(Duration::from_millis(10))
+        .multiplier(1)
+}
+
+#[test]
+fn test_waiter_wait_for_progress_restarts_timeout() {
+    let waiter = fixed_interval_waiter(50);
+    // Reaching the target takes longer than the timeout, but every attempt makes progress
+    let target = 2 * (waiter.timeout().as_millis() as u32 / 10 + 1);
+    let mut count = 0;
+    let result = waiter.wait_for_progress(
+        || {
+            count += 1;
+            if count >= target {
+                Ok(count)
+            } else {
+                Err(count)
+            }
+        },
+        |prev, curr| curr > prev,
+    );
+    assert_eq!(result.unwrap(), target);
+
+    let mut count = 0;
+    let err = waiter
+        .wait_for(|| -> Result<(), _> {
+            count += 1;
+            Err(count)
+        })
+        .unwrap_err();
+    assert!(err.last_state.unwrap() < target);
+}
+
+#[test]
+fn test_waiter_wait_for_progress_times_out_when_stalled() {
+    let waiter = fixed_interval_waiter(50);
+    let mut count = 0;
+    let err = waiter
+        .wait_for_progress(
+            || -> Result<(), _> {
+                count += 1;
+                // Progress until 3, then stall
+                Err(count.min(3))
+            },
+            |prev, curr| curr > prev,
+        )
+        .unwrap_err();
+    assert_eq!(err.last_state, Some(3));
+    assert!(err.elapsed >= waiter.timeout());
+}
@@ -0,0 +1,249 @@
+use crate::User;
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, Capacity, FeeRate, TransactionBuilder, TransactionView},
+    packed::{Byte32, CellDep, CellInput, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+};
+use std::collections::HashSet;
+
+/// The default fee rate, in shannons per KB, the same as the default `min_fee_rate` of ckb.
+pub const DEFAULT_FEE_RATE: u64 = 1000;
+
+/// TxBuilder builds a balanced and signed transaction paid by `payer`.
+///
+/// The builder takes the fixed inputs first, then collects inputs from the candidate cells
+/// until the inputs cover the outputs, the fee and the change output. The fee is calculated
+/// from the serialized size of the signed transaction in block and the fee rate. When the
+/// remainder cannot afford a change output, the whole remainder goes to the fee.
+///
+/// ```ignore
+/// let cells = user.get_spendable_single_secp256k1_cells(node);
+/// let tx = TxBuilder::new(&user)
+///     .cells(cells)
+///     .output(output, Bytes::new())
+///     .build()?;
+/// node.submit_transaction(&tx);
+/// ```
+pub struct TxBuilder<'a> {
+    payer: &'a User,
+    signers: Vec<&'a User>,
+    candidates: Vec<CellMeta>,
+    inputs: Vec<(CellMeta, u64, Capacity)>,
+    witnesses: Vec<(usize, WitnessArgs)>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    cell_deps: Vec<CellDep>,
+    header_deps: Vec<Byte32>,
+    fee_rate: u64,
+    change_lock: Option<Script>,
+}
+
+impl<'a> TxBuilder<'a> {
+    pub fn new(payer: &'a User) -> Self {
+        Self {
+            payer,
+            signers: Vec::new(),
+            candidates: Vec::new(),
+            inputs: Vec::new(),
+            witnesses: Vec::new(),
+            outputs: Vec::new(),
+            cell_deps: payer.cell_deps(),
+            header_deps: Vec::new(),
+            fee_rate: DEFAULT_FEE_RATE,
+            change_lock: None,
+        }
+    }
+
+    /// The candidate cells owned by the payer, from which inputs are collected in order.
+    pub fn cells(mut self, cells: Vec<CellMeta>) -> Self {
+        self.candidates.extend(cells);
+        self
+    }
+
+    /// Consume `cell` with `since`, no matter whether it is needed to balance the transaction.
+    /// Fixed inputs precede the collected ones.
+    pub fn input(mut self, cell: CellMeta, since: u64) -> Self {
+        let capacity = cell.capacity();
+        self.inputs.push((cell, since, capacity));
+        self
+    }
+
+    /// Like `input`, but `cell` provides `capacity` instead of its own capacity, e.g. the
+    /// maximum withdraw capacity of a Nervos DAO withdrawing cell.
+    pub fn input_with_capacity(mut self, cell: CellMeta, since: u64, capacity: Capacity) -> Self {
+        self.inputs.push((cell, since, capacity));
+        self
+    }
+
+    /// Add a co-signer, who signs the fixed inputs locked by it after the payer, e.g. the
+    /// inputs of other users. Its cell deps are added as well.
+    pub fn signer(mut self, signer: &'a User) -> Self {
+        for cell_dep in signer.cell_deps() {
+            self = self.cell_dep(cell_dep);
+        }
+        self.signers.push(signer);
+        self
+    }
+
+    /// Set the witness of the fixed input at `index`. The lock is filled on signing, while
+    /// `input_type` and `output_type` are kept.
+    pub fn witness(mut self, index: usize, witness: WitnessArgs) -> Self {
+        self.witnesses.push((index, witness));
+        self
+    }
+
+    pub fn output(mut self, output: CellOutput, data: Bytes) -> Self {
+        self.outputs.push((output, data));
+        self
+    }
+
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        if !self.cell_deps.contains(&cell_dep) {
+            self.cell_deps.push(cell_dep);
+        }
+        self
+    }
+
+    pub fn header_dep(mut self, header_dep: Byte32) -> Self {
+        if !self.header_deps.contains(&header_dep) {
+            self.header_deps.push(header_dep);
+        }
+        self
+    }
+
+    /// Set the fee rate in shannons per KB, default is `DEFAULT_FEE_RATE`.
+    pub fn fee_rate(mut self, fee_rate: u64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    /// Set the lock of the change output, default is `payer.lock_script()`.
+    pub fn change_lock(mut self, lock: Script) -> Self {
+        self.change_lock = Some(lock);
+        self
+    }
+
+    /// Build the signed transaction.
+    pub fn build(self) -> Result<TransactionView, String> {
+        for (index, (output, data)) in self.outputs.iter().enumerate() {
+            let occupied = occupied_capacity(output, data)?;
+            let capacity: Capacity = output.capacity().unpack();
+            if capacity < occupied {
+                return Err(format!(
+                    "output {} capacity {} is less than its occupied capacity {}",
+                    index, capacity, occupied
+                ));
+            }
+        }
+        let outputs_capacity = self
+            .outputs
+            .iter()
+            .try_fold(Capacity::zero(), |total, (output, _)| {
+                let capacity: Capacity = output.capacity().unpack();
+                total.safe_add(capacity)
+            })
+            .map_err(|err| err.to_string())?;
+        let change_lock = self
+            .change_lock
+            .clone()
+            .unwrap_or_else(|| self.payer.lock_script());
+        let change_output = CellOutput::new_builder().lock(change_lock).build();
+        let change_occupied = occupied_capacity(&change_output, &Bytes::new())?;
+
+        let fixed_out_points = self
+            .inputs
+            .iter()
+            .map(|(cell, _, _)| cell.out_point.clone())
+            .collect::<HashSet<OutPoint>>();
+        let mut inputs = self.inputs.clone();
+        let mut candidates = self
+            .candidates
+            .iter()
+            .filter(|cell| !fixed_out_points.contains(&cell.out_point));
+        loop {
+            let inputs_capacity = inputs
+                .iter()
+                .try_fold(Capacity::zero(), |total, (_, _, capacity)| {
+                    total.safe_add(*capacity)
+                })
+                .map_err(|err| err.to_string())?;
+            if inputs_capacity >= outputs_capacity && !inputs.is_empty() {
+                // Try with change output first, then without change output
+                let change_capacity = inputs_capacity.safe_sub(outputs_capacity).unwrap();
+                if change_capacity > change_occupied {
+                    let with_change = self.build_signed(&inputs, Some(change_output.clone()))?;
+                    let fee = self.fee(&with_change);
+                    if change_capacity >= fee.safe_add(change_occupied).unwrap() {
+                        let change_capacity = change_capacity.safe_sub(fee).unwrap();
+                        let change = change_output
+                            .as_builder()
+                            .capacity(change_capacity.pack())
+                            .build();
+                        return self.build_signed(&inputs, Some(change));
+                    }
+                }
+                let without_change = self.build_signed(&inputs, None)?;
+                if change_capacity >= self.fee(&without_change) {
+                    return Ok(without_change);
+                }
+            }
+            match candidates.next() {
+                Some(cell) => inputs.push((cell.to_owned(), 0, cell.capacity())),
+                None => {
+                    return Err(format!(
+                        "insufficient capacity, outputs require {}, inputs provide {}",
+                        outputs_capacity, inputs_capacity
+                    ))
+                }
+            }
+        }
+    }
+
+    fn fee(&self, tx: &TransactionView) -> Capacity {
+        FeeRate::from_u64(self.fee_rate).fee(tx.data().serialized_size_in_block())
+    }
+
+    fn build_signed(
+        &self,
+        inputs: &[(CellMeta, u64, Capacity)],
+        change: Option<CellOutput>,
+    ) -> Result<TransactionView, String> {
+        let mut witnesses = vec![Bytes::new(); inputs.len()];
+        for (index, witness) in self.witnesses.iter() {
+            if *index >= witnesses.len() {
+                return Err(format!("witness index {} is out of inputs", index));
+            }
+            witnesses[*index] = witness.as_bytes();
+        }
+        let mut outputs = self.outputs.clone();
+        if let Some(change) = change {
+            outputs.push((change, Bytes::new()));
+        }
+        let unsigned_tx = TransactionBuilder::default()
+            .inputs(
+                inputs
+                    .iter()
+                    .map(|(cell, since, _)| CellInput::new(cell.out_point.clone(), *since)),
+            )
+            .outputs(outputs.iter().map(|(output, _)| output.clone()))
+            .outputs_data(outputs.iter().map(|(_, data)| data.pack()))
+            .cell_deps(self.cell_deps.clone())
+            .header_deps(self.header_deps.clone())
+            .witnesses(witnesses.into_iter().map(|witness| witness.pack()))
+            .build();
+        let input_cells = inputs
+            .iter()
+            .map(|(cell, _, _)| cell.cell_output.clone())
+            .collect::<Vec<_>>();
+        self.signers.iter().try_fold(
+            self.payer.sign(&unsigned_tx, &input_cells)?,
+            |tx, signer| signer.sign(&tx, &input_cells),
+        )
+    }
+}
+
+fn occupied_capacity(output: &CellOutput, data: &Bytes) -> Result<Capacity, String> {
+    Capacity::bytes(data.len())
+        .and_then(|data_capacity| output.occupied_capacity(data_capacity))
+        .map_err(|err| format!("failed to calculate occupied capacity, error: {}", err))
+}
@@ -0,0 +1,105 @@
+use crate::util::dao_withdraw_since;
+use crate::{Node, TxBuilder, User};
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, Capacity, HeaderView, TransactionView},
+    packed::{CellOutput, WitnessArgs},
+    prelude::*,
+};
+
+impl User {
+    /// Build a transaction depositing `capacity` into Nervos DAO. The deposit cell is the first
+    /// output, locked by the user and paid by the user's spendable cells.
+    pub fn dao_deposit_transaction(
+        &self,
+        node: &Node,
+        capacity: Capacity,
+    ) -> Result<TransactionView, String> {
+        let deposit = CellOutput::new_builder()
+            .capacity(capacity.pack())
+            .lock(self.lock_script())
+            .type_(Some(node.dao_type_script()).pack())
+            .build();
+        TxBuilder::new(self)
+            .cells(self.get_spendable_cells(node))
+            .output(deposit, Bytes::from(vec![0u8; 8]))
+            .cell_dep(node.dao_cell_dep())
+            .build()
+    }
+
+    /// Build the phase-1 withdraw transaction, which turns the committed `deposit` cell into a
+    /// withdrawing cell recording the deposit block number. The fee is paid by the user's
+    /// spendable cells.
+    pub fn dao_prepare_transaction(
+        &self,
+        node: &Node,
+        deposit: &CellMeta,
+    ) -> Result<TransactionView, String> {
+        let deposit_header = committed_header(node, deposit)?;
+        // The withdrawing cell must be at the same index as the deposit cell
+        TxBuilder::new(self)
+            .cells(self.get_spendable_cells(node))
+            .input(deposit.clone(), 0)
+            .output(
+                deposit.cell_output.clone(),
+                Bytes::from(deposit_header.number().to_le_bytes().to_vec()),
+            )
+            .cell_dep(node.dao_cell_dep())
+            .header_dep(deposit_header.hash())
+            .build()
+    }
+
+    /// Build the phase-2 withdraw transaction, which consumes the committed `withdrawing` cell
+    /// with the minimal `since` and pays the maximum withdraw capacity, minus the fee, back to
+    /// the user.
+    pub fn dao_withdraw_transaction(
+        &self,
+        node: &Node,
+        withdrawing: &CellMeta,
+    ) -> Result<TransactionView, String> {
+        let prepare_header = committed_header(node, withdrawing)?;
+        let deposit_number = {
+            let data = withdrawing.mem_cell_data.as_ref().ok_or_else(|| {
+                format!(
+                    "withdrawing cell {} has no data loaded",
+                    withdrawing.out_point
+                )
+            })?;
+            if data.len() != 8 {
+                return Err(format!(
+                    "withdrawing cell {} has invalid data {:?}",
+                    withdrawing.out_point, data
+                ));
+            }
+            let mut number = [0u8; 8];
+            number.copy_from_slice(data);
+            u64::from_le_bytes(number)
+        };
+        let deposit_header = node.get_header_by_number(deposit_number);
+        let since = dao_withdraw_since(&deposit_header, &prepare_header);
+        let capacity = node.rpc_client().calculate_dao_maximum_withdraw(
+            withdrawing.out_point.clone().into(),
+            prepare_header.hash(),
+        );
+        // `input_type` is the index of the deposit header in header_deps
+        let witness = WitnessArgs::new_builder()
+            .input_type(Some(Bytes::from(0u64.to_le_bytes().to_vec())).pack())
+            .build();
+        TxBuilder::new(self)
+            .input_with_capacity(withdrawing.clone(), since, capacity)
+            .witness(0, witness)
+            .cell_dep(node.dao_cell_dep())
+            .header_dep(deposit_header.hash())
+            .header_dep(prepare_header.hash())
+            .build()
+    }
+}
+
+fn committed_header(node: &Node, cell: &CellMeta) -> Result<HeaderView, String> {
+    let block_hash = cell
+        .transaction_info
+        .as_ref()
+        .map(|info| info.block_hash.clone())
+        .ok_or_else(|| format!("cell {} is not committed", cell.out_point))?;
+    Ok(node.get_block(block_hash).header())
+}
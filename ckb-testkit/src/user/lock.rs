@@ -0,0 +1,95 @@
+use ckb_crypto::secp::Privkey;
+use ckb_hash::blake2b_256;
+use ckb_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    packed::{Byte32, CellDep},
+    H160,
+};
+
+/// The lock which a `User` locks its cells with.
+#[derive(Clone)]
+pub enum UserLock {
+    /// secp256k1_blake160_sighash_all from the genesis dep-group, signed with the
+    /// single_secp256k1 private key.
+    SingleSecp256k1,
+    /// secp256k1_blake160_multisig_all from the genesis dep-group.
+    Multisig(MultisigConfig),
+    /// An anyone-can-pay-style lock deployed by cases. Its args is `blake160(pubkey) | params`,
+    /// e.g. the minimum CKB and UDT amounts, and it is unlocked by a sighash-all signature of
+    /// the single_secp256k1 private key.
+    AnyoneCanPay {
+        script: AnyoneCanPayScript,
+        params: Bytes,
+    },
+    /// The always-success lock from the genesis system cells, no signature required.
+    AlwaysSuccess,
+}
+
+/// The multisig configuration, the signatures of the first `threshold` private keys unlock the
+/// cells, and the first `require_first_n` private keys must be among them.
+#[derive(Clone)]
+pub struct MultisigConfig {
+    require_first_n: u8,
+    threshold: u8,
+    privkeys: Vec<Privkey>,
+}
+
+impl MultisigConfig {
+    pub fn new(require_first_n: u8, threshold: u8, privkeys: Vec<Privkey>) -> Self {
+        assert!(
+            require_first_n <= threshold,
+            "require_first_n({}) should not be greater than threshold({})",
+            require_first_n,
+            threshold
+        );
+        assert!(
+            threshold as usize <= privkeys.len() && privkeys.len() <= u8::MAX as usize,
+            "threshold({}) should not be greater than the number of private keys({})",
+            threshold,
+            privkeys.len()
+        );
+        Self {
+            require_first_n,
+            threshold,
+            privkeys,
+        }
+    }
+
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    pub fn privkeys(&self) -> &[Privkey] {
+        &self.privkeys
+    }
+
+    /// `S | R | M | N | blake160(pubkey_1) | ... | blake160(pubkey_N)`, which leads the
+    /// witness lock and whose blake160 is the lock args.
+    pub fn multisig_script(&self) -> Bytes {
+        let mut script = vec![
+            0u8,
+            self.require_first_n,
+            self.threshold,
+            self.privkeys.len() as u8,
+        ];
+        for privkey in self.privkeys.iter() {
+            let pubkey = privkey.pubkey().expect("multisig pubkey");
+            script.extend_from_slice(&blake2b_256(pubkey.serialize())[0..20]);
+        }
+        Bytes::from(script)
+    }
+
+    pub fn address(&self) -> H160 {
+        H160::from_slice(&blake2b_256(self.multisig_script())[0..20]).unwrap()
+    }
+}
+
+/// The deployed anyone-can-pay-style script.
+#[derive(Debug, Clone)]
+pub struct AnyoneCanPayScript {
+    pub code_hash: Byte32,
+    pub hash_type: ScriptHashType,
+    /// The cell deps to run the script, including the secp256k1 data it loads.
+    pub cell_deps: Vec<CellDep>,
+}
@@ -1,24 +1,246 @@
+mod dao;
+mod lock;
+mod signer;
 pub mod single_secp256k1;
 
+pub use lock::{AnyoneCanPayScript, MultisigConfig, UserLock};
+
+use crate::{
+    Node, GENESIS_DEP_GROUP_TRANSACTION_INDEX, GENESIS_MULTISIG_ALL_DEP_GROUP_CELL_INDEX,
+    MULTISIG_ALL_TYPE_HASH, SYSTEM_CELL_ALWAYS_SUCCESS_INDEX,
+};
 use ckb_crypto::secp::Privkey;
-use ckb_types::core::BlockView;
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, BlockView, DepType, ScriptHashType, TransactionView},
+    packed::{CellDep, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use signer::sign_witness_group;
 
 #[derive(Clone)]
 pub struct User {
     // a workaround to get out-point of system script cells
     genesis_block: BlockView,
     single_secp256k1_privkey: Option<Privkey>,
+    lock: UserLock,
 }
 
 impl User {
+    /// Create a user locking its cells with secp256k1_blake160_sighash_all.
     pub fn new(genesis_block: BlockView, single_secp256k1_privkey: Option<Privkey>) -> Self {
         Self {
             genesis_block,
             single_secp256k1_privkey,
+            lock: UserLock::SingleSecp256k1,
+        }
+    }
+
+    /// Create a user locking its cells with secp256k1_blake160_multisig_all.
+    pub fn new_multisig(genesis_block: BlockView, config: MultisigConfig) -> Self {
+        Self {
+            genesis_block,
+            single_secp256k1_privkey: None,
+            lock: UserLock::Multisig(config),
+        }
+    }
+
+    /// Create a user locking its cells with an anyone-can-pay-style lock.
+    pub fn new_anyone_can_pay(
+        genesis_block: BlockView,
+        privkey: Privkey,
+        script: AnyoneCanPayScript,
+        params: Bytes,
+    ) -> Self {
+        Self {
+            genesis_block,
+            single_secp256k1_privkey: Some(privkey),
+            lock: UserLock::AnyoneCanPay { script, params },
+        }
+    }
+
+    /// Create a user locking its cells with the genesis always-success lock.
+    pub fn new_always_success(genesis_block: BlockView) -> Self {
+        Self {
+            genesis_block,
+            single_secp256k1_privkey: None,
+            lock: UserLock::AlwaysSuccess,
         }
     }
 
     pub fn single_secp256k1_privkey(&self) -> Option<&Privkey> {
         self.single_secp256k1_privkey.as_ref()
     }
+
+    pub fn lock(&self) -> &UserLock {
+        &self.lock
+    }
+
+    /// Return the lock script of the user's cells.
+    pub fn lock_script(&self) -> Script {
+        match &self.lock {
+            UserLock::SingleSecp256k1 => self.single_secp256k1_lock_script_via_type(),
+            UserLock::Multisig(config) => Script::new_builder()
+                .hash_type(ScriptHashType::Type.into())
+                .code_hash(MULTISIG_ALL_TYPE_HASH.pack())
+                .args(config.address().0.pack())
+                .build(),
+            UserLock::AnyoneCanPay { script, params } => {
+                let mut args = self.single_secp256k1_address().0.to_vec();
+                args.extend_from_slice(params);
+                Script::new_builder()
+                    .hash_type(script.hash_type.into())
+                    .code_hash(script.code_hash.clone())
+                    .args(Bytes::from(args).pack())
+                    .build()
+            }
+            UserLock::AlwaysSuccess => {
+                let type_ = self
+                    .genesis_block
+                    .transaction(0)
+                    .and_then(|cellbase| cellbase.output(SYSTEM_CELL_ALWAYS_SUCCESS_INDEX as usize))
+                    .and_then(|output| output.type_().to_opt())
+                    .expect("genesis always success cell should have type_=type-id script");
+                Script::new_builder()
+                    .code_hash(type_.calc_script_hash())
+                    .hash_type(ScriptHashType::Type.into())
+                    .build()
+            }
+        }
+    }
+
+    /// Return the cell deps required to unlock the user's cells.
+    pub fn cell_deps(&self) -> Vec<CellDep> {
+        match &self.lock {
+            UserLock::SingleSecp256k1 => vec![self.single_secp256k1_cell_dep()],
+            UserLock::Multisig(_) => {
+                let out_point = OutPoint::new_builder()
+                    .tx_hash(
+                        self.genesis_block
+                            .transaction(GENESIS_DEP_GROUP_TRANSACTION_INDEX)
+                            .expect("index genesis dep-group transaction")
+                            .hash(),
+                    )
+                    .index(GENESIS_MULTISIG_ALL_DEP_GROUP_CELL_INDEX.pack())
+                    .build();
+                vec![CellDep::new_builder()
+                    .out_point(out_point)
+                    .dep_type(DepType::DepGroup.into())
+                    .build()]
+            }
+            UserLock::AnyoneCanPay { script, .. } => script.cell_deps.clone(),
+            UserLock::AlwaysSuccess => {
+                let genesis_cellbase_hash = self
+                    .genesis_block
+                    .transaction(0)
+                    .expect("genesis cellbase")
+                    .hash();
+                vec![CellDep::new_builder()
+                    .out_point(OutPoint::new(
+                        genesis_cellbase_hash,
+                        SYSTEM_CELL_ALWAYS_SUCCESS_INDEX,
+                    ))
+                    .build()]
+            }
+        }
+    }
+
+    /// Return the live cells locked by the user, which have neither data nor type script.
+    pub fn get_spendable_cells(&self, node: &Node) -> Vec<CellMeta> {
+        if let UserLock::SingleSecp256k1 = self.lock {
+            return self.get_spendable_single_secp256k1_cells(node);
+        }
+        node.get_live_cells_by_lock_script(&self.lock_script())
+            .into_iter()
+            .filter(|cell_meta| {
+                cell_meta.data_bytes == 0
+                    && cell_meta.cell_output.type_().is_none()
+                    && node.is_mature(cell_meta)
+            })
+            .collect()
+    }
+
+    /// Sign all the inputs locked by the user, return the signed transaction.
+    ///
+    /// `inputs` are the cell outputs consumed by `tx`, in the same order as `tx.inputs()`. The
+    /// inputs with the same lock script form a witness group, the first witness of which is
+    /// filled with the signature; the other witnesses are kept as is. For single_secp256k1,
+    /// the inputs locked via type, data and data1 are signed as three groups.
+    pub fn sign(
+        &self,
+        tx: &TransactionView,
+        inputs: &[CellOutput],
+    ) -> Result<TransactionView, String> {
+        if inputs.len() != tx.inputs().len() {
+            return Err(format!(
+                "expect {} input cells, got {}",
+                tx.inputs().len(),
+                inputs.len()
+            ));
+        }
+        let lock_scripts = match &self.lock {
+            UserLock::SingleSecp256k1 => vec![
+                self.single_secp256k1_lock_script_via_type(),
+                self.single_secp256k1_lock_script_via_data(),
+                self.single_secp256k1_lock_script_via_data1(),
+            ],
+            _ => vec![self.lock_script()],
+        };
+        let mut signed_tx = tx.clone();
+        let mut signed_any = false;
+        for lock_script in lock_scripts {
+            let lock_hash = lock_script.calc_script_hash();
+            let group = inputs
+                .iter()
+                .enumerate()
+                .filter(|(_, input)| input.calc_lock_hash() == lock_hash)
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            if group.is_empty() {
+                continue;
+            }
+            signed_any = true;
+            let witnesses = match &self.lock {
+                UserLock::SingleSecp256k1 | UserLock::AnyoneCanPay { .. } => {
+                    let privkey = self.single_secp256k1_privkey_or_panic();
+                    sign_witness_group(&signed_tx, &group, 65, |message| {
+                        let sig = privkey.sign_recoverable(message).expect("sign");
+                        Bytes::from(sig.serialize())
+                    })
+                }
+                UserLock::Multisig(config) => {
+                    let multisig_script = config.multisig_script();
+                    let lock_len = multisig_script.len() + 65 * config.threshold() as usize;
+                    sign_witness_group(&signed_tx, &group, lock_len, |message| {
+                        let mut lock = multisig_script.to_vec();
+                        for privkey in config.privkeys().iter().take(config.threshold() as usize) {
+                            let sig = privkey.sign_recoverable(message).expect("sign");
+                            lock.extend_from_slice(&sig.serialize());
+                        }
+                        Bytes::from(lock)
+                    })
+                }
+                // always-success requires no signature
+                UserLock::AlwaysSuccess => continue,
+            };
+            signed_tx = signed_tx
+                .as_advanced_builder()
+                .set_witnesses(witnesses)
+                .build();
+        }
+        if signed_any {
+            Ok(signed_tx)
+        } else {
+            Err(format!(
+                "no input of transaction {:#x} is locked by the user",
+                tx.hash()
+            ))
+        }
+    }
+
+    fn single_secp256k1_privkey_or_panic(&self) -> &Privkey {
+        self.single_secp256k1_privkey
+            .as_ref()
+            .expect("user has no single_secp256k1 private key")
+    }
 }
@@ -0,0 +1,70 @@
+use ckb_types::{
+    bytes::Bytes,
+    core::TransactionView,
+    packed::{self, WitnessArgs},
+    prelude::*,
+    H256,
+};
+
+/// Sign the witness group `group` of `tx` in the sighash-all way, return the signed witnesses.
+///
+/// The message is `blake2b(tx_hash | first witness with zero-filled lock | other witnesses in
+/// the group | witnesses beyond the inputs)`, each witness is prefixed with its length as
+/// little-endian u64. `input_type` and `output_type` of the first witness are preserved.
+/// `sign` receives the message and returns the lock of the first witness, whose length must
+/// equal `lock_len`.
+pub(crate) fn sign_witness_group<F>(
+    tx: &TransactionView,
+    group: &[usize],
+    lock_len: usize,
+    sign: F,
+) -> Vec<packed::Bytes>
+where
+    F: FnOnce(&H256) -> Bytes,
+{
+    assert!(!group.is_empty(), "witness group should not be empty");
+    let inputs_len = tx.inputs().len();
+    let mut witnesses = tx.witnesses().into_iter().collect::<Vec<_>>();
+    if witnesses.len() < inputs_len {
+        witnesses.resize(inputs_len, packed::Bytes::default());
+    }
+
+    let first = group[0];
+    let first_witness = if witnesses[first].is_empty() {
+        WitnessArgs::default()
+    } else {
+        WitnessArgs::from_slice(&witnesses[first].raw_data())
+            .unwrap_or_else(|err| panic!("witness {} is not WitnessArgs, error: {}", first, err))
+    };
+    let witness_for_digest = first_witness
+        .clone()
+        .as_builder()
+        .lock(Some(Bytes::from(vec![0u8; lock_len])).pack())
+        .build();
+
+    let mut blake2b = ckb_hash::new_blake2b();
+    blake2b.update(&tx.hash().raw_data());
+    let update_witness = |blake2b: &mut ckb_hash::Blake2b, witness: &[u8]| {
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(witness);
+    };
+    update_witness(&mut blake2b, &witness_for_digest.as_bytes());
+    for index in group.iter().skip(1) {
+        update_witness(&mut blake2b, &witnesses[*index].raw_data());
+    }
+    for witness in witnesses.iter().skip(inputs_len) {
+        update_witness(&mut blake2b, &witness.raw_data());
+    }
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+
+    let lock = sign(&H256::from(message));
+    assert_eq!(lock.len(), lock_len, "signed lock length mismatch");
+    witnesses[first] = first_witness
+        .as_builder()
+        .lock(Some(lock).pack())
+        .build()
+        .as_bytes()
+        .pack();
+    witnesses
+}
@@ -1,3 +1,4 @@
+use super::signer::sign_witness_group;
 use crate::{
     Node, User, GENESIS_DEP_GROUP_TRANSACTION_INDEX, GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX,
     SIGHASH_ALL_DATA_HASH, SIGHASH_ALL_TYPE_HASH,
@@ -5,13 +6,12 @@ use crate::{
 use ckb_crypto::secp::{Message, Pubkey, Signature};
 use ckb_hash::blake2b_256;
 use ckb_types::core::cell::CellMeta;
-use ckb_types::core::EpochNumberWithFraction;
 use ckb_types::{
     bytes::Bytes,
     core::{DepType, ScriptHashType, TransactionView},
     packed::{CellDep, OutPoint, Script, WitnessArgs},
     prelude::*,
-    H160, H256,
+    H160,
 };
 
 impl User {
@@ -64,93 +64,49 @@ impl User {
     }
 
     pub fn single_secp256k1_pubkey(&self) -> Pubkey {
-        if let Some(ref privkey) = self.single_secp256k1_privkey {
-            privkey.pubkey().unwrap()
-        } else {
-            unreachable!("single_secp256k1 unset")
-        }
+        self.single_secp256k1_privkey_or_panic().pubkey().unwrap()
     }
 
-    pub fn single_secp256k1_signed_witness(&self, tx: &TransactionView) -> WitnessArgs {
-        if let Some(ref privkey) = self.single_secp256k1_privkey {
-            let tx_hash = tx.hash();
-            let mut blake2b = ckb_hash::new_blake2b();
-            let mut message = [0u8; 32];
-            blake2b.update(&tx_hash.raw_data());
-            let witness_for_digest = WitnessArgs::new_builder()
-                .lock(Some(Bytes::from(vec![0u8; 65])).pack())
-                .build();
-            let witness_len = witness_for_digest.as_bytes().len() as u64;
-            blake2b.update(&witness_len.to_le_bytes());
-            blake2b.update(&witness_for_digest.as_bytes());
-            blake2b.finalize(&mut message);
-            let message = H256::from(message);
-            let sig = privkey.sign_recoverable(&message).expect("sign");
-            WitnessArgs::new_builder()
-                .lock(Some(Bytes::from(sig.serialize())).pack())
-                .build()
-            // .as_bytes()
-            // .pack()
-        } else {
-            unreachable!("single_secp256k1 unset")
-        }
+    /// Sign the witness group `group`, i.e. the indexes of the inputs locked by the same
+    /// single_secp256k1 lock script, in the sighash-all way. Return the signed first witness of
+    /// the group, whose `input_type` and `output_type` are preserved.
+    ///
+    /// Prefer `User::sign`, which groups the inputs by lock script and signs them all.
+    pub fn single_secp256k1_signed_witness(
+        &self,
+        tx: &TransactionView,
+        group: &[usize],
+    ) -> WitnessArgs {
+        let privkey = self.single_secp256k1_privkey_or_panic();
+        let witnesses = sign_witness_group(tx, group, 65, |message| {
+            let sig = privkey.sign_recoverable(message).expect("sign");
+            Bytes::from(sig.serialize())
+        });
+        WitnessArgs::from_slice(&witnesses[group[0]].raw_data()).expect("signed WitnessArgs")
     }
 
     pub fn sign_recoverable(&self, message: &Message) -> Signature {
-        if let Some(ref privkey) = self.single_secp256k1_privkey {
-            privkey.sign_recoverable(message).expect("sign")
-        } else {
-            unreachable!("single_secp256k1 unset")
-        }
+        self.single_secp256k1_privkey_or_panic()
+            .sign_recoverable(message)
+            .expect("sign")
     }
 
     pub fn get_spendable_single_secp256k1_cells(&self, node: &Node) -> Vec<CellMeta> {
-        let tip_number = node.get_tip_block_number();
-        let mut live_out_points = Vec::new();
+        let mut live_cells = Vec::new();
 
-        live_out_points.extend(
-            node.indexer()
-                .get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_type())
-                .expect("indexer get_live_cells_by_lock_script"),
+        live_cells.extend(
+            node.get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_type()),
         );
-        live_out_points.extend(
-            node.indexer()
-                .get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_data())
-                .expect("indexer get_live_cells_by_lock_script"),
+        live_cells.extend(
+            node.get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_data()),
         );
-        live_out_points.extend(
-            node.indexer()
-                .get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_data1())
-                .expect("indexer get_live_cells_by_lock_script"),
+        live_cells.extend(
+            node.get_live_cells_by_lock_script(&self.single_secp256k1_lock_script_via_data1()),
         );
 
-        live_out_points
+        live_cells
             .into_iter()
-            .filter_map(|out_point| {
-                let cell_meta = node.get_cell_meta(out_point)?;
-
-                let txinfo = cell_meta
-                    .transaction_info
-                    .as_ref()
-                    .expect("committed tx has transaction_info");
-                if txinfo.is_cellbase() {
-                    let cellbase_maturity: EpochNumberWithFraction = {
-                        EpochNumberWithFraction::from_full_value(
-                            node.consensus().cellbase_maturity.into(),
-                        )
-                    };
-                    // We didn't fill the block_epoch inside `fn get_cell_meta`
-                    if txinfo.block_number + cellbase_maturity.number() * 1800 > tip_number {
-                        return None;
-                    }
-                }
-
-                if cell_meta.data_bytes != 0 {
-                    return None;
-                }
-
-                Some(cell_meta)
-            })
+            .filter(|cell_meta| node.is_mature(cell_meta) && cell_meta.data_bytes == 0)
             .collect::<Vec<_>>()
     }
 }
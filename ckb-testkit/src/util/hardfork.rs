@@ -0,0 +1,26 @@
+//! The hardfork features scheduled in `Consensus`, identified by their RFC numbers as reported
+//! by RPC `get_consensus`, e.g. "0028" and "0032".
+
+use ckb_jsonrpc_types::Consensus;
+use ckb_types::core::{BlockNumber, EpochNumber};
+
+/// The number of blocks around a hardfork activation within which ckb relays and accepts
+/// transactions by the rules of either side. Cases checking one side stay out of the window.
+pub const HARDFORK_DELAY_WINDOW: BlockNumber = 10;
+
+/// Return the epoch at which `rfc` activates, or `None` if it is never activated.
+pub fn rfc_epoch_number(consensus: &Consensus, rfc: &str) -> Option<EpochNumber> {
+    consensus
+        .hardfork_features
+        .iter()
+        .find(|feature| feature.rfc == rfc)
+        .and_then(|feature| feature.epoch_number)
+        .map(|epoch_number| epoch_number.value())
+}
+
+/// Return whether `rfc` is activated at epoch `epoch_number`.
+pub fn is_rfc_activated(consensus: &Consensus, rfc: &str, epoch_number: EpochNumber) -> bool {
+    rfc_epoch_number(consensus, rfc)
+        .map(|activated| epoch_number >= activated)
+        .unwrap_or(false)
+}
@@ -1,14 +1,21 @@
+pub mod hardfork;
 pub mod macros;
+pub mod rng;
+pub mod since;
+pub mod waiter;
 
-use ckb_types::core::{BlockNumber, EpochNumberWithFraction};
+pub use since::{Since, SinceBlock, SinceError, SinceMetric};
+pub use waiter::{WaitTimeout, Waiter};
+
+use ckb_types::core::{BlockNumber, EpochNumber, EpochNumberWithFraction, HeaderView};
 use lazy_static::lazy_static;
 use std::env;
+use std::fs;
 use std::net::{Ipv4Addr, SocketAddrV4, TcpListener};
 use std::path::PathBuf;
 use std::sync::atomic::AtomicU16;
 use std::sync::atomic::Ordering::SeqCst;
-use std::thread::sleep;
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
 pub const FLAG_SINCE_RELATIVE: u64 =
     0b1000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000;
@@ -19,6 +26,9 @@ pub const FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION: u64 =
 pub const FLAG_SINCE_TIMESTAMP: u64 =
     0b100_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000_0000;
 
+/// The minimal lock period of Nervos DAO deposits, in epochs.
+pub const DAO_LOCK_PERIOD_EPOCHS: EpochNumber = 180;
+
 lazy_static! {
     pub static ref PORT_COUNTER: AtomicU16 = AtomicU16::new(9000);
 }
@@ -34,18 +44,27 @@ pub fn find_available_port() -> u16 {
     panic!("failed to allocate available port")
 }
 
-/// Return a random path located on temp_dir
+/// Return a random path located on temp_dir, or on `$CKB_INTEGRATION_TEST_TMP/<case_name>/` if
+/// the environment variable is set
 ///
 /// We use `tempdir` only for generating a random path, and expect the corresponding directory
 /// that `tempdir` creates be deleted when go out of this function.
 pub fn temp_path(case_name: &str, suffix: &str) -> PathBuf {
     let mut builder = tempfile::Builder::new();
-    let prefix = ["ckb-it", case_name, suffix, ""].join("-");
-    builder.prefix(&prefix);
     let tempdir = if let Ok(val) = env::var("CKB_INTEGRATION_TEST_TMP") {
-        builder.tempdir_in(val)
+        let case_dir = PathBuf::from(val).join(case_name);
+        fs::create_dir_all(&case_dir).unwrap_or_else(|err| {
+            panic!(
+                "failed to create dir \"{}\", error: {}",
+                case_dir.display(),
+                err
+            )
+        });
+        let prefix = [suffix, ""].join("-");
+        builder.prefix(&prefix).tempdir_in(case_dir)
     } else {
-        builder.tempdir()
+        let prefix = ["ckb-it", case_name, suffix, ""].join("-");
+        builder.prefix(&prefix).tempdir()
     }
     .expect("create tempdir failed");
     let path = tempdir.path().to_owned();
@@ -53,19 +72,15 @@ pub fn temp_path(case_name: &str, suffix: &str) -> PathBuf {
     path
 }
 
-pub fn wait_until<F>(timeout_secs: u64, mut f: F) -> bool
+/// Return whether `f` holds within `timeout_secs`, which is stretched by the time-scale factor.
+/// Use `Waiter` for finer timeouts and descriptive errors.
+pub fn wait_until<F>(timeout_secs: u64, f: F) -> bool
 where
     F: FnMut() -> bool,
 {
-    let timeout = Duration::from_secs(timeout_secs);
-    let start = Instant::now();
-    while Instant::now().duration_since(start) <= timeout {
-        if f() {
-            return true;
-        }
-        sleep(Duration::new(1, 0));
-    }
-    false
+    Waiter::new("condition", Duration::from_secs(timeout_secs))
+        .wait_until(f)
+        .is_ok()
 }
 
 pub fn since_from_relative_block_number(block_number: BlockNumber) -> u64 {
@@ -90,6 +105,29 @@ pub fn since_from_absolute_epoch_number_with_fraction(
     FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION | epoch_number_with_fraction.full_value()
 }
 
+/// Return the minimal `since` of the Nervos DAO phase-2 withdrawing input, which is deposited
+/// in the block `deposit_header` and prepared in the block `prepare_header`.
+///
+/// The deposit is locked for the smallest multiple of `DAO_LOCK_PERIOD_EPOCHS` covering the
+/// deposited epochs, counted from the deposit epoch with fraction.
+pub fn dao_withdraw_since(deposit_header: &HeaderView, prepare_header: &HeaderView) -> u64 {
+    let deposit_epoch = deposit_header.epoch();
+    let prepare_epoch = prepare_header.epoch();
+    let mut deposited_epochs = prepare_epoch.number() - deposit_epoch.number();
+    if prepare_epoch.index() * deposit_epoch.length()
+        > deposit_epoch.index() * prepare_epoch.length()
+    {
+        deposited_epochs += 1;
+    }
+    let lock_periods = (deposited_epochs + DAO_LOCK_PERIOD_EPOCHS - 1) / DAO_LOCK_PERIOD_EPOCHS;
+    let lock_epochs = lock_periods.max(1) * DAO_LOCK_PERIOD_EPOCHS;
+    since_from_absolute_epoch_number_with_fraction(EpochNumberWithFraction::new(
+        deposit_epoch.number() + lock_epochs,
+        deposit_epoch.index(),
+        deposit_epoch.length(),
+    ))
+}
+
 pub fn since_from_relative_timestamp(timestamp: u64) -> u64 {
     FLAG_SINCE_RELATIVE | FLAG_SINCE_TIMESTAMP | timestamp
 }
@@ -0,0 +1,281 @@
+//! The `since` field of transaction inputs, see [RFC0017](https://github.com/nervosnetwork/rfcs/blob/master/rfcs/0017-tx-valid-since/0017-tx-valid-since.md).
+//!
+//! ```text
+//! ┌───────────────┬─────────────────┬───────────────┬────────────────┐
+//! │ relative flag │ metric flag     │ reserved      │ value          │
+//! │ bit 63        │ bits 62 ~ 61    │ bits 60 ~ 56  │ bits 55 ~ 0    │
+//! └───────────────┴─────────────────┴───────────────┴────────────────┘
+//! ```
+
+use super::hardfork::is_rfc_activated;
+use super::{
+    FLAG_SINCE_BLOCK_NUMBER, FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION, FLAG_SINCE_RELATIVE,
+    FLAG_SINCE_TIMESTAMP,
+};
+use crate::Node;
+use ckb_jsonrpc_types::Consensus;
+use ckb_types::{
+    core::{BlockNumber, EpochNumberWithFraction, HeaderView, TransactionView},
+    packed::Byte32,
+    prelude::*,
+};
+use std::fmt;
+
+const SINCE_METRIC_MASK: u64 = 0x6000_0000_0000_0000;
+const SINCE_RESERVED_MASK: u64 = 0x1f00_0000_0000_0000;
+const SINCE_VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinceMetric {
+    BlockNumber(BlockNumber),
+    EpochNumberWithFraction(EpochNumberWithFraction),
+    /// In seconds, unlike the block timestamps in milliseconds.
+    Timestamp(u64),
+}
+
+/// A typed `since`, which round-trips to `u64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Since {
+    pub relative: bool,
+    pub metric: SinceMetric,
+}
+
+/// The reasons a `since` is not satisfied, named after the ckb verification errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SinceError {
+    InvalidSince(String),
+    Immature,
+    /// The outcome is not modeled, i.e. the malformed epoch fractions before RFC0030.
+    Undetermined(String),
+}
+
+impl fmt::Display for SinceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SinceError::InvalidSince(reason) => write!(f, "InvalidSince: {}", reason),
+            SinceError::Immature => write!(f, "Immature"),
+            SinceError::Undetermined(reason) => write!(f, "Undetermined: {}", reason),
+        }
+    }
+}
+
+/// The chain state of a block a `since` is evaluated against, either the block committing the
+/// input cell or the block committing the spending transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SinceBlock {
+    pub number: BlockNumber,
+    pub epoch: EpochNumberWithFraction,
+    pub timestamp: u64,
+    /// The median time of the previous `median_time_block_count` blocks.
+    pub parent_median_time: u64,
+}
+
+impl SinceBlock {
+    /// Return the `SinceBlock` of the block `block_hash` of `node`.
+    pub fn from_block(node: &Node, block_hash: Byte32) -> Self {
+        let header: HeaderView = node
+            .rpc_client()
+            .get_header(block_hash.clone())
+            .unwrap_or_else(|| panic!("block {:#x} does not exist", block_hash))
+            .into();
+        let parent_median_time = if header.number() == 0 {
+            header.timestamp()
+        } else {
+            node.rpc_client()
+                .get_block_median_time(header.parent_hash())
+                .expect("parent block exists")
+                .value()
+        };
+        Self {
+            number: header.number(),
+            epoch: header.epoch(),
+            timestamp: header.timestamp(),
+            parent_median_time,
+        }
+    }
+}
+
+impl Since {
+    pub fn absolute_block_number(block_number: BlockNumber) -> Self {
+        Self::new(false, SinceMetric::BlockNumber(block_number))
+    }
+
+    pub fn relative_block_number(block_number: BlockNumber) -> Self {
+        Self::new(true, SinceMetric::BlockNumber(block_number))
+    }
+
+    pub fn absolute_epoch(epoch: EpochNumberWithFraction) -> Self {
+        Self::new(false, SinceMetric::EpochNumberWithFraction(epoch))
+    }
+
+    pub fn relative_epoch(epoch: EpochNumberWithFraction) -> Self {
+        Self::new(true, SinceMetric::EpochNumberWithFraction(epoch))
+    }
+
+    pub fn absolute_timestamp(secs: u64) -> Self {
+        Self::new(false, SinceMetric::Timestamp(secs))
+    }
+
+    pub fn relative_timestamp(secs: u64) -> Self {
+        Self::new(true, SinceMetric::Timestamp(secs))
+    }
+
+    fn new(relative: bool, metric: SinceMetric) -> Self {
+        Self { relative, metric }
+    }
+
+    /// Decode `since`, checking the metric flag and the reserved bits.
+    pub fn from_u64(since: u64) -> Result<Self, SinceError> {
+        if since & SINCE_RESERVED_MASK != 0 {
+            return Err(SinceError::InvalidSince(format!(
+                "since {:#x} has non-zero reserved bits",
+                since
+            )));
+        }
+        let relative = since & FLAG_SINCE_RELATIVE != 0;
+        let value = since & SINCE_VALUE_MASK;
+        let metric = match since & SINCE_METRIC_MASK {
+            FLAG_SINCE_BLOCK_NUMBER => SinceMetric::BlockNumber(value),
+            FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION => SinceMetric::EpochNumberWithFraction(
+                EpochNumberWithFraction::from_full_value(value),
+            ),
+            FLAG_SINCE_TIMESTAMP => SinceMetric::Timestamp(value),
+            _ => {
+                return Err(SinceError::InvalidSince(format!(
+                    "since {:#x} has an unknown metric flag",
+                    since
+                )))
+            }
+        };
+        Ok(Self { relative, metric })
+    }
+
+    /// Decode the `since` of each input of `transaction`, for diagnostics.
+    pub fn decode_inputs(transaction: &TransactionView) -> Vec<Result<Self, SinceError>> {
+        transaction
+            .inputs()
+            .into_iter()
+            .map(|input| Self::from_u64(input.since().unpack()))
+            .collect()
+    }
+
+    pub fn to_u64(&self) -> u64 {
+        let (flag, value) = match self.metric {
+            SinceMetric::BlockNumber(block_number) => (FLAG_SINCE_BLOCK_NUMBER, block_number),
+            SinceMetric::EpochNumberWithFraction(epoch) => {
+                (FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION, epoch.full_value())
+            }
+            SinceMetric::Timestamp(secs) => (FLAG_SINCE_TIMESTAMP, secs),
+        };
+        assert_eq!(
+            value & !SINCE_VALUE_MASK,
+            0,
+            "since value {:#x} overflows 56 bits",
+            value
+        );
+        let relative = if self.relative {
+            FLAG_SINCE_RELATIVE
+        } else {
+            0
+        };
+        relative | flag | value
+    }
+
+    /// Return whether the epoch fraction, if any, is well-formed as RFC0030 requires: the index
+    /// is less than the length, or both are zero.
+    pub fn is_well_formed(&self) -> bool {
+        match self.metric {
+            SinceMetric::EpochNumberWithFraction(epoch) => epoch.is_well_formed_increment(),
+            _ => true,
+        }
+    }
+
+    /// Evaluate whether the input cell committed in `input_block`, locked by `self`, can be spent
+    /// in `committing_block`, following the rules active at `committing_block` according to
+    /// the hardfork features of `consensus`.
+    pub fn verify(
+        &self,
+        consensus: &Consensus,
+        input_block: &SinceBlock,
+        committing_block: &SinceBlock,
+    ) -> Result<(), SinceError> {
+        let epoch_number = committing_block.epoch.number();
+        let rfc0028 = is_rfc_activated(consensus, "0028", epoch_number);
+        let rfc0030 = is_rfc_activated(consensus, "0030", epoch_number);
+        if !self.is_well_formed() {
+            if rfc0030 {
+                return Err(SinceError::InvalidSince(format!(
+                    "malformed epoch fraction, {}",
+                    self
+                )));
+            } else {
+                return Err(SinceError::Undetermined(format!(
+                    "malformed epoch fraction before RFC0030, {}",
+                    self
+                )));
+            }
+        }
+        let satisfied = match (self.relative, self.metric) {
+            (false, SinceMetric::BlockNumber(block_number)) => {
+                committing_block.number >= block_number
+            }
+            (true, SinceMetric::BlockNumber(block_number)) => {
+                committing_block.number >= input_block.number + block_number
+            }
+            (false, SinceMetric::EpochNumberWithFraction(epoch)) => {
+                committing_block.epoch.to_rational() >= epoch.normalize().to_rational()
+            }
+            (true, SinceMetric::EpochNumberWithFraction(epoch)) => {
+                committing_block.epoch.to_rational()
+                    >= input_block.epoch.to_rational() + epoch.normalize().to_rational()
+            }
+            (false, SinceMetric::Timestamp(secs)) => {
+                committing_block.parent_median_time >= secs * 1000
+            }
+            (true, SinceMetric::Timestamp(secs)) => {
+                // RFC0028 changes the base from the median time to the block timestamp
+                let base = if rfc0028 {
+                    input_block.timestamp
+                } else {
+                    input_block.parent_median_time
+                };
+                committing_block.parent_median_time >= base + secs * 1000
+            }
+        };
+        if satisfied {
+            Ok(())
+        } else {
+            Err(SinceError::Immature)
+        }
+    }
+}
+
+impl From<Since> for u64 {
+    fn from(since: Since) -> u64 {
+        since.to_u64()
+    }
+}
+
+impl fmt::Display for Since {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let relative = if self.relative {
+            "relative"
+        } else {
+            "absolute"
+        };
+        match self.metric {
+            SinceMetric::BlockNumber(block_number) => {
+                write!(f, "{} block number {}", relative, block_number)
+            }
+            SinceMetric::EpochNumberWithFraction(epoch) => write!(
+                f,
+                "{} epoch ({}, {}, {})",
+                relative,
+                epoch.number(),
+                epoch.index(),
+                epoch.length()
+            ),
+            SinceMetric::Timestamp(secs) => write!(f, "{} timestamp {}s", relative, secs),
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use lazy_static::lazy_static;
+use std::env;
+use std::fmt;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+/// The environment variable to stretch all the `Waiter` timeouts uniformly, e.g.
+/// `CKB_TESTKIT_TIME_SCALE=2.5` on slow CI machines.
+pub const TIME_SCALE_ENV: &str = "CKB_TESTKIT_TIME_SCALE";
+
+pub const DEFAULT_INITIAL_INTERVAL: Duration = Duration::from_millis(50);
+pub const DEFAULT_MAX_INTERVAL: Duration = Duration::from_secs(1);
+pub const DEFAULT_MULTIPLIER: u32 = 2;
+
+lazy_static! {
+    static ref TIME_SCALE: f64 = match env::var(TIME_SCALE_ENV) {
+        Ok(val) => match val.parse::<f64>() {
+            Ok(scale) if scale > 0.0 => scale,
+            _ => panic!(
+                "invalid {}=\"{}\", expect a positive number",
+                TIME_SCALE_ENV, val
+            ),
+        },
+        Err(_) => 1.0,
+    };
+}
+
+/// Return the time-scale factor set via `CKB_TESTKIT_TIME_SCALE`, 1.0 by default.
+pub fn time_scale() -> f64 {
+    *TIME_SCALE
+}
+
+/// Return `duration` stretched by the time-scale factor.
+pub fn scaled(duration: Duration) -> Duration {
+    duration.mul_f64(time_scale())
+}
+
+/// Waiter polls a condition with exponential backoff until it holds or the timeout elapses.
+///
+/// ```ignore
+/// let tip = Waiter::new("node synced", Duration::from_secs(60))
+///     .max_interval(Duration::from_millis(500))
+///     .wait_for(|| {
+///         let tip = node.get_tip_block_number();
+///         if tip >= target { Ok(tip) } else { Err(tip) }
+///     })
+///     .unwrap_or_else(|err| panic!("{}", err));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Waiter {
+    description: String,
+    timeout: Duration,
+    initial_interval: Duration,
+    max_interval: Duration,
+    multiplier: u32,
+}
+
+/// The error returned by `Waiter` on timeout, with the state observed at the last attempt.
+#[derive(Debug, Clone)]
+pub struct WaitTimeout<S> {
+    pub description: String,
+    pub elapsed: Duration,
+    pub attempts: u32,
+    pub last_state: Option<S>,
+}
+
+impl<S: fmt::Debug> fmt::Display for WaitTimeout<S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "timeout to wait for {} after {:?} and {} attempts",
+            self.description, self.elapsed, self.attempts
+        )?;
+        if let Some(last_state) = self.last_state.as_ref() {
+            write!(f, ", last state: {:?}", last_state)?;
+        }
+        Ok(())
+    }
+}
+
+impl Waiter {
+    /// `timeout` is stretched by the time-scale factor.
+    pub fn new<S: ToString>(description: S, timeout: Duration) -> Self {
+        Self {
+            description: description.to_string(),
+            timeout: scaled(timeout),
+            initial_interval: DEFAULT_INITIAL_INTERVAL,
+            max_interval: DEFAULT_MAX_INTERVAL,
+            multiplier: DEFAULT_MULTIPLIER,
+        }
+    }
+
+    pub fn initial_interval(mut self, initial_interval: Duration) -> Self {
+        self.initial_interval = initial_interval;
+        self
+    }
+
+    pub fn max_interval(mut self, max_interval: Duration) -> Self {
+        self.max_interval = max_interval;
+        self
+    }
+
+    /// Set the backoff multiplier; 1 means a fixed interval.
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier.max(1);
+        self
+    }
+
+    /// Return the scaled timeout.
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Poll `f` until it returns `Ok(value)`. `Err(state)` means not yet, the state of the last
+    /// attempt is reported on timeout.
+    pub fn wait_for<T, S, F>(&self, f: F) -> Result<T, WaitTimeout<S>>
+    where
+        F: FnMut() -> Result<T, S>,
+    {
+        self.wait_for_progress(f, |_, _| false)
+    }
+
+    /// Like `wait_for`, but the timeout restarts whenever `progressed(previous_state, state)`
+    /// holds, i.e. keep waiting as long as the condition is approaching.
+    pub fn wait_for_progress<T, S, F, P>(
+        &self,
+        mut f: F,
+        mut progressed: P,
+    ) -> Result<T, WaitTimeout<S>>
+    where
+        F: FnMut() -> Result<T, S>,
+        P: FnMut(&S, &S) -> bool,
+    {
+        let start_time = Instant::now();
+        let mut progress_time = start_time;
+        let mut interval = self.initial_interval;
+        let mut attempts = 0;
+        let mut last_state: Option<S> = None;
+        loop {
+            attempts += 1;
+            let state = match f() {
+                Ok(value) => return Ok(value),
+                Err(state) => state,
+            };
+            if let Some(last_state) = last_state.as_ref() {
+                if progressed(last_state, &state) {
+                    progress_time = Instant::now();
+                }
+            }
+            let stalled = progress_time.elapsed();
+            if stalled >= self.timeout {
+                return Err(WaitTimeout {
+                    description: self.description.clone(),
+                    elapsed: start_time.elapsed(),
+                    attempts,
+                    last_state: Some(state),
+                });
+            }
+            last_state = Some(state);
+            sleep(interval.min(self.timeout - stalled));
+            interval = (interval * self.multiplier).min(self.max_interval);
+        }
+    }
+
+    /// Poll `f` until it returns true.
+    pub fn wait_until<F>(&self, mut f: F) -> Result<(), WaitTimeout<()>>
+    where
+        F: FnMut() -> bool,
+    {
+        self.wait_for(|| if f() { Ok(()) } else { Err(()) })
+            .map_err(|err| WaitTimeout {
+                last_state: None,
+                ..err
+            })
+    }
+}